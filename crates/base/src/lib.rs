@@ -1,4 +1,7 @@
 pub mod serde;
+pub mod wiki;
+
+pub use wiki::Template;
 
 pub type FnvHashMap<K, V> = std::collections::HashMap<K, V, fnv::FnvBuildHasher>;
 pub type FnvIndexMap<K, V> = indexmap::IndexMap<K, V, fnv::FnvBuildHasher>;
@@ -24,6 +27,12 @@ pub trait Name {
 
 pub trait Wiki {
     fn wiki(&self) -> std::borrow::Cow<'static, str>;
+
+    /// 结构化的模板，方便调用方检查、修改个别参数后再输出
+    /// 页面不止一个模板时为开头的主模板，还没有迁移到 Template 的实现返回 None
+    fn wiki_template(&self) -> Option<Template> {
+        None
+    }
 }
 
 impl<L: Name, R: Name> Name for either::Either<L, R> {
@@ -0,0 +1,601 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::FnvIndexMap;
+
+/// MediaWiki 模板调用，如 {{敌人|名称=...|弱点=...}}
+/// 参数按插入顺序保存和输出，匿名参数以 "1"、"2" 等数字为键
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Template {
+    pub name: String,
+    pub params: FnvIndexMap<String, String>,
+}
+
+impl Template {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            params: FnvIndexMap::default(),
+        }
+    }
+
+    /// 插入参数，已存在时覆盖其值但保留原来的位置，返回旧值
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        self.params.insert(key.into(), value.into())
+    }
+
+    /// 向已有参数值末尾追加内容，参数不存在时插入
+    pub fn push_str(&mut self, key: impl Into<String>, value: &str) {
+        self.params.entry(key.into()).or_default().push_str(value);
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.params.get(key).map(String::as_str)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut String> {
+        self.params.get_mut(key)
+    }
+
+    /// 删除参数，后续参数顺序不变
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.params.shift_remove(key)
+    }
+
+    /// 解析单个模板调用，文本首尾除空白外不能有其他内容
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let end = text.strip_prefix("{{").and_then(|_| find_close(text, 2))?;
+        if end != text.len() {
+            return None;
+        }
+        Some(parse_inner(&text[2..end - 2]).0)
+    }
+
+    /// 以自身（新生成的模板）为准合并页面上已有的模板
+    /// 生成的值为空时保留已有的值，生成的模板中没有的参数追加在末尾
+    /// 两边都有值且不同时以生成的为准，并返回冲突
+    pub fn merge(&mut self, existing: &Template) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+        for (key, value) in &existing.params {
+            let Some(generated) = self.params.get_mut(key) else {
+                self.params.insert(key.clone(), value.clone());
+                continue;
+            };
+            let existing_value = normalize(value);
+            if existing_value.is_empty() {
+                continue;
+            }
+            let generated_value = normalize(generated);
+            if generated_value.is_empty() {
+                *generated = value.clone();
+            } else if generated_value != existing_value {
+                conflicts.push(Conflict {
+                    template: self.name.clone(),
+                    key: key.clone(),
+                    existing: value.clone(),
+                    generated: generated.clone(),
+                });
+            }
+        }
+        conflicts
+    }
+}
+
+/// 从 1 开始连续的数字参数输出为匿名参数，此后的参数均输出为具名参数
+/// 只有匿名参数时输出在同一行，如 {{颜色|描述|文本}}
+/// 存在具名参数时每个参数一行，如 {{敌人\n|名称=...\n}}
+impl std::fmt::Display for Template {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("{{")?;
+        f.write_str(&self.name)?;
+        let mut position = 1;
+        let mut named = false;
+        for (key, value) in &self.params {
+            if !named && *key == position.to_string() {
+                f.write_str("|")?;
+                f.write_str(value)?;
+                position += 1;
+                continue;
+            }
+            named = true;
+            f.write_str("\n|")?;
+            f.write_str(key)?;
+            f.write_str("=")?;
+            f.write_str(value)?;
+        }
+        if named {
+            f.write_str("\n")?;
+        }
+        f.write_str("}}")
+    }
+}
+
+impl From<Template> for std::borrow::Cow<'static, str> {
+    fn from(template: Template) -> Self {
+        Self::Owned(template.to_string())
+    }
+}
+
+/// 合并时两边都有值且不一致的参数
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    pub template: String,
+    pub key: String,
+    /// 页面上原有的值，合并后被丢弃
+    pub existing: String,
+    /// 新生成的值，合并后保留
+    pub generated: String,
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{{{{}}}}} 参数 {}：{:?} => {:?}",
+            self.template, self.key, self.existing, self.generated
+        )
+    }
+}
+
+/// 页面合并结果
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Merged {
+    pub text: String,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// 把新生成的页面合并进已有页面
+///
+/// 以已有页面为底，其中的模板按名称和出现次序与生成的模板一一对应，逐个参数合并
+/// 模板外的内容（编辑手写的正文、分类等）原样保留，已有页面中找不到的模板追加在末尾
+/// 模板内只替换值有变化的参数，没变的参数连同其中的空白、注释和排版原样保留
+/// 已有页面中没有任何模板时（比如新建页面），直接使用生成的页面
+///
+/// ```
+/// let existing = "{{敌人\n|名称=虚卒·掠夺者\n|别称=小兵<!-- 编辑手填 -->\n|韧性=10\n}}\n[[分类:敌人]]";
+/// let generated = "{{敌人\n|名称=虚卒·掠夺者\n|别称=\n|韧性=20\n}}";
+/// let merged = base::wiki::merge(existing, generated);
+/// assert_eq!(
+///     merged.text,
+///     "{{敌人\n|名称=虚卒·掠夺者\n|别称=小兵<!-- 编辑手填 -->\n|韧性=20\n}}\n[[分类:敌人]]"
+/// );
+/// assert_eq!(merged.conflicts.len(), 1);
+/// assert_eq!(merged.conflicts[0].key, "韧性");
+/// ```
+pub fn merge(existing: &str, generated: &str) -> Merged {
+    let existing_templates = scan_templates(existing);
+    if existing_templates.is_empty() {
+        return Merged {
+            text: generated.to_string(),
+            conflicts: Vec::new(),
+        };
+    }
+    let mut replacements = Vec::new();
+    let mut appended = String::new();
+    let mut conflicts = Vec::new();
+    let mut occurrences = FnvIndexMap::<String, usize>::default();
+    for (_, mut template, _) in scan_templates(generated) {
+        let name = normalize_name(&template.name);
+        let occurrence = occurrences.entry(name.clone()).or_default();
+        let matched = existing_templates
+            .iter()
+            .filter(|(_, existing, _)| normalize_name(&existing.name) == name)
+            .nth(*occurrence);
+        *occurrence += 1;
+        let Some((range, existing_template, spans)) = matched else {
+            appended.push('\n');
+            appended.push_str(&template.to_string());
+            continue;
+        };
+        conflicts.extend(template.merge(existing_template));
+        // 只替换值有变化的参数，其余参数连同原有的空白、注释、排版原样保留
+        let offset = range.start + 2;
+        let mut inserted = String::new();
+        for (key, value) in &template.params {
+            match spans.get(key) {
+                Some(span) => {
+                    let span = offset + span.start..offset + span.end;
+                    if normalize(&existing[span.clone()]) != normalize(value) {
+                        replacements.push((span, value.clone()));
+                    }
+                }
+                None => {
+                    inserted.push_str("\n|");
+                    inserted.push_str(key);
+                    inserted.push('=');
+                    inserted.push_str(value);
+                }
+            }
+        }
+        if !inserted.is_empty() {
+            // 新参数加在最后一个参数之后，没有参数时加在模板名之后
+            let position =
+                (spans.values().map(|span| offset + span.end).max()).unwrap_or(range.end - 2);
+            replacements.push((position..position, inserted));
+        }
+    }
+    replacements.sort_by_key(|(range, _)| range.start);
+    let mut text = String::with_capacity(existing.len() + appended.len());
+    let mut last = 0;
+    for (range, replacement) in replacements {
+        text.push_str(&existing[last..range.start]);
+        text.push_str(&replacement);
+        last = range.end;
+    }
+    text.push_str(&existing[last..]);
+    text.push_str(&appended);
+    Merged { text, conflicts }
+}
+
+/// 解析文本中所有最外层的模板调用，返回每个模板在文本中的字节范围
+/// 嵌套在参数值里的模板不会单独返回，注释、`<nowiki>` 和 `<pre>` 中的模板会被忽略
+pub fn parse_templates(text: &str) -> Vec<(Range<usize>, Template)> {
+    scan_templates(text)
+        .into_iter()
+        .map(|(range, template, _)| (range, template))
+        .collect()
+}
+
+/// 同 parse_templates，另外返回各参数值相对于 {{ 之后的字节范围
+fn scan_templates(text: &str) -> Vec<(Range<usize>, Template, ParamSpans)> {
+    let mut templates = Vec::new();
+    let mut index = 0;
+    while index < text.len() {
+        let rest = &text.as_bytes()[index..];
+        if let Some(end) = verbatim_end(text, index) {
+            index = end;
+        } else if rest.starts_with(b"{{") {
+            let Some(end) = find_close(text, index + 2) else {
+                break;
+            };
+            let (template, spans) = parse_inner(&text[index + 2..end - 2]);
+            templates.push((index..end, template, spans));
+            index = end;
+        } else {
+            index += 1;
+        }
+    }
+    templates
+}
+
+/// 去掉注释
+pub fn strip_comments(text: &str) -> Cow<'_, str> {
+    if !text.contains("<!--") {
+        return Cow::Borrowed(text);
+    }
+    let mut stripped = String::with_capacity(text.len());
+    let mut index = 0;
+    while let Some(start) = text[index..].find("<!--") {
+        stripped.push_str(&text[index..index + start]);
+        index = comment_end(text, index + start);
+    }
+    stripped.push_str(&text[index..]);
+    Cow::Owned(stripped)
+}
+
+/// 比较参数值时忽略注释和首尾空白
+fn normalize(value: &str) -> String {
+    strip_comments(value).trim().to_string()
+}
+
+/// MediaWiki 中模板名的下划线等同于空格
+fn normalize_name(name: &str) -> String {
+    name.trim().replace('_', " ")
+}
+
+/// 注释结束位置，注释没有闭合时一直到文本末尾
+fn comment_end(text: &str, start: usize) -> usize {
+    text[start + 4..]
+        .find("-->")
+        .map(|end| start + 4 + end + 3)
+        .unwrap_or(text.len())
+}
+
+/// 注释、`<nowiki>` 和 `<pre>` 中的内容不作为 wikitext 解析
+/// 位于这些内容开头时返回其结束位置，没有闭合时一直到文本末尾
+fn verbatim_end(text: &str, index: usize) -> Option<usize> {
+    let rest = &text.as_bytes()[index..];
+    if rest.starts_with(b"<!--") {
+        return Some(comment_end(text, index));
+    }
+    if rest.starts_with(b"<nowiki/>") {
+        return Some(index + 9);
+    }
+    let close: &[u8] = if rest.starts_with(b"<nowiki>") {
+        b"</nowiki>"
+    } else if rest.starts_with(b"<pre>") || rest.starts_with(b"<pre ") {
+        b"</pre>"
+    } else {
+        return None;
+    };
+    let end = rest
+        .windows(close.len())
+        .position(|window| window == close)
+        .map(|end| index + end + close.len())
+        .unwrap_or(text.len());
+    Some(end)
+}
+
+/// 从 {{ 之后开始查找与之配对的 }}，返回 }} 之后的位置
+fn find_close(text: &str, start: usize) -> Option<usize> {
+    let mut depth = 1;
+    let mut tables = 0usize;
+    let mut index = start;
+    while index < text.len() {
+        let rest = &text.as_bytes()[index..];
+        if let Some(end) = verbatim_end(text, index) {
+            index = end;
+        } else if rest.starts_with(b"{{") {
+            depth += 1;
+            index += 2;
+        } else if rest.starts_with(b"{|") {
+            tables += 1;
+            index += 2;
+        } else if tables > 0 && rest.starts_with(b"|}") {
+            tables -= 1;
+            index += 2;
+        } else if rest.starts_with(b"}}") {
+            depth -= 1;
+            index += 2;
+            if depth == 0 {
+                return Some(index);
+            }
+        } else {
+            index += 1;
+        }
+    }
+    None
+}
+
+/// 找出不在嵌套模板、内部链接、表格、注释、`<nowiki>` 和 `<pre>` 中的某个字符的所有位置
+fn top_level(text: &str, target: u8) -> Vec<usize> {
+    let mut positions = Vec::new();
+    let mut depth = 0usize;
+    let mut tables = 0usize;
+    let mut index = 0;
+    while index < text.len() {
+        let rest = &text.as_bytes()[index..];
+        if let Some(end) = verbatim_end(text, index) {
+            index = end;
+        } else if rest.starts_with(b"{{") || rest.starts_with(b"[[") {
+            depth += 1;
+            index += 2;
+        } else if rest.starts_with(b"{|") {
+            // 表格的单元格也用 | 分隔，表格内的 | 和 = 都不是模板参数的分隔符
+            tables += 1;
+            index += 2;
+        } else if tables > 0 && rest.starts_with(b"|}") {
+            tables -= 1;
+            index += 2;
+        } else if rest.starts_with(b"}}") || rest.starts_with(b"]]") {
+            depth = depth.saturating_sub(1);
+            index += 2;
+        } else {
+            if depth == 0 && tables == 0 && text.as_bytes()[index] == target {
+                positions.push(index);
+            }
+            index += 1;
+        }
+    }
+    positions
+}
+
+/// 模板各参数值在 {{ 和 }} 之间内容中的字节范围，键同 Template::params
+type ParamSpans = FnvIndexMap<String, Range<usize>>;
+
+/// 解析 {{ 和 }} 之间的内容，同时返回各参数值的字节范围
+/// 具名参数的键去掉首尾空白，值只去掉行尾空白和开头的空格，以保留开头换行的多行值
+/// 匿名参数原样保留
+fn parse_inner(inner: &str) -> (Template, ParamSpans) {
+    let mut segments = Vec::new();
+    let mut last = 0;
+    for position in top_level(inner, b'|') {
+        segments.push(last..position);
+        last = position + 1;
+    }
+    segments.push(last..inner.len());
+    let mut template = Template::new(strip_comments(&inner[segments[0].clone()]).trim());
+    let mut spans = ParamSpans::default();
+    let mut position = 1;
+    for range in &segments[1..] {
+        let segment = &inner[range.clone()];
+        match top_level(segment, b'=').first() {
+            Some(&equal) => {
+                let key = strip_comments(&segment[..equal]).trim().to_string();
+                let value = segment[equal + 1..].trim_end();
+                let trimmed = value.trim_start_matches([' ', '\t']);
+                let start = range.start + equal + 1 + value.len() - trimmed.len();
+                spans.insert(key.clone(), start..start + trimmed.len());
+                template.insert(key, trimmed);
+            }
+            None => {
+                spans.insert(position.to_string(), range.clone());
+                template.insert(position.to_string(), segment);
+                position += 1;
+            }
+        }
+    }
+    (template, spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(Template::new("分隔线").to_string(), "{{分隔线}}");
+        let mut template = Template::new("敌人");
+        template.insert("名称", "虚卒·掠夺者");
+        template.insert("韧性", "10");
+        assert_eq!(
+            template.to_string(),
+            "{{敌人\n|名称=虚卒·掠夺者\n|韧性=10\n}}"
+        );
+    }
+
+    #[test]
+    fn positional_and_named() {
+        // 只有匿名参数时输出在同一行
+        let mut template = Template::new("颜色");
+        template.insert("1", "描述");
+        template.insert("2", "文本");
+        assert_eq!(template.to_string(), "{{颜色|描述|文本}}");
+        // 匿名参数之后出现具名参数，之后的数字参数也按具名参数输出
+        template.insert("名称", "值");
+        template.insert("3", "第三个");
+        assert_eq!(
+            template.to_string(),
+            "{{颜色|描述|文本\n|名称=值\n|3=第三个\n}}"
+        );
+        // 数字不从 1 开始时不能省略键
+        let mut template = Template::new("颜色");
+        template.insert("2", "文本");
+        assert_eq!(template.to_string(), "{{颜色\n|2=文本\n}}");
+    }
+
+    #[test]
+    fn insert_and_remove_order() {
+        let mut template = Template::new("书籍");
+        template.insert("书籍名", "一");
+        template.insert("卷数", "1");
+        template.insert("描述", "");
+        // 覆盖已有参数时保留原来的位置
+        assert_eq!(template.insert("书籍名", "二").as_deref(), Some("一"));
+        template.push_str("描述", "追加");
+        template.push_str("所属", "雅利洛-VI");
+        assert_eq!(
+            template.params.keys().collect::<Vec<_>>(),
+            ["书籍名", "卷数", "描述", "所属"]
+        );
+        // 删除参数后其余参数顺序不变
+        assert_eq!(template.remove("卷数").as_deref(), Some("1"));
+        assert_eq!(template.remove("卷数"), None);
+        assert_eq!(
+            template.to_string(),
+            "{{书籍\n|书籍名=二\n|描述=追加\n|所属=雅利洛-VI\n}}"
+        );
+    }
+
+    #[test]
+    fn nested_templates_and_links() {
+        let template = Template::parse(
+            "{{敌人|{{颜色|描述|文本}}|名称=[[虚卒·掠夺者|掠夺者]]|效果={{图标|a=b}}}}",
+        )
+        .unwrap();
+        assert_eq!(template.name, "敌人");
+        assert_eq!(template.get("1"), Some("{{颜色|描述|文本}}"));
+        assert_eq!(template.get("名称"), Some("[[虚卒·掠夺者|掠夺者]]"));
+        assert_eq!(template.get("效果"), Some("{{图标|a=b}}"));
+        // 参数值中嵌套的模板不会单独返回
+        let templates = parse_templates("开头{{a|{{b}}}}中间{{c}}");
+        let names = templates.iter().map(|(_, template)| template.name.as_str());
+        assert_eq!(names.collect::<Vec<_>>(), ["a", "c"]);
+        assert_eq!(templates[0].0, 6..17);
+    }
+
+    #[test]
+    fn tables() {
+        let text = "{{祝福\n|效果={| class=\"wikitable\"\n! 等级\n|-\n| 1\n| 2\n|}\n|稀有度=3\n}}";
+        let template = Template::parse(text).unwrap();
+        assert_eq!(
+            template.params.keys().collect::<Vec<_>>(),
+            ["效果", "稀有度"]
+        );
+        assert_eq!(
+            template.get("效果"),
+            Some("{| class=\"wikitable\"\n! 等级\n|-\n| 1\n| 2\n|}")
+        );
+        assert_eq!(template.get("稀有度"), Some("3"));
+        // 表格结束紧跟模板结束
+        let template = Template::parse("{{祝福|效果={|\n| 1\n|}}}").unwrap();
+        assert_eq!(template.get("效果"), Some("{|\n| 1\n|}"));
+    }
+
+    #[test]
+    fn comments_nowiki_and_pre() {
+        let template = Template::parse(
+            "{{敌人\n|名称=掠夺者<!-- | 不是分隔符 \
+             -->\n|代码=<nowiki>{{a|b}}</nowiki>\n|示例=<pre>x|y=z</pre>\n}}",
+        )
+        .unwrap();
+        assert_eq!(
+            template.params.keys().collect::<Vec<_>>(),
+            ["名称", "代码", "示例"]
+        );
+        assert_eq!(template.get("名称"), Some("掠夺者<!-- | 不是分隔符 -->"));
+        assert_eq!(template.get("代码"), Some("<nowiki>{{a|b}}</nowiki>"));
+        assert_eq!(template.get("示例"), Some("<pre>x|y=z</pre>"));
+        // 注释和 nowiki 中的模板不会被解析
+        let templates = parse_templates("<!-- {{a}} --><nowiki>{{b}}</nowiki>{{c}}");
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].1.name, "c");
+    }
+
+    #[test]
+    fn anonymous_params() {
+        // 匿名参数原样保留空白，具名参数只去掉首尾空白
+        let template = Template::parse("{{颜色| 描述 |键 = 值 |文本}}").unwrap();
+        assert_eq!(template.get("1"), Some(" 描述 "));
+        assert_eq!(template.get("键"), Some("值"));
+        assert_eq!(template.get("2"), Some("文本"));
+        let merged = merge("{{颜色|描述|文本}}", "{{颜色|描述|新文本}}");
+        assert_eq!(merged.text, "{{颜色|描述|新文本}}");
+        assert_eq!(merged.conflicts.len(), 1);
+        assert_eq!(merged.conflicts[0].key, "2");
+    }
+
+    #[test]
+    fn merge_conflicts() {
+        let existing = "前言\n{{敌人\n|名称 = 掠夺者 <!-- 保留 \
+                        -->\n|韧性=10\n|备注=手填\n}}\n{{敌人\n|名称=二号\n}}";
+        let generated =
+            "{{敌人\n|名称=掠夺者\n|韧性=20\n|弱点=火\n}}\n{{敌人\n|名称=二号\n}}\n{{分隔线}}";
+        let merged = merge(existing, generated);
+        assert_eq!(
+            merged.text,
+            "前言\n{{敌人\n|名称 = 掠夺者 <!-- 保留 \
+             -->\n|韧性=20\n|备注=手填\n|弱点=火\n}}\n{{敌人\n|名称=二号\n}}\n{{分隔线}}"
+        );
+        assert_eq!(
+            merged.conflicts,
+            [Conflict {
+                template: "敌人".to_string(),
+                key: "韧性".to_string(),
+                existing: "10".to_string(),
+                generated: "20".to_string(),
+            }]
+        );
+        // 没有参数的模板，新参数加在模板名之后
+        let merged = merge("{{分隔线}}", "{{分隔线|1=粗}}");
+        assert_eq!(merged.text, "{{分隔线\n|1=粗}}");
+    }
+
+    #[test]
+    fn merge_table_keeps_formatting() {
+        let existing = "{{祝福\n|效果={| class=\"wikitable\"\n! 等级\n|-\n| 1\n|}\n|稀有度=3\n}}";
+        let generated = "{{祝福\n|效果=\n|稀有度=4\n}}";
+        let merged = merge(existing, generated);
+        assert_eq!(
+            merged.text,
+            "{{祝福\n|效果={| class=\"wikitable\"\n! 等级\n|-\n| 1\n|}\n|稀有度=4\n}}"
+        );
+        assert_eq!(merged.conflicts.len(), 1);
+    }
+
+    #[test]
+    fn merge_idempotent() {
+        let pages = [
+            "{{敌人\n|名称=虚卒·掠夺者\n|别称=小兵<!-- 编辑手填 -->\n|韧性=10\n}}\n[[分类:敌人]]",
+            "{{祝福\n|效果={| class=\"wikitable\"\n! 等级\n|-\n| 1\n…|}\n|稀有度=3\n}}",
+            "{{颜色| 描述 |文本}}{{a|{{b|c=d}}|e = f }}<nowiki>{{g}}</nowiki>",
+            "{{分隔线}}\n\n正文 {{空白|}} 结尾",
+        ];
+        for page in pages {
+            let merged = merge(page, page);
+            assert_eq!(merged.text, page);
+            assert!(merged.conflicts.is_empty());
+        }
+    }
+}
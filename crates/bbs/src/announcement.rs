@@ -0,0 +1,466 @@
+//! 从官方公告中提取版本更新、维护和活动的时间
+//!
+//! 公告的时间都写在正文里，格式大致有这几种（都是服务器时间，国服为 UTC+8）：
+//! - `2024/01/17 12:00:00 - 2024/02/05 14:59:59`
+//! - `2024/01/01 06:00 - 11:00`，结束时间和开始时间同一天
+//! - `1.6版本更新后 - 2024/01/17 14:59:59`，开始时间要从对应版本的更新维护时间推出
+//! - `1月17日12:00`，省略年份时按发帖时间推断
+//!
+//! 得到的 [`Timeline`] 可以和游戏数据中的 ScheduleData、ScheduleDataGlobal 对照，
+//! 也可以给出每个版本的上线时间
+
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveTime, TimeDelta, Utc};
+
+use crate::model::user_post::UserPost;
+
+/// 国服的服务器时间
+const SERVER_OFFSET: FixedOffset = FixedOffset::east_opt(8 * 3600).unwrap();
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum AnnouncementKind {
+    /// 版本更新说明
+    VersionNotes,
+    /// 停服维护、更新维护预告
+    Maintenance,
+    /// 活动、跃迁等限时内容
+    Event,
+    Other,
+}
+
+/// 按标题分类
+pub fn classify(subject: &str) -> AnnouncementKind {
+    let contains = |keywords: &[&str]| keywords.iter().any(|keyword| subject.contains(keyword));
+    if contains(&["维护"]) {
+        AnnouncementKind::Maintenance
+    } else if contains(&["更新说明", "版本更新"]) {
+        AnnouncementKind::VersionNotes
+    } else if contains(&["活动", "跃迁", "限时", "开启"]) {
+        AnnouncementKind::Event
+    } else {
+        AnnouncementKind::Other
+    }
+}
+
+/// 开始时间
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Begin {
+    Time(DateTime<FixedOffset>),
+    /// 某个版本更新后，比如 `1.6版本更新后`
+    AfterVersionUpdate(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct TimeRange {
+    pub begin: Begin,
+    /// 只写了一个时间点时为 None
+    pub end: Option<DateTime<FixedOffset>>,
+    /// 时间所在的那一行
+    pub line: String,
+    /// 往前最近的一行不含时间的短文本，一般是小标题或者活动名
+    pub heading: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Announcement {
+    pub post_id: u32,
+    pub subject: String,
+    pub kind: AnnouncementKind,
+    /// 标题或正文中出现的第一个版本号，比如 `2.0`
+    pub version: Option<String>,
+    pub published: DateTime<Utc>,
+    pub ranges: Vec<TimeRange>,
+}
+
+static DATE_TIME: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(concat!(
+        r"(?P<version>\d+\.\d+)\s*版本更新后",
+        r"|(?:(?P<year>\d{4})\s*[/\-年]\s*)?(?P<month>\d{1,2})\s*[/\-月]\s*(?P<day>\d{1,2})\s*日?",
+        r"\s*(?P<hour>\d{1,2})\s*[:：]\s*(?P<minute>\d{2})(?:\s*[:：]\s*(?P<second>\d{2}))?",
+        r"|(?P<time>(?P<only_hour>\d{1,2})\s*[:：]\s*(?P<only_minute>\d{2})(?:\s*[:：]\s*(?P<only_second>\d{2}))?)",
+    ))
+    .unwrap()
+});
+
+static VERSION: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"(\d+\.\d+)\s*版本").unwrap());
+
+/// 连接开始和结束时间的文字
+fn is_separator(text: &str) -> bool {
+    matches!(
+        text.trim(),
+        "-" | "~" | "～" | "—" | "–" | "——" | "至" | "到"
+    )
+}
+
+enum Token {
+    Version(String),
+    DateTime(DateTime<FixedOffset>),
+    Time(NaiveTime),
+}
+
+fn time(hour: &str, minute: &str, second: Option<regex::Match>) -> Option<NaiveTime> {
+    let second = second.map_or(Some(0), |second| second.as_str().parse().ok())?;
+    NaiveTime::from_hms_opt(hour.parse().ok()?, minute.parse().ok()?, second)
+}
+
+/// 省略年份时取离发帖时间最近的一年，公告提到的时间一般在发帖前后几个月内
+fn infer_year(month: u32, day: u32, published: DateTime<FixedOffset>) -> Option<NaiveDate> {
+    let year = published.year();
+    (year - 1..=year + 1)
+        .filter_map(|year| NaiveDate::from_ymd_opt(year, month, day))
+        .min_by_key(|date| (*date - published.date_naive()).num_days().abs())
+}
+
+fn tokenize(line: &str, published: DateTime<FixedOffset>) -> Vec<(Token, usize, usize)> {
+    let mut tokens = Vec::new();
+    for captures in DATE_TIME.captures_iter(line) {
+        let whole = captures.get(0).unwrap();
+        let token = if let Some(version) = captures.name("version") {
+            Some(Token::Version(version.as_str().to_string()))
+        } else if captures.name("time").is_some() {
+            let time = time(
+                &captures["only_hour"],
+                &captures["only_minute"],
+                captures.name("only_second"),
+            );
+            time.map(Token::Time)
+        } else {
+            let month = captures["month"].parse().unwrap_or(0);
+            let day = captures["day"].parse().unwrap_or(0);
+            let date = match captures.name("year") {
+                Some(year) => {
+                    NaiveDate::from_ymd_opt(year.as_str().parse().unwrap_or(0), month, day)
+                }
+                None => infer_year(month, day, published),
+            };
+            let time = time(
+                &captures["hour"],
+                &captures["minute"],
+                captures.name("second"),
+            );
+            (date.zip(time))
+                .and_then(|(date, time)| {
+                    date.and_time(time)
+                        .and_local_timezone(SERVER_OFFSET)
+                        .single()
+                })
+                .map(Token::DateTime)
+        };
+        if let Some(token) = token {
+            tokens.push((token, whole.start(), whole.end()));
+        }
+    }
+    tokens
+}
+
+/// 解析一行中的时间，published 用于推断省略的年份
+pub fn parse_line(
+    line: &str,
+    published: DateTime<Utc>,
+) -> Vec<(Begin, Option<DateTime<FixedOffset>>)> {
+    let published = published.with_timezone(&SERVER_OFFSET);
+    let tokens = tokenize(line, published);
+    let mut ranges = Vec::new();
+    let mut index = 0;
+    while index < tokens.len() {
+        let (token, _, end) = &tokens[index];
+        let begin = match token {
+            Token::Version(version) => Begin::AfterVersionUpdate(version.clone()),
+            Token::DateTime(time) => Begin::Time(*time),
+            Token::Time(_) => {
+                index += 1;
+                continue;
+            }
+        };
+        let next =
+            (tokens.get(index + 1)).filter(|(_, start, _)| is_separator(&line[*end..*start]));
+        let until = match (next.map(|(token, _, _)| token), &begin) {
+            (Some(Token::DateTime(time)), _) => Some(*time),
+            // 只写了时刻的结束时间和开始时间同一天
+            (Some(Token::Time(time)), Begin::Time(begin)) => (begin.date_naive().and_time(*time))
+                .and_local_timezone(SERVER_OFFSET)
+                .single(),
+            _ => None,
+        };
+        match (&begin, until) {
+            // 单独的「x.x版本更新后」没有意义
+            (Begin::AfterVersionUpdate(_), None) => (),
+            _ => ranges.push((begin, until)),
+        }
+        index += if until.is_some() { 2 } else { 1 };
+    }
+    ranges
+}
+
+impl Announcement {
+    pub fn from_post(post: &UserPost) -> Self {
+        let text = match &post.post.structured_content {
+            Some(content) => crate::render::plain_text(content),
+            None => post.post.content.clone(),
+        };
+        let published = post.post.created_at;
+        let mut ranges = Vec::new();
+        let mut heading = None;
+        let lines = (text.lines())
+            .map(str::trim)
+            .filter(|line| !line.is_empty());
+        for line in lines {
+            let parsed = parse_line(line, published);
+            if parsed.is_empty() {
+                if line.chars().count() <= 40 {
+                    heading = Some(line.to_string());
+                }
+                continue;
+            }
+            for (begin, end) in parsed {
+                ranges.push(TimeRange {
+                    begin,
+                    end,
+                    line: line.to_string(),
+                    heading: heading.clone(),
+                });
+            }
+        }
+        let version = VERSION
+            .captures(&post.post.subject)
+            .or_else(|| VERSION.captures(&text))
+            .map(|captures| captures[1].to_string());
+        Self {
+            post_id: post.post.post_id,
+            subject: post.post.subject.clone(),
+            kind: classify(&post.post.subject),
+            version,
+            published,
+            ranges,
+        }
+    }
+}
+
+/// 时间线上的一项，开始时间已经换算成具体的时间
+#[derive(Clone, Debug)]
+pub struct TimelineEvent<'a> {
+    pub announcement: &'a Announcement,
+    pub range: &'a TimeRange,
+    pub begin: DateTime<FixedOffset>,
+    pub end: Option<DateTime<FixedOffset>>,
+}
+
+/// 时间线上的事件和游戏数据中的某个 schedule 对上了
+#[derive(Clone, Debug)]
+pub struct ScheduleMatch<'a> {
+    pub schedule_id: u32,
+    pub event: TimelineEvent<'a>,
+    /// 公告中的时间减去 schedule 中的时间
+    pub begin_offset: TimeDelta,
+    pub end_offset: Option<TimeDelta>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Timeline {
+    /// 按发帖时间从旧到新
+    pub announcements: Vec<Announcement>,
+}
+
+impl Timeline {
+    pub fn new(posts: impl IntoIterator<Item = impl std::borrow::Borrow<UserPost>>) -> Self {
+        let mut announcements = (posts.into_iter())
+            .map(|post| Announcement::from_post(post.borrow()))
+            .collect::<Vec<_>>();
+        announcements.sort_by_key(|announcement| (announcement.published, announcement.post_id));
+        Self { announcements }
+    }
+
+    /// 每个版本的更新时间，取版本更新说明或者维护公告中第一个时间段的开始时间
+    ///
+    /// 同一个版本有多篇公告时以最后发布的为准，延期之类的更正会覆盖之前的公告
+    pub fn version_releases(&self) -> BTreeMap<String, DateTime<FixedOffset>> {
+        let mut releases = BTreeMap::new();
+        for announcement in &self.announcements {
+            if !matches!(
+                announcement.kind,
+                AnnouncementKind::VersionNotes | AnnouncementKind::Maintenance
+            ) {
+                continue;
+            }
+            let Some(version) = &announcement.version else {
+                continue;
+            };
+            let begin = announcement
+                .ranges
+                .iter()
+                .find_map(|range| match range.begin {
+                    Begin::Time(begin) => Some(begin),
+                    Begin::AfterVersionUpdate(_) => None,
+                });
+            if let Some(begin) = begin {
+                releases.insert(version.clone(), begin);
+            }
+        }
+        releases
+    }
+
+    /// 全部时间段，按开始时间排序，开始时间无法换算的会被忽略
+    pub fn events(&self) -> Vec<TimelineEvent<'_>> {
+        let releases = self.version_releases();
+        let mut events = Vec::new();
+        for announcement in &self.announcements {
+            for range in &announcement.ranges {
+                let begin = match &range.begin {
+                    Begin::Time(begin) => Some(*begin),
+                    Begin::AfterVersionUpdate(version) => releases.get(version).copied(),
+                };
+                if let Some(begin) = begin {
+                    events.push(TimelineEvent {
+                        announcement,
+                        range,
+                        begin,
+                        end: range.end,
+                    });
+                }
+            }
+        }
+        events.sort_by_key(|event| event.begin);
+        events
+    }
+
+    /// 和游戏数据中的 schedule 对照，schedules 为 `(ID, 开始时间, 结束时间)`
+    ///
+    /// 开始时间相差不超过 tolerance，并且公告中有结束时间时结束时间也相差不超过 tolerance 才算对上
+    /// 游戏数据的结束时间常为 `14:59:59`，公告可能写成 `14:59`，tolerance 一般取一分钟
+    pub fn cross_check(
+        &self,
+        schedules: impl IntoIterator<Item = (u32, DateTime<FixedOffset>, DateTime<FixedOffset>)>,
+        tolerance: TimeDelta,
+    ) -> Vec<ScheduleMatch<'_>> {
+        let events = self.events();
+        let mut matches = Vec::new();
+        for (schedule_id, begin, end) in schedules {
+            for event in &events {
+                let begin_offset = event.begin - begin;
+                let end_offset = event.end.map(|event_end| event_end - end);
+                let within = |offset: TimeDelta| offset.abs() <= tolerance;
+                if within(begin_offset) && end_offset.is_none_or(within) {
+                    matches.push(ScheduleMatch {
+                        schedule_id,
+                        event: event.clone(),
+                        begin_offset,
+                        end_offset,
+                    });
+                }
+            }
+        }
+        matches
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<FixedOffset> {
+        let time = chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap();
+        time.and_local_timezone(SERVER_OFFSET).unwrap()
+    }
+
+    fn published() -> DateTime<Utc> {
+        at("2023-12-28 12:00:00").to_utc()
+    }
+
+    #[test]
+    fn classify_subject() {
+        assert_eq!(
+            classify("「多重同调」版本更新说明"),
+            AnnouncementKind::VersionNotes
+        );
+        assert_eq!(
+            classify("「多重同调」版本更新维护预告"),
+            AnnouncementKind::Maintenance
+        );
+        assert_eq!(classify("「星芒战幕」活动说明"), AnnouncementKind::Event);
+        assert_eq!(classify("米游社创作者激励计划"), AnnouncementKind::Other);
+    }
+
+    #[test]
+    fn parse_ranges() {
+        let ranges = parse_line("更新时间：2024/01/01 06:00 - 11:00", published());
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0].0, Begin::Time(at("2024-01-01 06:00:00")));
+        assert_eq!(ranges[0].1, Some(at("2024-01-01 11:00:00")));
+
+        let ranges = parse_line(
+            "活动时间：2024/01/17 12:00:00 ~ 2024/02/05 14:59:59（服务器时间）",
+            published(),
+        );
+        assert_eq!(ranges[0].0, Begin::Time(at("2024-01-17 12:00:00")));
+        assert_eq!(ranges[0].1, Some(at("2024-02-05 14:59:59")));
+
+        let ranges = parse_line("1.6版本更新后 - 2024/01/17 14:59:59", published());
+        assert_eq!(ranges[0].0, Begin::AfterVersionUpdate(String::from("1.6")));
+        assert_eq!(ranges[0].1, Some(at("2024-01-17 14:59:59")));
+
+        // 跨年时省略的年份取离发帖时间最近的一年
+        let ranges = parse_line("将于1月2日10:00开启", published());
+        assert_eq!(ranges, [(Begin::Time(at("2024-01-02 10:00:00")), None)]);
+
+        assert!(parse_line("1.6版本更新后开启", published()).is_empty());
+        assert!(parse_line("每日04:00刷新", published()).is_empty());
+    }
+
+    #[test]
+    fn cross_check_schedule() {
+        let announcement =
+            |post_id, kind, version: &str, ranges: Vec<(Begin, Option<_>)>| Announcement {
+                post_id,
+                subject: String::new(),
+                kind,
+                version: Some(version.to_string()),
+                published: published(),
+                ranges: (ranges.into_iter())
+                    .map(|(begin, end)| TimeRange {
+                        begin,
+                        end,
+                        line: String::new(),
+                        heading: None,
+                    })
+                    .collect(),
+            };
+        let timeline = Timeline {
+            announcements: vec![
+                announcement(
+                    1,
+                    AnnouncementKind::Maintenance,
+                    "2.0",
+                    vec![(
+                        Begin::Time(at("2024-02-06 06:00:00")),
+                        Some(at("2024-02-06 11:00:00")),
+                    )],
+                ),
+                announcement(
+                    2,
+                    AnnouncementKind::Event,
+                    "2.0",
+                    vec![(
+                        Begin::AfterVersionUpdate(String::from("2.0")),
+                        Some(at("2024-02-26 03:59:00")),
+                    )],
+                ),
+            ],
+        };
+        let releases = timeline.version_releases();
+        assert_eq!(releases["2.0"], at("2024-02-06 06:00:00"));
+
+        let schedules = [
+            (1001, at("2024-02-06 06:00:00"), at("2024-02-26 03:59:59")),
+            (1002, at("2024-02-26 04:00:00"), at("2024-03-11 03:59:59")),
+        ];
+        let matches = timeline.cross_check(schedules, TimeDelta::minutes(1));
+        // 维护公告的开始时间对得上，但是结束时间不符
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].schedule_id, 1001);
+        assert_eq!(matches[0].event.announcement.post_id, 2);
+        assert_eq!(matches[0].end_offset, Some(TimeDelta::seconds(-59)));
+    }
+}
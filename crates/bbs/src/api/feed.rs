@@ -0,0 +1,243 @@
+//! 版块、话题和搜索的帖子列表
+
+use std::{
+    async_iter::AsyncIterator,
+    future::Future,
+    pin::{pin, Pin},
+    task::{ready, Context, Poll},
+};
+
+use crate::model::GameId;
+
+const FORUM_PATH: &str = "/post/wapi/getForumPostList";
+const TOPIC_PATH: &str = "/post/wapi/getTopicPostList";
+const SEARCH_PATH: &str = "/post/wapi/searchPosts";
+
+/// 版块帖子的排序方式
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum ForumSort {
+    /// 最新回复
+    #[default]
+    LatestReply,
+    /// 最新发布
+    LatestPost,
+    /// 热门
+    Hot,
+}
+
+/// 话题帖子的排序方式
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum TopicSort {
+    /// 热门
+    Hot,
+    /// 最新
+    #[default]
+    Latest,
+    /// 精华
+    Good,
+}
+
+/// 搜索结果的排序方式
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum SearchSort {
+    /// 综合
+    #[default]
+    Relevance,
+    /// 最新
+    Latest,
+}
+
+pub struct ForumAPI {
+    pub(crate) client: crate::client::Client,
+    pub(crate) game_id: GameId,
+    pub(crate) forum_id: u8,
+    pub(crate) sort: ForumSort,
+    pub(crate) is_good: bool,
+    pub(crate) page_size: u8,
+}
+
+pub struct TopicAPI {
+    pub(crate) client: crate::client::Client,
+    pub(crate) game_id: GameId,
+    pub(crate) topic_id: u32,
+    pub(crate) sort: TopicSort,
+    pub(crate) page_size: u8,
+}
+
+pub struct SearchAPI {
+    pub(crate) client: crate::client::Client,
+    pub(crate) game_id: GameId,
+    pub(crate) keyword: String,
+    pub(crate) sort: SearchSort,
+    pub(crate) page_size: u8,
+}
+
+/// 三个接口的参数合在一起，不需要的字段为 None，不会出现在请求中
+#[derive(Clone, Default, serde::Serialize)]
+struct PostListQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gids: Option<GameId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    forum_id: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    topic_id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    game_id: Option<GameId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keyword: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_good: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_hot: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sort_type: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    list_type: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page_size: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u8>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    last_id: String,
+}
+
+pub struct PostListIter {
+    client: crate::client::Client,
+    path: &'static str,
+    query: PostListQuery,
+
+    is_last: bool,
+    posts: Vec<crate::model::user_post::UserPost>,
+    future: crate::api::ResponseFuture,
+}
+
+impl PostListIter {
+    fn new(client: &crate::client::Client, path: &'static str, query: PostListQuery) -> Self {
+        Self {
+            client: client.clone(),
+            path,
+            is_last: false,
+
+            posts: Vec::new(),
+            future: crate::api::ResponseFuture::new(client, path, &query),
+            query,
+        }
+    }
+}
+
+impl ForumAPI {
+    pub fn sort(mut self, sort: ForumSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// 只看精华帖
+    pub fn good(mut self, is_good: bool) -> Self {
+        self.is_good = is_good;
+        self
+    }
+
+    /// 每页帖子数，默认 20
+    pub fn page_size(mut self, page_size: u8) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    pub fn async_iter(&self) -> PostListIter {
+        let (is_hot, sort_type) = match self.sort {
+            ForumSort::LatestReply => (false, 1),
+            ForumSort::LatestPost => (false, 2),
+            ForumSort::Hot => (true, 1),
+        };
+        let query = PostListQuery {
+            gids: Some(self.game_id),
+            forum_id: Some(self.forum_id),
+            is_good: Some(self.is_good),
+            is_hot: Some(is_hot),
+            sort_type: Some(sort_type),
+            page_size: Some(self.page_size),
+            ..Default::default()
+        };
+        PostListIter::new(&self.client, FORUM_PATH, query)
+    }
+}
+
+impl TopicAPI {
+    pub fn sort(mut self, sort: TopicSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// 每页帖子数，默认 20
+    pub fn page_size(mut self, page_size: u8) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    pub fn async_iter(&self) -> PostListIter {
+        let list_type = match self.sort {
+            TopicSort::Hot => 0,
+            TopicSort::Latest => 1,
+            TopicSort::Good => 2,
+        };
+        let query = PostListQuery {
+            gids: Some(self.game_id),
+            game_id: Some(self.game_id),
+            topic_id: Some(self.topic_id),
+            list_type: Some(list_type),
+            page_size: Some(self.page_size),
+            ..Default::default()
+        };
+        PostListIter::new(&self.client, TOPIC_PATH, query)
+    }
+}
+
+impl SearchAPI {
+    pub fn sort(mut self, sort: SearchSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// 每页帖子数，默认 20
+    pub fn page_size(mut self, page_size: u8) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    pub fn async_iter(&self) -> PostListIter {
+        let sort_type = match self.sort {
+            SearchSort::Relevance => 1,
+            SearchSort::Latest => 2,
+        };
+        let query = PostListQuery {
+            gids: Some(self.game_id),
+            keyword: Some(self.keyword.clone()),
+            sort_type: Some(sort_type),
+            size: Some(self.page_size),
+            ..Default::default()
+        };
+        PostListIter::new(&self.client, SEARCH_PATH, query)
+    }
+}
+
+impl AsyncIterator for PostListIter {
+    type Item = crate::api::Result<crate::model::user_post::UserPost>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(post) = self.posts.pop() {
+            return Poll::Ready(Some(Ok(post)));
+        }
+        if self.is_last {
+            return Poll::Ready(None);
+        }
+        let bytes = ready!(pin!(&mut self.future).poll(cx))?;
+        let data: crate::model::post::PostList =
+            crate::api::parse_response(&self.client, self.path, &bytes)?;
+        self.query.last_id = data.last_id;
+        self.future = crate::api::ResponseFuture::new(&self.client, self.path, &self.query);
+        self.is_last = data.is_last || data.list.is_empty();
+        self.posts = data.list;
+        self.posts.reverse();
+        Poll::Ready(self.posts.pop().map(Ok))
+    }
+}
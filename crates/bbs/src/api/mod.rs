@@ -1,5 +1,15 @@
+pub mod feed;
+pub mod post;
 pub mod user_post;
 
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+pub use feed::{ForumAPI, ForumSort, PostListIter, SearchAPI, SearchSort, TopicAPI, TopicSort};
+pub use post::{PostAPI, ReplyIter, ReplyOrder};
 pub use user_post::UserPostAPI;
 
 #[derive(Debug, thiserror::Error)]
@@ -8,8 +18,52 @@ pub enum Error {
     Network(#[from] reqwest::Error),
     #[error("deserialization error")]
     Deserialization(#[from] serde_json::Error),
+    #[error("fixture error")]
+    Fixture(#[from] std::io::Error),
     #[error("business error: {retcode} {message}")]
     Business { retcode: i32, message: String },
 }
 
-type Result<T> = std::result::Result<T, Error>;
+pub(crate) type Result<T> = std::result::Result<T, Error>;
+
+/// 进行中的请求，用于在 AsyncIterator 中保存
+pub(crate) struct ResponseFuture(Pin<Box<dyn Future<Output = Result<bytes::Bytes>> + Send>>);
+
+impl ResponseFuture {
+    pub(crate) fn new(
+        client: &crate::client::Client,
+        path: &str,
+        query: &impl serde::Serialize,
+    ) -> Self {
+        Self(Box::pin(client.get(path, query)))
+    }
+}
+
+impl Future for ResponseFuture {
+    type Output = Result<bytes::Bytes>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.0.as_mut().poll(cx)
+    }
+}
+
+/// 解析 [`crate::model::Response`]，retcode 不为 0 时返回 [`Error::Business`]
+pub(crate) fn parse_response<T>(
+    client: &crate::client::Client,
+    endpoint: &'static str,
+    bytes: &[u8],
+) -> Result<T>
+where
+    for<'de> T: serde::Deserialize<'de>,
+{
+    // 出错时 data 一般是 null，先检查 retcode 再解析 data
+    let res: crate::model::Response<serde::de::IgnoredAny> = serde_json::from_slice(bytes)?;
+    if res.retcode != 0 {
+        return Err(Error::Business {
+            retcode: res.retcode,
+            message: res.message,
+        });
+    }
+    let res: crate::model::Response<T> = client.deserialize(endpoint, bytes)?;
+    Ok(res.data)
+}
@@ -0,0 +1,136 @@
+use std::{
+    async_iter::AsyncIterator,
+    future::Future,
+    pin::{pin, Pin},
+    task::{ready, Context, Poll},
+};
+
+const POST_FULL_PATH: &str = "/post/wapi/getPostFull";
+const REPLIES_PATH: &str = "/post/wapi/getPostReplies";
+const SUB_REPLIES_PATH: &str = "/post/wapi/getSubReplies";
+
+pub struct PostAPI {
+    pub(crate) client: crate::client::Client,
+    pub(crate) post_id: u32,
+}
+
+/// 评论的排序方式
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum ReplyOrder {
+    /// 热门
+    Hot,
+    /// 按楼层从旧到新
+    #[default]
+    Earliest,
+    /// 按楼层从新到旧
+    Latest,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct ReplyQuery {
+    post_id: u32,
+    size: u8,
+    /// 只有楼中楼有
+    #[serde(skip_serializing_if = "Option::is_none")]
+    floor_id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_hot: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    order_type: Option<u8>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    only_master: bool,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    last_id: String,
+}
+
+/// 楼层或者楼中楼列表，两者的分页方式和返回结构相同
+pub struct ReplyIter {
+    client: crate::client::Client,
+    path: &'static str,
+    query: ReplyQuery,
+
+    is_last: bool,
+    replies: Vec<crate::model::post::Reply>,
+    future: crate::api::ResponseFuture,
+}
+
+impl ReplyIter {
+    fn new(client: &crate::client::Client, path: &'static str, query: ReplyQuery) -> Self {
+        Self {
+            client: client.clone(),
+            path,
+            is_last: false,
+
+            replies: Vec::new(),
+            future: crate::api::ResponseFuture::new(client, path, &query),
+            query,
+        }
+    }
+}
+
+impl PostAPI {
+    /// 帖子详情，包含完整的 structured_content
+    pub async fn full(&self) -> crate::api::Result<crate::model::user_post::UserPost> {
+        let query = [("post_id", self.post_id)];
+        let bytes = self.client.get(POST_FULL_PATH, &query).await?;
+        let data: crate::model::post::PostFull =
+            crate::api::parse_response(&self.client, POST_FULL_PATH, &bytes)?;
+        Ok(data.post)
+    }
+
+    /// 楼层列表，only_master 为 true 时只看楼主
+    pub fn replies(&self, order: ReplyOrder, only_master: bool) -> ReplyIter {
+        let (is_hot, order_type) = match order {
+            ReplyOrder::Hot => (true, None),
+            ReplyOrder::Earliest => (false, Some(1)),
+            ReplyOrder::Latest => (false, Some(2)),
+        };
+        let query = ReplyQuery {
+            post_id: self.post_id,
+            size: 20,
+            floor_id: None,
+            is_hot: Some(is_hot),
+            order_type,
+            only_master,
+            last_id: String::new(),
+        };
+        ReplyIter::new(&self.client, REPLIES_PATH, query)
+    }
+
+    /// 楼层下的全部楼中楼，按时间从旧到新
+    pub fn sub_replies(&self, floor_id: u32) -> ReplyIter {
+        let query = ReplyQuery {
+            post_id: self.post_id,
+            size: 20,
+            floor_id: Some(floor_id),
+            is_hot: None,
+            order_type: None,
+            only_master: false,
+            last_id: String::new(),
+        };
+        ReplyIter::new(&self.client, SUB_REPLIES_PATH, query)
+    }
+}
+
+impl AsyncIterator for ReplyIter {
+    type Item = crate::api::Result<crate::model::post::Reply>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(reply) = self.replies.pop() {
+            return Poll::Ready(Some(Ok(reply)));
+        }
+        if self.is_last {
+            return Poll::Ready(None);
+        }
+        let bytes = ready!(pin!(&mut self.future).poll(cx))?;
+        let data: crate::model::post::ReplyList =
+            crate::api::parse_response(&self.client, self.path, &bytes)?;
+        self.query.last_id = data.last_id;
+        self.future = crate::api::ResponseFuture::new(&self.client, self.path, &self.query);
+        // 最后一页之前也可能返回空列表，此时没有下一页可以继续翻
+        self.is_last = data.is_last || data.list.is_empty();
+        self.replies = data.list;
+        self.replies.reverse();
+        Poll::Ready(self.replies.pop().map(Ok))
+    }
+}
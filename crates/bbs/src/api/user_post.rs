@@ -5,20 +5,22 @@ use std::{
     task::{ready, Context, Poll},
 };
 
-const URL: &str = "https://bbs-api.miyoushe.com/post/wapi/userPost";
+use crate::model::user_post::UserPost;
+
+const PATH: &str = "/post/wapi/userPost";
 
 pub struct UserPostAPI {
-    pub(crate) client: reqwest::Client,
+    pub(crate) client: crate::client::Client,
     pub(crate) user_id: u32,
 }
 
 pub struct UserPostIter {
-    client: reqwest::Client,
+    client: crate::client::Client,
     user_id: u32,
 
     is_last: bool,
     posts: Vec<crate::model::user_post::UserPost>,
-    future: UserPostFuture,
+    future: crate::api::ResponseFuture,
 }
 
 #[derive(serde::Serialize)]
@@ -29,37 +31,88 @@ struct UserPostQuery {
     offset: u32,
 }
 
-struct UserPostFuture {
-    req: Option<Pin<Box<dyn Future<Output = reqwest::Result<reqwest::Response>>>>>,
-    body: Option<Pin<Box<dyn Future<Output = reqwest::Result<bytes::Bytes>>>>>,
+/// 帖子和本地已保存状态的比较结果
+pub(crate) enum Change {
+    /// 和保存的一致，计入连续没有变化的帖子数
+    Unchanged,
+    /// 新增或有变化，需要写入
+    Changed,
+    /// 不需要写入，也不影响停止翻页的判断
+    Ignored,
 }
 
-impl UserPostFuture {
-    fn new(client: &reqwest::Client, query: &UserPostQuery) -> Self {
-        Self {
-            req: Some(Box::pin(client.get(URL).query(&query).send())),
-            body: None,
-        }
-    }
+/// 增量翻页：从最新的帖子开始，连续遇到若干篇没有变化的帖子时停止
+///
+/// 有变化且没有被删除的帖子可以换成 getPostFull 的完整正文，
+/// last_modify_time 以列表中的为准，避免两个接口不一致导致下次更新时误判为有变化
+pub(crate) struct Incremental {
+    client: crate::client::Client,
+    posts: UserPostIter,
+    stop_after_unchanged: usize,
+    full_content: bool,
+    /// 还可以读取的帖子数
+    remaining: usize,
+    unchanged: usize,
 }
 
-impl Future for UserPostFuture {
-    type Output = reqwest::Result<bytes::Bytes>;
+impl Incremental {
+    /// 最多读取的帖子数，包括没有变化的帖子
+    pub(crate) fn limit(mut self, limit: usize) -> Self {
+        self.remaining = limit;
+        self
+    }
 
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        if let Some(req) = self.req.as_mut() {
-            let res = ready!(req.as_mut().poll(cx))?.error_for_status()?;
-            self.body = Some(Box::pin(res.bytes()));
-            self.req = None;
-        }
-        if let Some(body) = self.body.as_mut() {
-            return body.as_mut().poll(cx);
+    /// 下一篇需要写入的帖子和它是否为完整正文，`check` 比较帖子和本地已保存的状态
+    pub(crate) async fn next<E: From<crate::api::Error>>(
+        &mut self,
+        mut check: impl FnMut(&UserPost) -> Result<Change, E>,
+    ) -> Result<Option<(UserPost, bool)>, E> {
+        while self.remaining > 0 {
+            self.remaining -= 1;
+            let next = std::future::poll_fn(|cx| Pin::new(&mut self.posts).poll_next(cx)).await;
+            let Some(post) = next else {
+                return Ok(None);
+            };
+            let mut post = post?;
+            match check(&post)? {
+                Change::Unchanged => {
+                    self.unchanged += 1;
+                    if self.unchanged >= self.stop_after_unchanged {
+                        self.remaining = 0;
+                    }
+                    continue;
+                }
+                Change::Ignored => continue,
+                Change::Changed => self.unchanged = 0,
+            }
+            if !self.full_content || post.post.is_deleted != 0 {
+                return Ok(Some((post, false)));
+            }
+            let last_modify_time = post.last_modify_time;
+            post = self.client.post(post.post.post_id).full().await?;
+            post.last_modify_time = last_modify_time;
+            return Ok(Some((post, true)));
         }
-        unreachable!()
+        Ok(None)
     }
 }
 
 impl UserPostAPI {
+    pub(crate) fn incremental(
+        &self,
+        stop_after_unchanged: usize,
+        full_content: bool,
+    ) -> Incremental {
+        Incremental {
+            client: self.client.clone(),
+            posts: self.async_iter(),
+            stop_after_unchanged,
+            full_content,
+            remaining: usize::MAX,
+            unchanged: 0,
+        }
+    }
+
     pub fn async_iter(&self) -> UserPostIter {
         let query = UserPostQuery {
             size: 50,
@@ -72,7 +125,7 @@ impl UserPostAPI {
             is_last: false,
 
             posts: Vec::new(),
-            future: UserPostFuture::new(&self.client, &query),
+            future: crate::api::ResponseFuture::new(&self.client, PATH, &query),
         }
     }
 }
@@ -88,22 +141,16 @@ impl AsyncIterator for UserPostIter {
             return Poll::Ready(None);
         }
         let bytes = ready!(pin!(&mut self.future).poll(cx))?;
-        let res: crate::model::Response<crate::model::user_post::UserPostList> =
-            serde_json::from_slice(&bytes)?;
-        if res.retcode != 0 {
-            return Poll::Ready(Some(Err(crate::api::Error::Business {
-                retcode: res.retcode,
-                message: res.message,
-            })));
-        }
+        let data: crate::model::user_post::UserPostList =
+            crate::api::parse_response(&self.client, PATH, &bytes)?;
         let query = UserPostQuery {
             size: 50,
             uid: self.user_id,
-            offset: res.data.next_offset,
+            offset: data.next_offset,
         };
-        self.future = UserPostFuture::new(&self.client, &query);
-        self.is_last = res.data.is_last;
-        self.posts = res.data.list;
+        self.future = crate::api::ResponseFuture::new(&self.client, PATH, &query);
+        self.is_last = data.is_last;
+        self.posts = data.list;
         self.posts.reverse();
         std::task::Poll::Ready(self.posts.pop().map(Ok))
     }
@@ -0,0 +1,363 @@
+//! 帖子的本地存档
+//!
+//! 帖子以 JSON 的形式保存在 SQLite 中，每次 last_modify_time 或者删除状态变化时新增一个版本，
+//! 旧版本不会被覆盖，官方公告发布后悄悄修改的内容可以从历史版本中找回
+//!
+//! 增量同步从最新的帖子开始翻页，遇到连续若干篇没有变化的帖子时停止，
+//! 只有 userPost 列表中第一次出现或者有变化的帖子才会额外请求 getPostFull 拿完整的正文
+//!
+//! 被删除的帖子直接从列表中消失，增量同步发现不了，需要定期用 [`Archive::check_deleted`] 翻完整个列表检查
+
+use std::{async_iter::AsyncIterator, collections::HashSet, path::Path, pin::Pin};
+
+use chrono::{DateTime, Utc};
+use rusqlite::OptionalExtension;
+
+use crate::api::user_post::Change;
+use crate::model::user_post::UserPost;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("database error")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("serialization error")]
+    Serialization(#[from] serde_json::Error),
+    #[error("api error")]
+    Api(#[from] crate::api::Error),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS post (
+    post_id INTEGER PRIMARY KEY,
+    uid INTEGER NOT NULL,
+    subject TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    last_modify_time INTEGER NOT NULL,
+    is_deleted INTEGER NOT NULL,
+    deleted_at INTEGER NOT NULL,
+    revision INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS post_uid ON post (uid, created_at);
+CREATE TABLE IF NOT EXISTS revision (
+    post_id INTEGER NOT NULL,
+    revision INTEGER NOT NULL,
+    last_modify_time INTEGER NOT NULL,
+    is_deleted INTEGER NOT NULL,
+    is_full INTEGER NOT NULL,
+    archived_at INTEGER NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (post_id, revision)
+);
+";
+
+/// 写入一篇帖子的结果
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum StoreOutcome {
+    /// 第一次存档
+    Inserted,
+    /// 内容或删除状态有变化，新增了一个版本
+    Updated,
+    /// 和最新的版本一致，没有写入
+    Unchanged,
+}
+
+/// 帖子的某个历史版本
+#[derive(Clone, Debug)]
+pub struct Revision {
+    /// 从 1 开始
+    pub revision: u32,
+    pub last_modify_time: DateTime<Utc>,
+    pub is_deleted: bool,
+    /// 正文来自 getPostFull，否则是列表中的预览
+    pub is_full: bool,
+    /// 写入存档的时间
+    pub archived_at: DateTime<Utc>,
+    pub post: UserPost,
+}
+
+#[derive(Clone, Debug)]
+pub struct SyncOptions {
+    /// 连续遇到多少篇没有变化的帖子后停止翻页
+    /// 置顶的帖子总是排在最前面，所以默认不是遇到第一篇就停
+    pub stop_after_unchanged: usize,
+    /// 新增或有变化的帖子是否请求 getPostFull 获取完整正文
+    pub full_content: bool,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        Self {
+            stop_after_unchanged: 5,
+            full_content: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SyncReport {
+    pub inserted: Vec<u32>,
+    pub updated: Vec<u32>,
+    pub unchanged: usize,
+}
+
+pub struct Archive {
+    conn: rusqlite::Connection,
+}
+
+fn timestamp(secs: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(secs, 0).unwrap_or_default()
+}
+
+impl Archive {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::with_connection(rusqlite::Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(rusqlite::Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: rusqlite::Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// 写入一篇帖子，is_full 表示 post 是否来自 getPostFull
+    pub fn store(&mut self, post: &UserPost, is_full: bool) -> Result<StoreOutcome> {
+        let post_id = post.post.post_id;
+        let last_modify_time = post.last_modify_time.timestamp();
+        let is_deleted = post.post.is_deleted != 0;
+        let latest = self
+            .conn
+            .query_row(
+                "SELECT last_modify_time, is_deleted, revision FROM post WHERE post_id = ?1",
+                [post_id],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, bool>(1)?,
+                        row.get::<_, u32>(2)?,
+                    ))
+                },
+            )
+            .optional()?;
+        let (outcome, revision) = match latest {
+            None => (StoreOutcome::Inserted, 1u32),
+            Some((time, deleted, _)) if time == last_modify_time && deleted == is_deleted => {
+                return Ok(StoreOutcome::Unchanged);
+            }
+            Some((_, _, revision)) => (StoreOutcome::Updated, revision + 1),
+        };
+        let data = serde_json::to_string(post)?;
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO revision
+                (post_id, revision, last_modify_time, is_deleted, is_full, archived_at, data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                post_id,
+                revision,
+                last_modify_time,
+                is_deleted,
+                is_full,
+                Utc::now().timestamp(),
+                data,
+            ],
+        )?;
+        tx.execute(
+            "INSERT OR REPLACE INTO post
+                (post_id, uid, subject, created_at,
+                 last_modify_time, is_deleted, deleted_at, revision)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            rusqlite::params![
+                post_id,
+                post.post.uid,
+                post.post.subject,
+                post.post.created_at.timestamp(),
+                last_modify_time,
+                is_deleted,
+                post.post.deleted_at.timestamp(),
+                revision,
+            ],
+        )?;
+        tx.commit()?;
+        Ok(outcome)
+    }
+
+    /// 存档中该帖子的最新版本
+    pub fn post(&self, post_id: u32) -> Result<Option<UserPost>> {
+        let data = self
+            .conn
+            .query_row(
+                "SELECT revision.data FROM post JOIN revision USING (post_id, revision)
+                 WHERE post_id = ?1",
+                [post_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        Ok(data.map(|data| serde_json::from_str(&data)).transpose()?)
+    }
+
+    /// 全部历史版本，从旧到新
+    pub fn revisions(&self, post_id: u32) -> Result<Vec<Revision>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT revision, last_modify_time, is_deleted, is_full, archived_at, data
+             FROM revision WHERE post_id = ?1 ORDER BY revision",
+        )?;
+        let rows = stmt.query_map([post_id], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        })?;
+        let mut revisions = Vec::new();
+        for row in rows {
+            let (revision, last_modify_time, is_deleted, is_full, archived_at, data) = row?;
+            revisions.push(Revision {
+                revision,
+                last_modify_time: timestamp(last_modify_time),
+                is_deleted,
+                is_full,
+                archived_at: timestamp(archived_at),
+                post: serde_json::from_str(&data)?,
+            });
+        }
+        Ok(revisions)
+    }
+
+    fn query_posts(&self, condition: &str, params: impl rusqlite::Params) -> Result<Vec<UserPost>> {
+        let sql = format!(
+            "SELECT revision.data FROM post JOIN revision USING (post_id, revision)
+             WHERE {condition} ORDER BY post.created_at DESC"
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params, |row| row.get::<_, String>(0))?;
+        let mut posts = Vec::new();
+        for data in rows {
+            posts.push(serde_json::from_str(&data?)?);
+        }
+        Ok(posts)
+    }
+
+    /// 某个用户的全部帖子的最新版本，从新到旧
+    pub fn posts_by_user(&self, user_id: u32) -> Result<Vec<UserPost>> {
+        self.query_posts("post.uid = ?1", [user_id])
+    }
+
+    /// 标题中包含关键词的帖子，从新到旧
+    pub fn search(&self, keyword: &str) -> Result<Vec<UserPost>> {
+        let pattern = format!(
+            "%{}%",
+            keyword
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        self.query_posts("post.subject LIKE ?1 ESCAPE '\\'", [pattern])
+    }
+
+    /// 已经被删除的帖子，从新到旧
+    pub fn deleted_posts(&self) -> Result<Vec<UserPost>> {
+        self.query_posts("post.is_deleted != 0", [])
+    }
+
+    /// 增量同步某个用户的帖子
+    pub async fn sync_user(
+        &mut self,
+        client: &crate::client::Client,
+        user_id: u32,
+        options: &SyncOptions,
+    ) -> Result<SyncReport> {
+        let mut report = SyncReport::default();
+        let mut posts = (client.user_post(user_id))
+            .incremental(options.stop_after_unchanged, options.full_content);
+        loop {
+            let next = posts.next(|post| -> Result<Change> {
+                let stored = self.latest_modify_time(post.post.post_id)?;
+                let is_deleted = post.post.is_deleted != 0;
+                if stored == Some((post.last_modify_time.timestamp(), is_deleted)) {
+                    report.unchanged += 1;
+                    return Ok(Change::Unchanged);
+                }
+                Ok(Change::Changed)
+            });
+            let Some((post, is_full)) = next.await? else {
+                break;
+            };
+            match self.store(&post, is_full)? {
+                StoreOutcome::Inserted => report.inserted.push(post.post.post_id),
+                StoreOutcome::Updated => report.updated.push(post.post.post_id),
+                StoreOutcome::Unchanged => report.unchanged += 1,
+            }
+        }
+        Ok(report)
+    }
+
+    /// 重新检查某个用户已存档、但不在当前列表中的帖子，返回这次新标记为删除的帖子
+    ///
+    /// getPostFull 返回已删除的帖子时按新版本写入；返回业务错误（帖子不存在、不可见）时，
+    /// 在存档的最新版本上标记删除，删除时间记为检查时间；仍然可以正常访问的帖子不做处理
+    pub async fn check_deleted(
+        &mut self,
+        client: &crate::client::Client,
+        user_id: u32,
+    ) -> Result<Vec<u32>> {
+        let mut listed = HashSet::new();
+        let mut posts = client.user_post(user_id).async_iter();
+        while let Some(post) = std::future::poll_fn(|cx| Pin::new(&mut posts).poll_next(cx)).await {
+            listed.insert(post?.post.post_id);
+        }
+        let missing = self.query_posts("post.uid = ?1 AND post.is_deleted = 0", [user_id])?;
+        let mut deleted = Vec::new();
+        for mut post in missing {
+            let post_id = post.post.post_id;
+            if listed.contains(&post_id) {
+                continue;
+            }
+            let (post, is_full) = match client.post(post_id).full().await {
+                Ok(full) if full.post.is_deleted != 0 => (full, true),
+                Ok(_) => continue,
+                Err(crate::api::Error::Business { retcode, .. })
+                    if !client.is_rate_limit_retcode(retcode) =>
+                {
+                    post.post.is_deleted = 1;
+                    post.post.deleted_at = Utc::now();
+                    (post, self.latest_is_full(post_id)?)
+                }
+                Err(err) => return Err(err.into()),
+            };
+            if self.store(&post, is_full)? == StoreOutcome::Updated {
+                deleted.push(post_id);
+            }
+        }
+        Ok(deleted)
+    }
+
+    fn latest_is_full(&self, post_id: u32) -> Result<bool> {
+        self.conn
+            .query_row(
+                "SELECT revision.is_full FROM post JOIN revision USING (post_id, revision)
+                 WHERE post_id = ?1",
+                [post_id],
+                |row| row.get(0),
+            )
+            .map_err(Error::from)
+    }
+
+    fn latest_modify_time(&self, post_id: u32) -> Result<Option<(i64, bool)>> {
+        self.conn
+            .query_row(
+                "SELECT last_modify_time, is_deleted FROM post WHERE post_id = ?1",
+                [post_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(Error::from)
+    }
+}
@@ -1,11 +1,291 @@
-#[derive(Default)]
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+const BASE_URL: &str = "https://bbs-api.miyoushe.com";
+
+/// 米游社 API 客户端，clone 的开销很小，各个 API 和迭代器共享同一个限流器
+///
+/// 网络错误、HTTP 429 和 5xx、以及表示访问过于频繁的 retcode 会按指数退避重试
+#[derive(Clone)]
 pub struct Client {
-    client: reqwest::Client,
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    http: reqwest::Client,
+    base_url: String,
+    max_retries: u32,
+    retry_delay: Duration,
+    rate_limit_retcodes: Vec<i32>,
+    /// 两次请求之间的最小间隔，None 表示不限制
+    interval: Option<Duration>,
+    next_request: Mutex<Option<Instant>>,
+    fixtures: Option<crate::fixture::Fixtures>,
+    lenient: bool,
+    warnings: Mutex<Vec<ResponseWarnings>>,
+}
+
+/// 宽松模式下某个响应中和模型不一致的地方
+#[derive(Clone, Debug)]
+pub struct ResponseWarnings {
+    /// 接口路径，比如 `/post/wapi/userPost`
+    pub endpoint: &'static str,
+    pub warnings: crate::model::lenient::Warnings,
+}
+
+pub struct ClientBuilder {
+    http: reqwest::ClientBuilder,
+    headers: reqwest::header::HeaderMap,
+    base_url: String,
+    max_retries: u32,
+    retry_delay: Duration,
+    rate_limit_retcodes: Vec<i32>,
+    interval: Option<Duration>,
+    fixtures: Option<crate::fixture::Fixtures>,
+    lenient: bool,
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self {
+            http: reqwest::Client::builder(),
+            headers: reqwest::header::HeaderMap::new(),
+            base_url: BASE_URL.to_string(),
+            max_retries: 3,
+            retry_delay: Duration::from_secs(1),
+            // -110 访问过于频繁，1034 触发风控（需要验证码），两者过一段时间都会恢复
+            rate_limit_retcodes: vec![-110, 1034],
+            interval: None,
+            fixtures: None,
+            lenient: false,
+        }
+    }
+}
+
+impl ClientBuilder {
+    /// 替换 https://bbs-api.miyoushe.com，主要用于测试
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.http = self.http.user_agent(user_agent.into());
+        self
+    }
+
+    /// 每个请求都带上的请求头，比如 Referer
+    pub fn header(
+        mut self,
+        name: reqwest::header::HeaderName,
+        value: reqwest::header::HeaderValue,
+    ) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// 单个请求（不含重试）的超时时间
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.http = self.http.timeout(timeout);
+        self
+    }
+
+    /// 最大重试次数，默认 3
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// 第一次重试前等待的时间，之后每次翻倍，默认 1 秒
+    pub fn retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// 需要退避重试的 retcode
+    pub fn rate_limit_retcodes(mut self, retcodes: impl Into<Vec<i32>>) -> Self {
+        self.rate_limit_retcodes = retcodes.into();
+        self
+    }
+
+    /// 每秒最多发出的请求数，由这个 Client 的所有 clone 共享
+    pub fn requests_per_second(mut self, requests_per_second: f64) -> Self {
+        self.interval =
+            (requests_per_second > 0.).then(|| Duration::from_secs_f64(1. / requests_per_second));
+        self
+    }
+
+    /// 把每个成功的响应保存到 dir 下，见 [`crate::fixture`]
+    pub fn record(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.fixtures = Some(crate::fixture::Fixtures {
+            dir: dir.into(),
+            mode: crate::fixture::Mode::Record,
+        });
+        self
+    }
+
+    /// 只从 dir 中读取录制好的响应，不访问网络，见 [`crate::fixture`]
+    pub fn replay(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.fixtures = Some(crate::fixture::Fixtures {
+            dir: dir.into(),
+            mode: crate::fixture::Mode::Replay,
+        });
+        self
+    }
+
+    /// 宽松模式，接口返回模型中没有的字段时不报错，而是记录下来，见 [`Client::take_warnings`]
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    pub fn build(self) -> reqwest::Result<Client> {
+        let http = self.http.default_headers(self.headers).build()?;
+        Ok(Client {
+            inner: Arc::new(Inner {
+                http,
+                base_url: self.base_url,
+                max_retries: self.max_retries,
+                retry_delay: self.retry_delay,
+                rate_limit_retcodes: self.rate_limit_retcodes,
+                interval: self.interval,
+                next_request: Mutex::new(None),
+                fixtures: self.fixtures,
+                lenient: self.lenient,
+                warnings: Mutex::new(Vec::new()),
+            }),
+        })
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Client {
     pub fn new() -> Self {
-        Self::default()
+        // 和 reqwest::Client::new 一样，只有 TLS 后端初始化失败时才会 panic
+        Self::builder().build().unwrap()
+    }
+
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// 取出宽松模式下积累的警告，严格模式下总是为空
+    pub fn take_warnings(&self) -> Vec<ResponseWarnings> {
+        std::mem::take(&mut self.inner.warnings.lock().unwrap())
+    }
+
+    /// 严格模式下直接解析，宽松模式下记录和模型不一致的地方
+    pub(crate) fn deserialize<T>(
+        &self,
+        endpoint: &'static str,
+        bytes: &[u8],
+    ) -> serde_json::Result<T>
+    where
+        for<'de> T: serde::Deserialize<'de>,
+    {
+        if !self.inner.lenient {
+            return serde_json::from_slice(bytes);
+        }
+        let (value, warnings) = crate::model::lenient::from_slice(bytes)?;
+        if !warnings.is_empty() {
+            let warning = ResponseWarnings { endpoint, warnings };
+            self.inner.warnings.lock().unwrap().push(warning);
+        }
+        Ok(value)
+    }
+
+    /// 等到限流器允许发出下一个请求
+    async fn throttle(&self) {
+        let Some(interval) = self.inner.interval else {
+            return;
+        };
+        let now = Instant::now();
+        let at = {
+            let mut next_request = self.inner.next_request.lock().unwrap();
+            let at = next_request.map_or(now, |next| next.max(now));
+            *next_request = Some(at + interval);
+            at
+        };
+        tokio::time::sleep_until(at).await;
+    }
+
+    fn is_rate_limited(&self, body: &[u8]) -> bool {
+        #[derive(serde::Deserialize)]
+        struct Retcode {
+            retcode: i32,
+        }
+        serde_json::from_slice::<Retcode>(body)
+            .is_ok_and(|res| self.is_rate_limit_retcode(res.retcode))
+    }
+
+    /// 是否为需要退避重试的 retcode，重试次数用完后调用方会收到这些 retcode 的业务错误
+    pub(crate) fn is_rate_limit_retcode(&self, retcode: i32) -> bool {
+        self.inner.rate_limit_retcodes.contains(&retcode)
+    }
+
+    /// GET base_url + path，返回完整的响应体
+    ///
+    /// 重试次数用完时，网络错误原样返回，频率限制的 retcode 则返回最后一次的响应体，由调用方解析
+    pub(crate) fn get(
+        &self,
+        path: &str,
+        query: &impl serde::Serialize,
+    ) -> impl std::future::Future<Output = crate::api::Result<bytes::Bytes>> + Send + 'static {
+        let url = format!("{}{path}", self.inner.base_url);
+        let request = self.inner.http.get(url).query(query).build();
+        let client = self.clone();
+        async move { client.execute(request?).await }
+    }
+
+    async fn execute(&self, request: reqwest::Request) -> crate::api::Result<bytes::Bytes> {
+        let fixtures = self.inner.fixtures.as_ref();
+        if let Some(fixtures) = fixtures.filter(|f| f.mode == crate::fixture::Mode::Replay) {
+            return Ok(fixtures.load(request.url())?);
+        }
+        let mut attempt = 0;
+        loop {
+            self.throttle().await;
+            // GET 请求没有 body，一定可以 clone
+            let result = match self.inner.http.execute(request.try_clone().unwrap()).await {
+                Ok(res) => match res.error_for_status() {
+                    Ok(res) => res.bytes().await,
+                    Err(err) => Err(err),
+                },
+                Err(err) => Err(err),
+            };
+            let retry = match &result {
+                Ok(body) => self.is_rate_limited(body),
+                Err(err) => {
+                    err.is_timeout()
+                        || err.is_connect()
+                        || err.is_body()
+                        || (err.status()).is_some_and(|status| {
+                            status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                                || status.is_server_error()
+                        })
+                }
+            };
+            if !retry || attempt >= self.inner.max_retries {
+                let body = result?;
+                if let Some(fixtures) = fixtures.filter(|_| !retry) {
+                    fixtures.save(request.url(), &body)?;
+                }
+                return Ok(body);
+            }
+            tokio::time::sleep(
+                (self.inner.retry_delay).saturating_mul(2u32.saturating_pow(attempt)),
+            )
+            .await;
+            attempt += 1;
+        }
     }
 }
 
@@ -18,7 +298,7 @@ impl Client {
 
     pub fn user_post(&self, user_id: u32) -> crate::api::UserPostAPI {
         crate::api::UserPostAPI {
-            client: self.client.clone(),
+            client: self.clone(),
             user_id,
         }
     }
@@ -43,3 +323,52 @@ impl Client {
         self.user_post(Self::ZZZ_OFFICIAL_UID)
     }
 }
+
+impl Client {
+    pub fn post(&self, post_id: u32) -> crate::api::PostAPI {
+        crate::api::PostAPI {
+            client: self.clone(),
+            post_id,
+        }
+    }
+}
+
+impl Client {
+    /// 版块帖子列表，forum_id 即帖子的 f_forum_id
+    pub fn forum(&self, game_id: crate::model::GameId, forum_id: u8) -> crate::api::ForumAPI {
+        crate::api::ForumAPI {
+            client: self.clone(),
+            game_id,
+            forum_id,
+            sort: crate::api::ForumSort::default(),
+            is_good: false,
+            page_size: 20,
+        }
+    }
+
+    /// 话题帖子列表，topic_id 即帖子的 topic_ids
+    pub fn topic(&self, game_id: crate::model::GameId, topic_id: u32) -> crate::api::TopicAPI {
+        crate::api::TopicAPI {
+            client: self.clone(),
+            game_id,
+            topic_id,
+            sort: crate::api::TopicSort::default(),
+            page_size: 20,
+        }
+    }
+
+    /// 在某个游戏的社区中按关键词搜索帖子
+    pub fn search(
+        &self,
+        game_id: crate::model::GameId,
+        keyword: impl Into<String>,
+    ) -> crate::api::SearchAPI {
+        crate::api::SearchAPI {
+            client: self.clone(),
+            game_id,
+            keyword: keyword.into(),
+            sort: crate::api::SearchSort::default(),
+            page_size: 20,
+        }
+    }
+}
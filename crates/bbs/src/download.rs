@@ -0,0 +1,369 @@
+//! 下载帖子中的图片和视频
+//!
+//! 文件按内容的 SHA-256 保存在 `objects/<前两位>/<哈希>.<扩展名>`，不同链接的相同内容只保存一份
+//! 下载中的文件保存在 `partial/` 下，中断后再次下载会用 Range 请求从断点继续
+//! `manifest.json` 记录每个链接属于哪篇帖子、对应哪个文件
+//!
+//! manifest 只在 [`Downloader::download_media`] 结束时整体写入，不要让多个 Downloader 同时使用同一个目录
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use futures_util::StreamExt;
+use sha2::Digest;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::model::media::{VideoCodec, VideoResolution, VideoResolutionDefinition};
+use crate::model::structured_content::StructuredContent;
+use crate::model::user_post::UserPost;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("network error")]
+    Network(#[from] reqwest::Error),
+    #[error("io error")]
+    Io(#[from] std::io::Error),
+    #[error("manifest error")]
+    Manifest(#[from] serde_json::Error),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    /// 封面
+    Cover,
+    /// 帖子的 image_list
+    Image,
+    /// 正文中插入的图片
+    InlineImage,
+    /// 视频，只下载按 [`VideoPreference`] 选出的一个清晰度
+    Video,
+}
+
+#[derive(Clone, Debug)]
+pub struct Media {
+    pub url: http::Uri,
+    pub kind: MediaKind,
+}
+
+/// 视频清晰度的偏好
+#[derive(Clone, Debug)]
+pub struct VideoPreference {
+    /// 不超过该清晰度中最高的一个，都超过时选最低的
+    /// 按视频实际的宽高比较，不看接口给出的清晰度标签
+    pub definition: VideoResolutionDefinition,
+    /// 有该编码的清晰度时只在其中选择
+    pub codec: Option<VideoCodec>,
+}
+
+impl Default for VideoPreference {
+    fn default() -> Self {
+        Self {
+            definition: VideoResolutionDefinition::_1080P,
+            codec: None,
+        }
+    }
+}
+
+impl VideoPreference {
+    pub fn select<'a>(&self, resolutions: &'a [VideoResolution]) -> Option<&'a VideoResolution> {
+        let matched = (resolutions.iter())
+            .filter(|res| self.codec.is_none_or(|codec| res.codec == codec))
+            .collect::<Vec<_>>();
+        let candidates = match matched.is_empty() {
+            true => resolutions.iter().collect(),
+            false => matched,
+        };
+        let limit = self.definition.short_side();
+        let within = (candidates.iter())
+            .filter(|res| res.dimensions().0 <= limit)
+            .max_by_key(|res| res.dimensions());
+        within
+            .or_else(|| candidates.iter().min_by_key(|res| res.dimensions()))
+            .copied()
+    }
+}
+
+fn collect_inline(
+    content: &[StructuredContent],
+    preference: &VideoPreference,
+    out: &mut Vec<Media>,
+) {
+    for item in content {
+        match item {
+            StructuredContent::Image { insert, .. } => {
+                if let Some(url) = &insert.image {
+                    out.push(Media {
+                        url: url.clone(),
+                        kind: MediaKind::InlineImage,
+                    });
+                }
+            }
+            StructuredContent::Fold { insert } => {
+                collect_inline(&insert.fold.title, preference, out);
+                collect_inline(&insert.fold.content, preference, out);
+            }
+            StructuredContent::Video { insert } => out.push(Media {
+                url: insert.video.clone(),
+                kind: MediaKind::Video,
+            }),
+            StructuredContent::Vod { insert } => {
+                if let Some(res) = preference.select(&insert.vod.resolutions) {
+                    out.push(Media {
+                        url: res.url.clone(),
+                        kind: MediaKind::Video,
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// 帖子中需要下载的全部媒体，按链接去重
+pub fn post_media(post: &UserPost, preference: &VideoPreference) -> Vec<Media> {
+    let mut media = Vec::new();
+    let cover = (post.cover.as_ref().map(|cover| &cover.url)).or(post.post.cover.as_ref());
+    if let Some(url) = cover {
+        media.push(Media {
+            url: url.clone(),
+            kind: MediaKind::Cover,
+        });
+    }
+    for image in &post.image_list {
+        media.push(Media {
+            url: image.url.clone(),
+            kind: MediaKind::Image,
+        });
+    }
+    if let Some(content) = &post.post.structured_content {
+        collect_inline(content, preference, &mut media);
+    }
+    for vod in &post.vod_list {
+        if let Some(res) = preference.select(&vod.resolutions) {
+            media.push(Media {
+                url: res.url.clone(),
+                kind: MediaKind::Video,
+            });
+        }
+    }
+    let mut seen = HashSet::new();
+    media.retain(|media| seen.insert(media.url.to_string()));
+    media
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct ManifestEntry {
+    pub post_id: u32,
+    #[serde(with = "http_serde::uri")]
+    pub url: http::Uri,
+    pub kind: MediaKind,
+    /// 相对于下载目录的路径
+    pub file: PathBuf,
+    pub sha256: String,
+    pub size: u64,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct Manifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    /// 文件不存在时返回空的 manifest
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        crate::json_file::load(path)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        crate::json_file::save(path, self)
+    }
+
+    /// 某个链接已经下载过的记录
+    pub fn find(&self, url: &http::Uri) -> Option<&ManifestEntry> {
+        self.entries.iter().find(|entry| entry.url == *url)
+    }
+
+    /// 某篇帖子的全部文件
+    pub fn post(&self, post_id: u32) -> impl Iterator<Item = &ManifestEntry> {
+        (self.entries.iter()).filter(move |entry| entry.post_id == post_id)
+    }
+
+    fn insert(&mut self, entry: ManifestEntry) {
+        let exists =
+            (self.entries.iter()).any(|old| old.post_id == entry.post_id && old.url == entry.url);
+        if !exists {
+            self.entries.push(entry);
+        }
+    }
+}
+
+pub struct Downloader {
+    client: reqwest::Client,
+    dir: PathBuf,
+    concurrency: usize,
+    video: VideoPreference,
+}
+
+impl Downloader {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            dir: dir.into(),
+            concurrency: 4,
+            video: VideoPreference::default(),
+        }
+    }
+
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// 同时进行的下载数，默认 4
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn video_preference(mut self, video: VideoPreference) -> Self {
+        self.video = video;
+        self
+    }
+
+    pub fn manifest_path(&self) -> PathBuf {
+        self.dir.join("manifest.json")
+    }
+
+    /// 下载帖子的封面、图片和视频
+    pub async fn download_post(&self, post: &UserPost) -> Result<Vec<ManifestEntry>> {
+        let media = post_media(post, &self.video);
+        self.download_media(post.post.post_id, &media).await
+    }
+
+    /// 下载一组媒体并记入 manifest，已经下载过的链接不会重复请求
+    ///
+    /// 单个文件失败不影响其他文件，成功的部分仍会写入 manifest，之后返回遇到的第一个错误
+    pub async fn download_media(
+        &self,
+        post_id: u32,
+        media: &[Media],
+    ) -> Result<Vec<ManifestEntry>> {
+        let manifest_path = self.manifest_path();
+        let mut manifest = Manifest::load(&manifest_path)?;
+        let mut entries = Vec::new();
+        let mut pending = Vec::new();
+        for media in media {
+            let downloaded =
+                (manifest.find(&media.url)).filter(|entry| self.dir.join(&entry.file).exists());
+            match downloaded {
+                Some(entry) => entries.push(ManifestEntry {
+                    post_id,
+                    kind: media.kind,
+                    ..entry.clone()
+                }),
+                None => pending.push(media),
+            }
+        }
+        let results = futures_util::stream::iter(pending)
+            .map(|media| async move {
+                let (file, sha256, size) = self.fetch(&media.url).await?;
+                Ok::<_, Error>(ManifestEntry {
+                    post_id,
+                    url: media.url.clone(),
+                    kind: media.kind,
+                    file,
+                    sha256,
+                    size,
+                })
+            })
+            .buffer_unordered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+        let mut error = None;
+        for result in results {
+            match result {
+                Ok(entry) => entries.push(entry),
+                Err(err) => {
+                    error.get_or_insert(err);
+                }
+            }
+        }
+        for entry in &entries {
+            manifest.insert(entry.clone());
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        manifest.save(&manifest_path)?;
+        match error {
+            Some(err) => Err(err),
+            None => Ok(entries),
+        }
+    }
+
+    /// 下载到 partial 目录，完成后按内容哈希移动到 objects 目录
+    async fn fetch(&self, uri: &http::Uri) -> Result<(PathBuf, String, u64)> {
+        let url = uri.to_string();
+        let partial_dir = self.dir.join("partial");
+        tokio::fs::create_dir_all(&partial_dir).await?;
+        let partial = partial_dir.join(hex::encode(sha2::Sha256::digest(&url)) + ".part");
+        let offset = match tokio::fs::metadata(&partial).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+        let mut req = self.client.get(&url);
+        if offset != 0 {
+            req = req.header(http::header::RANGE, format!("bytes={offset}-"));
+        }
+        let mut res = req.send().await?;
+        let status = res.status();
+        // 416 说明上次已经下载完整，只是没来得及移动
+        let is_complete = offset != 0 && status == http::StatusCode::RANGE_NOT_SATISFIABLE;
+        if !is_complete {
+            res = res.error_for_status()?;
+            let append = status == http::StatusCode::PARTIAL_CONTENT;
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(append)
+                .truncate(!append)
+                .open(&partial)
+                .await?;
+            while let Some(chunk) = res.chunk().await? {
+                file.write_all(&chunk).await?;
+            }
+            file.flush().await?;
+        }
+
+        let mut hasher = sha2::Sha256::new();
+        let mut file = tokio::fs::File::open(&partial).await?;
+        let mut buffer = vec![0; 64 * 1024];
+        let mut size = 0;
+        loop {
+            let len = file.read(&mut buffer).await?;
+            if len == 0 {
+                break;
+            }
+            hasher.update(&buffer[..len]);
+            size += len as u64;
+        }
+        let sha256 = hex::encode(hasher.finalize());
+
+        let extension = Path::new(uri.path())
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_else(|| String::from("bin"));
+        let file = Path::new("objects")
+            .join(&sha256[..2])
+            .join(format!("{sha256}.{extension}"));
+        let object = self.dir.join(&file);
+        tokio::fs::create_dir_all(object.parent().unwrap()).await?;
+        match tokio::fs::try_exists(&object).await? {
+            true => tokio::fs::remove_file(&partial).await?,
+            false => tokio::fs::rename(&partial, &object).await?,
+        }
+        Ok((file, sha256, size))
+    }
+}
@@ -0,0 +1,88 @@
+//! 录制和回放 API 响应
+//!
+//! 录制时把每个请求的原始响应体保存到 `<目录>/<接口路径>/<查询参数的哈希>.json`，
+//! 回放时按同样的规则读取文件，不发出任何网络请求，找不到对应的文件时返回错误
+//!
+//! 响应体原样保存，不经过模型的序列化，模型的改动可以用录制好的文件离线验证
+//! 新录制的响应放到 `crates/bbs/tests/fixtures` 下，`tests/fixture.rs` 会检查它们都能被当前的模型解析
+
+use std::path::{Path, PathBuf};
+
+use sha2::Digest;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Mode {
+    /// 正常请求，并把成功的响应写入目录
+    Record,
+    /// 只从目录中读取响应
+    Replay,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Fixtures {
+    pub(crate) dir: PathBuf,
+    pub(crate) mode: Mode,
+}
+
+/// 某个请求对应的文件，相对于录制目录
+///
+/// 查询参数可能包含搜索关键词之类的任意字符，所以只用它的哈希作为文件名
+pub fn file_name(url: &reqwest::Url) -> PathBuf {
+    let query = url.query().unwrap_or_default();
+    let hash = hex::encode(sha2::Sha256::digest(query));
+    Path::new(url.path().trim_start_matches('/')).join(format!("{}.json", &hash[..16]))
+}
+
+impl Fixtures {
+    pub(crate) fn load(&self, url: &reqwest::Url) -> std::io::Result<bytes::Bytes> {
+        std::fs::read(self.dir.join(file_name(url))).map(bytes::Bytes::from)
+    }
+
+    pub(crate) fn save(&self, url: &reqwest::Url, body: &[u8]) -> std::io::Result<()> {
+        let path = self.dir.join(file_name(url));
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, body)
+    }
+}
+
+/// 录制好的一个响应
+#[derive(Clone, Debug)]
+pub struct Fixture {
+    /// 接口路径，比如 `/post/wapi/userPost`
+    pub endpoint: String,
+    pub path: PathBuf,
+    pub body: Vec<u8>,
+}
+
+/// 读取目录下录制的全部响应，按文件路径排序
+pub fn load_dir(dir: impl AsRef<Path>) -> std::io::Result<Vec<Fixture>> {
+    fn walk(root: &Path, dir: &Path, out: &mut Vec<Fixture>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(root, &path, out)?;
+                continue;
+            }
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let endpoint = (path.parent().unwrap().strip_prefix(root).unwrap())
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .fold(String::new(), |endpoint, component| {
+                    endpoint + "/" + &component
+                });
+            out.push(Fixture {
+                endpoint,
+                body: std::fs::read(&path)?,
+                path,
+            });
+        }
+        Ok(())
+    }
+    let dir = dir.as_ref();
+    let mut fixtures = Vec::new();
+    walk(dir, dir, &mut fixtures)?;
+    fixtures.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(fixtures)
+}
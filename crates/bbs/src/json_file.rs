@@ -0,0 +1,29 @@
+//! 保存在本地的 JSON 状态文件，比如订阅状态和下载的 manifest
+
+use std::path::Path;
+
+/// 读取并解析文件，文件不存在时返回默认值
+pub(crate) fn load<T, E>(path: impl AsRef<Path>) -> Result<T, E>
+where
+    for<'de> T: serde::Deserialize<'de> + Default,
+    E: From<std::io::Error> + From<serde_json::Error>,
+{
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// 先写入同目录下的 `<文件名>.tmp` 再重命名，写到一半中断时不会损坏原有的文件
+pub(crate) fn save<T, E>(path: impl AsRef<Path>, value: &T) -> Result<(), E>
+where
+    T: serde::Serialize,
+    E: From<std::io::Error> + From<serde_json::Error>,
+{
+    let path = path.as_ref();
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    std::fs::write(&temp, serde_json::to_vec_pretty(value)?)?;
+    Ok(std::fs::rename(&temp, path)?)
+}
@@ -1,5 +1,12 @@
 #![feature(async_iterator)]
 
+pub mod announcement;
 pub mod api;
+pub mod archive;
 pub mod client;
+pub mod download;
+pub mod fixture;
+mod json_file;
 pub mod model;
+pub mod render;
+pub mod syndication;
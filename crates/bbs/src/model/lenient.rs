@@ -0,0 +1,427 @@
+//! 宽松的反序列化
+//!
+//! 模型都标注了 `deny_unknown_fields`，接口新增一个字段就会导致整页解析失败
+//! 宽松模式下，模型中没有的字段会被移除并按所在对象记录下来，
+//! `Option<()>` 之类只见过空值的字段出现了实际的值时也会保留原始 JSON，解析本身照常进行
+//!
+//! untagged 枚举（比如 [`StructuredContent`](crate::model::structured_content::StructuredContent)）
+//! 由 serde 内部缓存后再匹配，其中的未知字段无法移除。这类枚举以 [`Unrecognized`] 作为最后一个分支，
+//! 宽松模式下没有匹配任何分支的值会落到这里并记录下来，严格模式下仍然解析失败
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use serde::de::{DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Value};
+
+/// 一次解析中发现的和模型不一致的地方，路径形如 `data.list[0].post`
+#[derive(Clone, Debug, Default)]
+pub struct Warnings {
+    /// 对象的路径 → 模型中没有的字段
+    pub unknown_fields: BTreeMap<String, Map<String, Value>>,
+    /// 字段的路径 → 模型中只有空值的字段实际出现的值
+    pub unexpected_values: BTreeMap<String, Value>,
+    /// 值的路径 → 没有匹配 untagged 枚举任何分支的值
+    pub unrecognized_values: BTreeMap<String, Value>,
+}
+
+impl Warnings {
+    pub fn is_empty(&self) -> bool {
+        self.unknown_fields.is_empty()
+            && self.unexpected_values.is_empty()
+            && self.unrecognized_values.is_empty()
+    }
+}
+
+impl std::fmt::Display for Warnings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (path, fields) in &self.unknown_fields {
+            for (field, value) in fields {
+                writeln!(f, "未知字段 {}: {value}", join(path, field))?;
+            }
+        }
+        for (path, value) in &self.unexpected_values {
+            writeln!(f, "非空值 {path}: {value}")?;
+        }
+        for (path, value) in &self.unrecognized_values {
+            writeln!(f, "无法识别 {path}: {value}")?;
+        }
+        Ok(())
+    }
+}
+
+thread_local! {
+    /// 正在进行的宽松解析，None 表示严格模式
+    static WARNINGS: RefCell<Option<Warnings>> = const { RefCell::new(None) };
+    /// 最近一次解析字符串时的路径，给 JSON 套 JSON 的字段使用
+    static STRING_PATH: RefCell<String> = const { RefCell::new(String::new()) };
+    /// 最近一次整体读取对象时的路径，给 untagged 枚举的兜底分支使用
+    static OBJECT_PATH: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+fn join(path: &str, key: &str) -> String {
+    match path.is_empty() {
+        true => key.to_string(),
+        false => format!("{path}.{key}"),
+    }
+}
+
+fn warn(f: impl FnOnce(&mut Warnings)) {
+    WARNINGS.with_borrow_mut(|warnings| {
+        if let Some(warnings) = warnings {
+            f(warnings);
+        }
+    });
+}
+
+/// 宽松地解析 JSON，同时返回解析过程中发现的问题
+pub fn from_slice<T>(bytes: &[u8]) -> serde_json::Result<(T, Warnings)>
+where
+    for<'de> T: serde::Deserialize<'de>,
+{
+    let value: Value = serde_json::from_slice(bytes)?;
+    let outer = WARNINGS.replace(Some(Warnings::default()));
+    let result = T::deserialize(Lenient {
+        value,
+        path: String::new(),
+    });
+    let warnings = WARNINGS.replace(outer).unwrap_or_default();
+    Ok((result?, warnings))
+}
+
+/// 解析字段中以字符串形式保存的 JSON，宽松模式下沿用外层的模式和路径
+pub(crate) fn from_str_nested<T>(str: &str) -> serde_json::Result<T>
+where
+    for<'de> T: serde::Deserialize<'de>,
+{
+    if WARNINGS.with_borrow(Option::is_none) {
+        return serde_json::from_str(str);
+    }
+    T::deserialize(Lenient {
+        value: serde_json::from_str(str)?,
+        path: STRING_PATH.with_borrow(Clone::clone),
+    })
+}
+
+/// untagged 枚举的兜底分支，保留原始 JSON
+///
+/// 只在宽松模式下匹配成功，严格模式下总是报错，以免接口变化被悄悄吞掉
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+#[serde(transparent)]
+pub struct Unrecognized(pub Value);
+
+impl<'de> serde::Deserialize<'de> for Unrecognized {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        if WARNINGS.with_borrow(Option::is_none) {
+            return Err(serde::de::Error::custom("data did not match any variant"));
+        }
+        warn(|warnings| {
+            let path = OBJECT_PATH.with_borrow(Clone::clone);
+            (warnings.unrecognized_values).insert(path, value.clone());
+        });
+        Ok(Unrecognized(value))
+    }
+}
+
+struct Lenient {
+    value: Value,
+    path: String,
+}
+
+struct LenientSeq {
+    iter: std::iter::Enumerate<std::vec::IntoIter<Value>>,
+    path: String,
+}
+
+impl<'de> SeqAccess<'de> for LenientSeq {
+    type Error = serde_json::Error;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, Self::Error> {
+        let Some((index, value)) = self.iter.next() else {
+            return Ok(None);
+        };
+        let path = format!("{}[{index}]", self.path);
+        seed.deserialize(Lenient { value, path }).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct LenientMap {
+    iter: serde_json::map::IntoIter,
+    value: Option<Lenient>,
+    path: String,
+}
+
+impl<'de> MapAccess<'de> for LenientMap {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        let Some((key, value)) = self.iter.next() else {
+            return Ok(None);
+        };
+        let path = join(&self.path, &key);
+        self.value = Some(Lenient { value, path });
+        seed.deserialize(key.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let value = self.value.take();
+        seed.deserialize(value.ok_or_else(|| serde::de::Error::custom("value is missing"))?)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+macro_rules! delegate {
+    ($($method:ident)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            self.value.$method(visitor)
+        }
+    )*};
+}
+
+impl<'de> Deserializer<'de> for Lenient {
+    type Error = serde_json::Error;
+
+    delegate! {
+        deserialize_bool deserialize_char deserialize_bytes deserialize_byte_buf deserialize_identifier
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Array(_) => self.deserialize_seq(visitor),
+            Value::Object(_) => {
+                // untagged 枚举会先整体读取对象再逐个分支匹配，子对象读取完之后再记录路径
+                let path = self.path.clone();
+                let result = self.deserialize_map(visitor);
+                OBJECT_PATH.set(path);
+                result
+            }
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        STRING_PATH.set(self.path);
+        self.value.deserialize_str(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        STRING_PATH.set(self.path);
+        self.value.deserialize_string(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        if !self.value.is_null() {
+            warn(|warnings| {
+                (warnings.unexpected_values).insert(self.path, self.value);
+            });
+        }
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Array(array) => visitor.visit_seq(LenientSeq {
+                iter: array.into_iter().enumerate(),
+                path: self.path,
+            }),
+            value => value.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Object(map) => visitor.visit_map(LenientMap {
+                iter: map.into_iter(),
+                value: None,
+                path: self.path,
+            }),
+            value => value.deserialize_map(visitor),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let Value::Object(mut map) = self.value else {
+            return self.value.deserialize_map(visitor);
+        };
+        let unknown = (map.keys())
+            .filter(|key| !fields.contains(&key.as_str()))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            let unknown = (unknown.into_iter())
+                .filter_map(|key| map.remove_entry(&key))
+                .collect::<Map<_, _>>();
+            warn(|warnings| {
+                let fields = warnings
+                    .unknown_fields
+                    .entry(self.path.clone())
+                    .or_default();
+                fields.extend(unknown);
+            });
+        }
+        visitor.visit_map(LenientMap {
+            iter: map.into_iter(),
+            value: None,
+            path: self.path,
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.value.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, serde::Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Inner {
+        id: u32,
+        placeholder: Option<()>,
+        #[serde(default, with = "crate::model::optional_json_twice")]
+        nested: Option<Vec<Inner>>,
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Outer {
+        #[serde(alias = "items")]
+        list: Vec<Inner>,
+    }
+
+    #[test]
+    fn collect_unknown_fields() {
+        let json = br#"{"items":[{"id":1,"placeholder":null},{"id":2,"placeholder":{"a":1},"new":"x"}],"is_last":true}"#;
+        assert!(serde_json::from_slice::<Outer>(json).is_err());
+        let (outer, warnings) = from_slice::<Outer>(json).unwrap();
+        assert_eq!(outer.list.len(), 2);
+        assert_eq!(outer.list[1].id, 2);
+        assert_eq!(warnings.unknown_fields[""]["is_last"], Value::Bool(true));
+        assert_eq!(warnings.unknown_fields["items[1]"]["new"], "x");
+        assert_eq!(
+            warnings.unexpected_values["items[1].placeholder"],
+            serde_json::json!({"a": 1})
+        );
+    }
+
+    #[test]
+    fn nested_json_string() {
+        let json = br#"{"list":[{"id":1,"placeholder":null,"nested":"[{\"id\":2,\"placeholder\":null,\"new\":0}]"}]}"#;
+        let (outer, warnings) = from_slice::<Outer>(json).unwrap();
+        assert_eq!(outer.list[0].nested.as_ref().unwrap()[0].id, 2);
+        assert_eq!(
+            warnings.unknown_fields["list[0].nested[0]"]["new"],
+            Value::from(0)
+        );
+        // 严格模式下的行为不变
+        let json = br#"{"list":[{"id":1,"placeholder":null,"nested":"[{\"id\":2,\"placeholder\":null}]"}]}"#;
+        assert!(serde_json::from_slice::<Outer>(json).is_ok());
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    #[serde(deny_unknown_fields, untagged)]
+    enum Untagged {
+        Text { insert: String },
+        Embed { insert: Inner },
+        Unknown(Unrecognized),
+    }
+
+    #[test]
+    fn unrecognized_variant() {
+        let json = br#"[{"insert":"a"},{"insert":{"id":1,"placeholder":null}},{"insert":{"id":2,"placeholder":null,"new":0}}]"#;
+        assert!(serde_json::from_slice::<Vec<Untagged>>(json).is_err());
+        let (list, warnings) = from_slice::<Vec<Untagged>>(json).unwrap();
+        assert!(matches!(&list[0], Untagged::Text { insert } if insert == "a"));
+        assert!(matches!(&list[1], Untagged::Embed { insert } if insert.id == 1));
+        let Untagged::Unknown(Unrecognized(value)) = &list[2] else {
+            panic!("{:?}", list[2]);
+        };
+        assert_eq!(value["insert"]["new"], Value::from(0));
+        assert_eq!(warnings.unrecognized_values["[2]"], *value);
+        assert_eq!(warnings.unrecognized_values.len(), 1);
+    }
+
+    #[test]
+    fn no_warnings() {
+        let json = br#"{"list":[{"id":1,"placeholder":null}]}"#;
+        let (outer, warnings) = from_slice::<Outer>(json).unwrap();
+        assert!(outer.list[0].placeholder.is_none());
+        assert!(warnings.is_empty());
+    }
+}
@@ -79,6 +79,25 @@ pub enum VideoResolutionDefinition {
     _2K,
 }
 
+impl VideoResolutionDefinition {
+    /// 该清晰度画面短边的像素数
+    pub fn short_side(self) -> u16 {
+        match self {
+            Self::_480P => 480,
+            Self::_720P => 720,
+            Self::_1080P => 1080,
+            Self::_2K => 1440,
+        }
+    }
+}
+
+impl VideoResolution {
+    /// 实际的（短边，长边）像素数，竖屏视频的宽高是反过来的
+    pub fn dimensions(&self) -> (u16, u16) {
+        (self.width.min(self.height), self.width.max(self.height))
+    }
+}
+
 mod num_maybe_str {
     pub fn serialize<S, I>(int: &I, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -1,5 +1,7 @@
+pub mod lenient;
 pub mod media;
 
+pub mod post;
 pub mod structured_content;
 pub mod user_post;
 
@@ -54,7 +56,8 @@ mod json_twice {
         D: Deserializer<'de>,
         for<'owned> T: Deserialize<'owned>,
     {
-        serde_json::from_str(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+        crate::model::lenient::from_str_nested(&String::deserialize(deserializer)?)
+            .map_err(serde::de::Error::custom)
     }
 }
 
@@ -81,7 +84,7 @@ mod optional_json_twice {
         if str.is_empty() {
             return Ok(None);
         }
-        serde_json::from_str(&str).map_err(serde::de::Error::custom)
+        crate::model::lenient::from_str_nested(&str).map_err(serde::de::Error::custom)
     }
 }
 
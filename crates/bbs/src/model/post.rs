@@ -0,0 +1,92 @@
+//! 帖子详情和评论（回复、楼中楼）
+//!
+//! 评论接口的样本还不够多，这里的结构暂时没有 deny_unknown_fields，只保留了确定含义的字段
+
+use chrono::{DateTime, Utc};
+
+use crate::model::structured_content::StructuredContent;
+use crate::model::user_post::{User, UserPost};
+
+/// getPostFull 的返回，和 userPost 列表中的结构相同，但 structured_content 是完整的正文而非预览
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct PostFull {
+    pub post: UserPost,
+}
+
+#[serde_with::serde_as]
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct ReplyContent {
+    pub game_id: crate::model::GameId,
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    pub post_id: u32,
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    pub reply_id: u64,
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    pub uid: u32,
+    /// 楼中楼回复的楼层的 reply_id，楼层本身为 0
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    pub f_reply_id: u64,
+    /// 楼层号，楼中楼和所在楼层相同
+    pub floor_id: u32,
+    /// HTML 格式的内容
+    pub content: String,
+    #[serde(default, with = "crate::model::optional_json_twice")]
+    pub struct_content: Option<Vec<StructuredContent>>,
+    pub is_deleted: u8,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct ReplyStat {
+    pub reply_num: u32,
+    pub like_num: u32,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct ReplyMasterStatus {
+    /// 官方版主
+    pub is_official_master: bool,
+    /// 用户版主
+    pub is_user_master: bool,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Reply {
+    pub reply: ReplyContent,
+    pub user: User,
+    /// 楼中楼中被回复的用户，直接回复楼层时为空
+    #[serde(default)]
+    pub r_user: Option<User>,
+    pub stat: ReplyStat,
+    /// 楼层下预览的几条楼中楼，完整的需要用 [`crate::api::PostAPI::sub_replies`] 获取
+    #[serde(default)]
+    pub sub_replies: Vec<Reply>,
+    #[serde(default)]
+    pub sub_reply_count: u32,
+    /// 是否为楼主
+    #[serde(default)]
+    pub is_lz: bool,
+    #[serde(default)]
+    pub master_status: Option<ReplyMasterStatus>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct ReplyList {
+    pub list: Vec<Reply>,
+    /// 下一页的游标，原样传回
+    pub last_id: String,
+    pub is_last: bool,
+}
+
+/// 版块、话题、搜索的帖子列表，版块返回的字段名为 list，话题和搜索的为 posts
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct PostList {
+    #[serde(alias = "posts")]
+    pub list: Vec<UserPost>,
+    /// 下一页的游标，原样传回
+    pub last_id: String,
+    pub is_last: bool,
+}
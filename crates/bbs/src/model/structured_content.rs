@@ -139,7 +139,7 @@ pub struct LinkCard {
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct InsertLinkCard {
-    link_card: LinkCard,
+    pub link_card: LinkCard,
 }
 
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
@@ -257,8 +257,8 @@ pub struct ReceptionCard {
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct InsertReceptionCard {
-    backup_text: String,
-    reception_card: ReceptionCard,
+    pub backup_text: String,
+    pub reception_card: ReceptionCard,
 }
 
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
@@ -271,7 +271,7 @@ pub struct InsertVideo {
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct InsertVillaRoomCard {
-    villa_room_card: VillaRoomCard,
+    pub villa_room_card: VillaRoomCard,
 }
 
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
@@ -285,11 +285,11 @@ pub enum VillaRoomType {
 pub struct VillaRoomCard {
     #[serde_as(as = "serde_with::DisplayFromStr")]
     room_id: u32,
-    room_name: String,
+    pub room_name: String,
     room_type: VillaRoomType,
     #[serde_as(as = "serde_with::DisplayFromStr")]
     villa_id: u16,
-    villa_name: String,
+    pub villa_name: String,
     #[serde(with = "http_serde::uri")]
     villa_avatar_url: http::Uri,
     #[serde_as(as = "serde_with::DisplayFromStr")]
@@ -355,4 +355,6 @@ pub enum StructuredContent {
     Vod {
         insert: InsertVod,
     },
+    /// 宽松模式下无法识别的内容，渲染时忽略
+    Unknown(crate::model::lenient::Unrecognized),
 }
@@ -0,0 +1,613 @@
+//! 把帖子的 structured_content 渲染成 Markdown、HTML 或 MediaWiki
+//!
+//! structured_content 是 Quill 的 Delta 格式：文本中的换行符结束一行，
+//! 换行符所在片段的 attributes 是整行的属性（对齐、列表、标题），其余片段的 attributes 是行内样式
+//!
+//! HTML 输出会转义所有文本，链接只保留 http 和 https，可以直接嵌入页面
+
+use std::borrow::Cow;
+
+use crate::model::structured_content::{
+    Align, Color, ColorName, ImageAttributes, StructuredContent, TextAttributes,
+};
+
+/// 渲染为 Markdown，颜色和对齐没有对应语法会被忽略，折叠内容使用 `<details>`
+pub fn markdown(content: &[StructuredContent]) -> String {
+    render(&Markdown, content)
+}
+
+/// 渲染为 HTML 片段
+pub fn html(content: &[StructuredContent]) -> String {
+    render(&Html, content)
+}
+
+/// 渲染为 wikitext，折叠内容使用 mw-collapsible
+pub fn media_wiki(content: &[StructuredContent]) -> String {
+    render(&MediaWiki, content)
+}
+
+/// 提取纯文本，忽略所有样式，折叠内容展开为标题和正文，图片、视频等嵌入内容单独占一行
+pub fn plain_text(content: &[StructuredContent]) -> String {
+    let mut text = String::new();
+    for item in content {
+        match item {
+            StructuredContent::Text { insert, .. } => text += insert,
+            StructuredContent::Fold { insert } => {
+                if !text.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                let title = plain_text(&insert.fold.title);
+                let content = plain_text(&insert.fold.content);
+                for part in [title, content] {
+                    text += part.trim_end_matches('\n');
+                    text.push('\n');
+                }
+            }
+            _ => {
+                if !text.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+            }
+        }
+    }
+    text
+}
+
+/// 转义 HTML 和 XML 中的特殊字符，结果可以用在文本和属性值中
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            char => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+enum Inline<'a> {
+    Text(&'a str, Option<&'a TextAttributes>),
+    Embed(&'a StructuredContent),
+}
+
+struct Line<'a> {
+    inlines: Vec<Inline<'a>>,
+    attributes: Option<&'a TextAttributes>,
+}
+
+/// 按换行符把 Delta 切成行
+fn lines(content: &[StructuredContent]) -> Vec<Line<'_>> {
+    let mut lines = Vec::new();
+    let mut inlines = Vec::new();
+    for item in content {
+        let StructuredContent::Text { insert, attributes } = item else {
+            inlines.push(Inline::Embed(item));
+            continue;
+        };
+        let mut segments = insert.split('\n').peekable();
+        while let Some(segment) = segments.next() {
+            if !segment.is_empty() {
+                inlines.push(Inline::Text(segment, attributes.as_ref()));
+            }
+            if segments.peek().is_some() {
+                lines.push(Line {
+                    inlines: std::mem::take(&mut inlines),
+                    attributes: attributes.as_ref(),
+                });
+            }
+        }
+    }
+    if !inlines.is_empty() {
+        lines.push(Line {
+            inlines,
+            attributes: None,
+        });
+    }
+    lines
+}
+
+fn is_safe(uri: &http::Uri) -> bool {
+    matches!(uri.scheme_str(), Some("http" | "https"))
+}
+
+fn css_color(color: &Color) -> Option<Cow<'static, str>> {
+    match color {
+        Color::Hex(color) => Some(Cow::Owned(color.to_string())),
+        Color::Name(ColorName::Black) => Some(Cow::Borrowed("black")),
+        Color::Name(ColorName::Gray) => Some(Cow::Borrowed("gray")),
+        Color::Name(ColorName::Red) => Some(Cow::Borrowed("red")),
+        Color::Name(ColorName::Blue) => Some(Cow::Borrowed("blue")),
+        // inherit 和 windowtext 都是默认颜色，不需要额外的样式
+        Color::Special(_) => None,
+    }
+}
+
+fn css_align(align: Align) -> &'static str {
+    match align {
+        Align::Center => "center",
+        Align::Justify => "justify",
+        Align::Right => "right",
+    }
+}
+
+/// 把两侧的空白留在标记外面，Markdown 和 wikitext 的强调标记内侧不能是空白
+fn wrap(text: String, open: &str, close: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text;
+    }
+    let begin = text.len() - text.trim_start().len();
+    let end = begin + trimmed.len();
+    format!("{}{open}{trimmed}{close}{}", &text[..begin], &text[end..])
+}
+
+trait Syntax {
+    fn escape(&self, text: &str) -> String;
+    fn bold(&self, text: String) -> String;
+    fn italic(&self, text: String) -> String;
+    fn color(&self, text: String, color: &str) -> String;
+    /// url 已经检查过是 http 或 https
+    fn link(&self, url: &http::Uri, text: String) -> String;
+    fn image(&self, url: &http::Uri, attributes: &ImageAttributes) -> String;
+    fn divider(&self) -> String;
+    fn fold(&self, title: String, content: String) -> String;
+    fn line(&self, text: String, attributes: Option<&TextAttributes>) -> String;
+    fn list(&self, items: Vec<String>) -> String;
+    fn join(&self, blocks: Vec<String>) -> String;
+}
+
+fn styled(syntax: &impl Syntax, text: String, attributes: Option<&TextAttributes>) -> String {
+    let Some(attributes) = attributes else {
+        return text;
+    };
+    let mut text = text;
+    if let Some(link) = attributes.link.as_ref().filter(|link| is_safe(link)) {
+        text = syntax.link(link, text);
+    }
+    if attributes.bold {
+        text = syntax.bold(text);
+    }
+    if attributes.italic {
+        text = syntax.italic(text);
+    }
+    if let Some(color) = attributes.color.as_ref().and_then(css_color) {
+        text = syntax.color(text, &color);
+    }
+    text
+}
+
+fn link_or_text(syntax: &impl Syntax, url: &http::Uri, text: String) -> String {
+    match is_safe(url) {
+        true => syntax.link(url, text),
+        false => text,
+    }
+}
+
+fn inline(syntax: &impl Syntax, piece: &Inline) -> String {
+    let item = match piece {
+        Inline::Text(text, attributes) => {
+            return styled(syntax, syntax.escape(text), *attributes);
+        }
+        Inline::Embed(item) => item,
+    };
+    match item {
+        StructuredContent::Text { .. } => unreachable!(),
+        StructuredContent::Divider { .. } => syntax.divider(),
+        StructuredContent::Image { insert, attributes } => match &insert.image {
+            Some(url) if is_safe(url) => syntax.image(url, attributes),
+            _ => String::new(),
+        },
+        StructuredContent::LinkCard { insert } => {
+            let card = &insert.link_card;
+            link_or_text(syntax, &card.landing_url, syntax.escape(&card.title))
+        }
+        StructuredContent::Lottery { insert, attributes } => styled(
+            syntax,
+            syntax.escape(&insert.backup_text),
+            attributes.as_ref(),
+        ),
+        StructuredContent::Fold { insert } => {
+            let title = (lines(&insert.fold.title).iter())
+                .map(|line| line.inlines.iter().map(|item| inline(syntax, item)))
+                .map(String::from_iter)
+                .collect::<Vec<_>>()
+                .join(" ");
+            syntax.fold(title, render(syntax, &insert.fold.content))
+        }
+        StructuredContent::Mention { insert, attributes } => {
+            let text = String::from("@") + &insert.mention.nickname;
+            styled(syntax, syntax.escape(&text), attributes.as_ref())
+        }
+        StructuredContent::ReceptionCard { insert } => syntax.escape(&insert.reception_card.name),
+        StructuredContent::Video { insert } => {
+            link_or_text(syntax, &insert.video, syntax.escape("视频"))
+        }
+        StructuredContent::VillaRoomCard { insert } => {
+            let card = &insert.villa_room_card;
+            syntax.escape(&format!("{} {}", card.villa_name, card.room_name))
+        }
+        StructuredContent::Vod { insert } => {
+            // 选分辨率最高的那一个
+            let resolution = (insert.vod.resolutions.iter()).max_by_key(|res| res.width);
+            match resolution {
+                Some(resolution) => link_or_text(syntax, &resolution.url, syntax.escape("视频")),
+                None => link_or_text(syntax, &insert.vod.cover, syntax.escape("视频")),
+            }
+        }
+        StructuredContent::Unknown(_) => String::new(),
+    }
+}
+
+fn render(syntax: &impl Syntax, content: &[StructuredContent]) -> String {
+    let mut blocks = Vec::new();
+    let mut list = Vec::new();
+    for line in lines(content) {
+        let text = line
+            .inlines
+            .iter()
+            .map(|item| inline(syntax, item))
+            .collect::<String>();
+        if line.attributes.is_some_and(|attrs| attrs.list.is_some()) {
+            list.push(text);
+            continue;
+        }
+        if !list.is_empty() {
+            blocks.push(syntax.list(std::mem::take(&mut list)));
+        }
+        // 分隔线和折叠内容是块级元素，单独成行时不再包一层段落
+        let is_block = matches!(
+            line.inlines.as_slice(),
+            [Inline::Embed(
+                StructuredContent::Divider { .. } | StructuredContent::Fold { .. }
+            )]
+        );
+        match is_block {
+            true => blocks.push(text),
+            false => blocks.push(syntax.line(text, line.attributes)),
+        }
+    }
+    if !list.is_empty() {
+        blocks.push(syntax.list(list));
+    }
+    syntax.join(blocks)
+}
+
+struct Markdown;
+
+impl Markdown {
+    /// 行首的这些字符会被当作列表、Setext 标题或者缩进代码块
+    /// `#`、`>` 等在 escape 中已经转义
+    fn escape_line_start(text: String) -> String {
+        match text.chars().next() {
+            Some('-' | '+' | '=') => return String::from("\\") + &text,
+            Some(char @ (' ' | '\t')) => {
+                return format!("&#{};{}", char as u32, &text[char.len_utf8()..]);
+            }
+            _ => (),
+        }
+        // 有序列表：1. 或者 1)
+        let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        match text[digits..].starts_with(['.', ')']) {
+            true if digits > 0 => format!("{}\\{}", &text[..digits], &text[digits..]),
+            _ => text,
+        }
+    }
+}
+
+impl Syntax for Markdown {
+    fn escape(&self, text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for char in text.chars() {
+            if matches!(
+                char,
+                '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~'
+            ) {
+                escaped.push('\\');
+            }
+            escaped.push(char);
+        }
+        escaped
+    }
+
+    fn bold(&self, text: String) -> String {
+        wrap(text, "**", "**")
+    }
+
+    fn italic(&self, text: String) -> String {
+        wrap(text, "*", "*")
+    }
+
+    fn color(&self, text: String, _: &str) -> String {
+        text
+    }
+
+    fn link(&self, url: &http::Uri, text: String) -> String {
+        format!("[{text}]({})", url.to_string().replace(')', "%29"))
+    }
+
+    fn image(&self, url: &http::Uri, _: &ImageAttributes) -> String {
+        format!("![]({})", url.to_string().replace(')', "%29"))
+    }
+
+    fn divider(&self) -> String {
+        String::from("---")
+    }
+
+    fn fold(&self, title: String, content: String) -> String {
+        format!("<details>\n<summary>{title}</summary>\n\n{content}\n\n</details>")
+    }
+
+    fn line(&self, text: String, attributes: Option<&TextAttributes>) -> String {
+        let text = Markdown::escape_line_start(text);
+        match attributes.and_then(|attrs| attrs.header) {
+            Some(level) if !text.is_empty() => {
+                "#".repeat(level.get().min(6) as usize) + " " + &text
+            }
+            _ => text,
+        }
+    }
+
+    fn list(&self, items: Vec<String>) -> String {
+        let items =
+            (items.into_iter()).map(|item| String::from("- ") + &Markdown::escape_line_start(item));
+        items.collect::<Vec<_>>().join("\n")
+    }
+
+    fn join(&self, blocks: Vec<String>) -> String {
+        let blocks = blocks.into_iter().filter(|block| !block.trim().is_empty());
+        blocks.collect::<Vec<_>>().join("\n\n")
+    }
+}
+
+struct Html;
+
+impl Html {
+    fn escape_attribute(&self, url: &http::Uri) -> String {
+        self.escape(&url.to_string())
+    }
+}
+
+impl Syntax for Html {
+    fn escape(&self, text: &str) -> String {
+        escape_html(text)
+    }
+
+    fn bold(&self, text: String) -> String {
+        format!("<strong>{text}</strong>")
+    }
+
+    fn italic(&self, text: String) -> String {
+        format!("<em>{text}</em>")
+    }
+
+    fn color(&self, text: String, color: &str) -> String {
+        format!(r#"<span style="color:{color}">{text}</span>"#)
+    }
+
+    fn link(&self, url: &http::Uri, text: String) -> String {
+        let url = self.escape_attribute(url);
+        format!(r#"<a href="{url}" rel="noopener noreferrer nofollow">{text}</a>"#)
+    }
+
+    fn image(&self, url: &http::Uri, attributes: &ImageAttributes) -> String {
+        let mut html = format!(r#"<img src="{}" alt="""#, self.escape_attribute(url));
+        if attributes.width != 0 && attributes.height != 0 {
+            html += &format!(
+                r#" width="{}" height="{}""#,
+                attributes.width, attributes.height
+            );
+        }
+        html + r#" loading="lazy">"#
+    }
+
+    fn divider(&self) -> String {
+        String::from("<hr>")
+    }
+
+    fn fold(&self, title: String, content: String) -> String {
+        format!("<details>\n<summary>{title}</summary>\n{content}\n</details>")
+    }
+
+    fn line(&self, text: String, attributes: Option<&TextAttributes>) -> String {
+        let tag = match attributes.and_then(|attrs| attrs.header) {
+            Some(level) => Cow::Owned(format!("h{}", level.get().min(6))),
+            None => Cow::Borrowed("p"),
+        };
+        let style = match attributes.and_then(|attrs| attrs.align) {
+            Some(align) => format!(r#" style="text-align:{}""#, css_align(align)),
+            None => String::new(),
+        };
+        let text = if text.is_empty() { "<br>".into() } else { text };
+        format!("<{tag}{style}>{text}</{tag}>")
+    }
+
+    fn list(&self, items: Vec<String>) -> String {
+        let items = items.into_iter().map(|item| format!("<li>{item}</li>\n"));
+        format!("<ul>\n{}</ul>", items.collect::<String>())
+    }
+
+    fn join(&self, blocks: Vec<String>) -> String {
+        blocks.join("\n")
+    }
+}
+
+struct MediaWiki;
+
+impl Syntax for MediaWiki {
+    fn escape(&self, text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for char in text.chars() {
+            match char {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                char => escaped.push(char),
+            }
+        }
+        // 方括号、花括号、竖线、连续的单引号和波浪线都可能被解析成 wiki 语法
+        let is_markup = escaped.contains(['[', ']', '{', '}', '|'])
+            || escaped.contains("''")
+            || escaped.contains("~~~")
+            || escaped.contains("__");
+        match is_markup {
+            true => format!("<nowiki>{escaped}</nowiki>"),
+            false => escaped,
+        }
+    }
+
+    fn bold(&self, text: String) -> String {
+        wrap(text, "'''", "'''")
+    }
+
+    fn italic(&self, text: String) -> String {
+        wrap(text, "''", "''")
+    }
+
+    fn color(&self, text: String, color: &str) -> String {
+        format!(r#"<span style="color:{color}">{text}</span>"#)
+    }
+
+    fn link(&self, url: &http::Uri, text: String) -> String {
+        format!("[{url} {text}]")
+    }
+
+    /// 外链图片需要站点开启 $wgAllowExternalImages，否则显示为链接
+    fn image(&self, url: &http::Uri, _: &ImageAttributes) -> String {
+        url.to_string()
+    }
+
+    fn divider(&self) -> String {
+        String::from("----")
+    }
+
+    fn fold(&self, title: String, content: String) -> String {
+        format!(
+            "<div class=\"mw-collapsible mw-collapsed\">\n<div>'''{title}'''</div>\n<div \
+             class=\"mw-collapsible-content\">\n{content}\n</div>\n</div>"
+        )
+    }
+
+    fn line(&self, text: String, attributes: Option<&TextAttributes>) -> String {
+        // 行首的这些字符会被当作列表、缩进、预格式化等语法
+        let mut text = match text.chars().next() {
+            Some(char @ (' ' | '*' | '#' | ':' | ';' | '-' | '=' | '{' | '|')) => {
+                format!("&#{};{}", char as u32, &text[char.len_utf8()..])
+            }
+            _ => text,
+        };
+        if let Some(level) = attributes.and_then(|attrs| attrs.header) {
+            if !text.is_empty() {
+                let marks = "=".repeat(level.get().min(5) as usize + 1);
+                text = format!("{marks} {text} {marks}");
+            }
+        }
+        if let Some(align) = attributes.and_then(|attrs| attrs.align) {
+            text = format!(
+                r#"<div style="text-align:{}">{text}</div>"#,
+                css_align(align)
+            );
+        }
+        text
+    }
+
+    fn list(&self, items: Vec<String>) -> String {
+        let items = items.into_iter().map(|item| String::from("* ") + &item);
+        items.collect::<Vec<_>>().join("\n")
+    }
+
+    fn join(&self, blocks: Vec<String>) -> String {
+        let blocks = blocks.into_iter().filter(|block| !block.trim().is_empty());
+        blocks.collect::<Vec<_>>().join("\n\n")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::structured_content::StructuredContent;
+
+    fn content(json: &str) -> Vec<StructuredContent> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    const DELTA: &str = r##"[
+        {"insert": "标题"},
+        {"insert": "\n", "attributes": {"header": 2}},
+        {"insert": "普通 "},
+        {"insert": "加粗", "attributes": {"bold": true}},
+        {"insert": " 和 "},
+        {"insert": "<红色>", "attributes": {"color": "#ff0000"}},
+        {"insert": "\n第一项"},
+        {"insert": "\n", "attributes": {"list": "bullet"}},
+        {"insert": "第二项"},
+        {"insert": "\n", "attributes": {"list": "bullet"}},
+        {"insert": "居中"},
+        {"insert": "\n", "attributes": {"align": "center"}},
+        {"insert": "链接", "attributes": {"link": "https://www.miyoushe.com/sr/"}},
+        {"insert": "\n"},
+        {"insert": {"divider": "line_1"}},
+        {"insert": "\n"}
+    ]"##;
+
+    #[test]
+    fn markdown() {
+        assert_eq!(
+            super::markdown(&content(DELTA)),
+            "## 标题\n\n\
+             普通 **加粗** 和 \\<红色\\>\n\n\
+             - 第一项\n- 第二项\n\n\
+             居中\n\n\
+             [链接](https://www.miyoushe.com/sr/)\n\n\
+             ---"
+        );
+    }
+
+    #[test]
+    fn html() {
+        assert_eq!(
+            super::html(&content(DELTA)),
+            "<h2>标题</h2>\n\
+             <p>普通 <strong>加粗</strong> 和 <span style=\"color:#ff0000ff\">&lt;红色&gt;</span></p>\n\
+             <ul>\n<li>第一项</li>\n<li>第二项</li>\n</ul>\n\
+             <p style=\"text-align:center\">居中</p>\n\
+             <p><a href=\"https://www.miyoushe.com/sr/\" rel=\"noopener noreferrer nofollow\">链接</a></p>\n\
+             <hr>"
+        );
+    }
+
+    #[test]
+    fn media_wiki() {
+        assert_eq!(
+            super::media_wiki(&content(DELTA)),
+            "=== 标题 ===\n\n\
+             普通 '''加粗''' 和 <span style=\"color:#ff0000ff\">&lt;红色&gt;</span>\n\n\
+             * 第一项\n* 第二项\n\n\
+             <div style=\"text-align:center\">居中</div>\n\n\
+             [https://www.miyoushe.com/sr/ 链接]\n\n\
+             ----"
+        );
+    }
+
+    #[test]
+    fn markdown_line_start() {
+        let delta = r#"[
+            {"insert": "- 不是列表\n+ 也不是\n1. 不是有序列表\n2) 也不是\n=\n  缩进\n1.5 倍\n"},
+            {"insert": "-1"},
+            {"insert": "\n", "attributes": {"list": "bullet"}}
+        ]"#;
+        assert_eq!(
+            super::markdown(&content(delta)),
+            "\\- 不是列表\n\n\\+ 也不是\n\n1\\. 不是有序列表\n\n2\\) 也不是\n\n\\=\n\n&#32; \
+             缩进\n\n1\\.5 倍\n\n- \\-1"
+        );
+    }
+
+    #[test]
+    fn unsafe_link() {
+        let delta = r#"[{"insert": "x", "attributes": {"link": "javascript:alert(1)"}}]"#;
+        assert_eq!(super::html(&content(delta)), "<p>x</p>");
+    }
+}
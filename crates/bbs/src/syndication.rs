@@ -0,0 +1,408 @@
+//! 把官方账号的帖子生成 Atom 和 RSS 订阅
+//!
+//! 条目保存在本地的状态文件（JSON）中，每次更新只从最新的帖子开始翻页，
+//! 遇到连续若干篇没有变化的帖子时停止，生成订阅时不需要访问网络
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use bbs::syndication::{FeedMeta, FeedState, UpdateOptions};
+//!
+//! let client = bbs::client::Client::new();
+//! let mut state = FeedState::load("sr.json")?;
+//! state.update(&client.sr_user_post(), &UpdateOptions::default()).await?;
+//! state.save("sr.json")?;
+//! let meta = FeedMeta::new("崩坏：星穹铁道官方", "https://www.miyoushe.com/sr/");
+//! std::fs::write("sr.atom", state.atom(&meta))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::Write;
+use std::path::Path;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::api::user_post::Change;
+use crate::model::user_post::UserPost;
+use crate::model::GameId;
+use crate::render::escape_html;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("io error")]
+    Io(#[from] std::io::Error),
+    #[error("state error")]
+    State(#[from] serde_json::Error),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// 订阅中的一篇帖子
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct FeedEntry {
+    pub post_id: u32,
+    pub title: String,
+    pub link: String,
+    pub author: String,
+    /// 渲染好的 HTML 正文
+    pub content: String,
+    #[serde(default, with = "http_serde::option::uri")]
+    pub cover: Option<http::Uri>,
+    /// 话题名
+    pub topics: Vec<String>,
+    /// 发帖时间
+    pub published: DateTime<Utc>,
+    /// 最后编辑的时间，没有编辑过时和发帖时间相同
+    pub updated: DateTime<Utc>,
+    /// 列表中的 last_modify_time，用来判断帖子有没有变化
+    pub last_modify_time: DateTime<Utc>,
+}
+
+/// 帖子在网页版上的链接
+pub fn article_link(game_id: GameId, post_id: u32) -> String {
+    let game = match game_id {
+        GameId::HonkaiImpact3 => "bh3",
+        GameId::GenshinImpact => "ys",
+        GameId::GunsGirlSchoolDayZ => "bh2",
+        GameId::TearsOfThemis => "wd",
+        GameId::Villa => "dby",
+        GameId::HonkaiStarRail => "sr",
+        GameId::ZenlessZoneZero => "zzz",
+    };
+    format!("https://www.miyoushe.com/{game}/article/{post_id}")
+}
+
+impl FeedEntry {
+    pub fn from_post(post: &UserPost) -> Self {
+        let content = match &post.post.structured_content {
+            Some(content) => crate::render::html(content),
+            None => format!("<p>{}</p>", escape_html(&post.post.content)),
+        };
+        let cover = (post.cover.as_ref().map(|cover| &cover.url)).or(post.post.cover.as_ref());
+        let published = post.post.created_at;
+        Self {
+            post_id: post.post.post_id,
+            title: post.post.subject.clone(),
+            link: article_link(post.post.game_id, post.post.post_id),
+            author: post.user.nickname.clone(),
+            content,
+            cover: cover.cloned(),
+            topics: post.topics.iter().map(|topic| topic.name.clone()).collect(),
+            published,
+            // 没有编辑过的帖子 updated_at 可能为 0
+            updated: post.post.updated_at.max(published),
+            last_modify_time: post.last_modify_time,
+        }
+    }
+}
+
+/// 订阅本身的信息
+#[derive(Clone, Debug)]
+pub struct FeedMeta {
+    pub title: String,
+    /// 网页的链接，比如 https://www.miyoushe.com/sr/
+    pub link: String,
+    /// 订阅文件自身的链接，Atom 推荐填写
+    pub self_link: Option<String>,
+    pub description: String,
+}
+
+impl FeedMeta {
+    pub fn new(title: impl Into<String>, link: impl Into<String>) -> Self {
+        let title = title.into();
+        Self {
+            description: title.clone(),
+            title,
+            link: link.into(),
+            self_link: None,
+        }
+    }
+
+    pub fn self_link(mut self, self_link: impl Into<String>) -> Self {
+        self.self_link = Some(self_link.into());
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct UpdateOptions {
+    /// 连续遇到多少篇没有变化的帖子后停止翻页，置顶的帖子总是排在最前面，所以默认不是遇到第一篇就停
+    pub stop_after_unchanged: usize,
+    /// 新增或有变化的帖子是否请求 getPostFull 获取完整正文，列表中的正文可能不完整
+    pub full_content: bool,
+    /// 最多保留的条目数，超出时丢弃最早发布的，也是一次更新最多读取的帖子数
+    pub max_entries: usize,
+}
+
+impl Default for UpdateOptions {
+    fn default() -> Self {
+        Self {
+            stop_after_unchanged: 5,
+            full_content: true,
+            max_entries: 50,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct UpdateReport {
+    pub added: Vec<u32>,
+    pub updated: Vec<u32>,
+    pub removed: Vec<u32>,
+}
+
+/// 保存在本地的订阅状态，条目按发帖时间从新到旧排列
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct FeedState {
+    pub entries: Vec<FeedEntry>,
+}
+
+fn mime_type(url: &http::Uri) -> &'static str {
+    let extension = Path::new(url.path())
+        .extension()
+        .and_then(|ext| ext.to_str());
+    match extension.map(str::to_ascii_lowercase).as_deref() {
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        _ => "image/jpeg",
+    }
+}
+
+/// 转义特殊字符，同时去掉 XML 1.0 不允许出现的控制字符（比如 U+0008），否则阅读器会拒绝整个订阅
+fn escape_xml(text: &str) -> String {
+    let is_allowed = |char: &char| match *char {
+        '\t' | '\n' | '\r' => true,
+        '\u{FFFE}' | '\u{FFFF}' => false,
+        char => char >= ' ',
+    };
+    escape_html(&text.chars().filter(is_allowed).collect::<String>())
+}
+
+fn rfc3339(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// 正文前加上封面，阅读器一般不会显示 enclosure 中的图片
+fn entry_html(entry: &FeedEntry) -> String {
+    match &entry.cover {
+        Some(cover) => format!(
+            "<p><img src=\"{}\"></p>{}",
+            escape_html(&cover.to_string()),
+            entry.content
+        ),
+        None => entry.content.clone(),
+    }
+}
+
+impl FeedState {
+    /// 文件不存在时返回空的状态
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        crate::json_file::load(path)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        crate::json_file::save(path, self)
+    }
+
+    /// 写入一篇帖子，被删除的帖子会从订阅中移除
+    pub fn upsert(&mut self, post: &UserPost) {
+        let post_id = post.post.post_id;
+        self.entries.retain(|entry| entry.post_id != post_id);
+        if post.post.is_deleted == 0 {
+            self.entries.push(FeedEntry::from_post(post));
+        }
+        (self.entries).sort_by_key(|entry| std::cmp::Reverse((entry.published, entry.post_id)));
+    }
+
+    /// 从某个账号的帖子列表增量更新
+    pub async fn update(
+        &mut self,
+        api: &crate::api::UserPostAPI,
+        options: &UpdateOptions,
+    ) -> crate::api::Result<UpdateReport> {
+        let mut report = UpdateReport::default();
+        // 第一次更新时状态为空，最多翻到能填满订阅为止，不会把账号的全部帖子都翻一遍
+        let mut posts = (api.incremental(options.stop_after_unchanged, options.full_content))
+            .limit(options.max_entries);
+        loop {
+            let next = posts.next(|post| -> crate::api::Result<Change> {
+                let post_id = post.post.post_id;
+                let is_deleted = post.post.is_deleted != 0;
+                let stored = self.entries.iter().find(|entry| entry.post_id == post_id);
+                Ok(match stored {
+                    Some(_) if is_deleted => {
+                        report.removed.push(post_id);
+                        Change::Changed
+                    }
+                    Some(entry) if entry.last_modify_time == post.last_modify_time => {
+                        Change::Unchanged
+                    }
+                    Some(_) => {
+                        report.updated.push(post_id);
+                        Change::Changed
+                    }
+                    None if is_deleted => Change::Ignored,
+                    None => {
+                        report.added.push(post_id);
+                        Change::Changed
+                    }
+                })
+            });
+            let Some((post, _)) = next.await? else {
+                break;
+            };
+            self.upsert(&post);
+        }
+        if self.entries.len() > options.max_entries {
+            self.entries.truncate(options.max_entries);
+        }
+        Ok(report)
+    }
+
+    /// 最近一次更新的时间，没有条目时为 Unix 纪元
+    pub fn updated(&self) -> DateTime<Utc> {
+        let updated = self.entries.iter().map(|entry| entry.updated).max();
+        updated.unwrap_or_default()
+    }
+
+    /// 生成 Atom 1.0 订阅
+    pub fn atom(&self, meta: &FeedMeta) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        xml += "<feed xmlns=\"http://www.w3.org/2005/Atom\">\n";
+        let id = meta.self_link.as_ref().unwrap_or(&meta.link);
+        writeln!(xml, "  <id>{}</id>", escape_xml(id)).unwrap();
+        writeln!(xml, "  <title>{}</title>", escape_xml(&meta.title)).unwrap();
+        writeln!(
+            xml,
+            "  <subtitle>{}</subtitle>",
+            escape_xml(&meta.description)
+        )
+        .unwrap();
+        writeln!(xml, "  <updated>{}</updated>", rfc3339(&self.updated())).unwrap();
+        writeln!(
+            xml,
+            "  <link rel=\"alternate\" href=\"{}\"/>",
+            escape_xml(&meta.link)
+        )
+        .unwrap();
+        if let Some(self_link) = &meta.self_link {
+            writeln!(
+                xml,
+                "  <link rel=\"self\" href=\"{}\"/>",
+                escape_xml(self_link)
+            )
+            .unwrap();
+        }
+        for entry in &self.entries {
+            xml += "  <entry>\n";
+            writeln!(xml, "    <id>{}</id>", escape_xml(&entry.link)).unwrap();
+            writeln!(xml, "    <title>{}</title>", escape_xml(&entry.title)).unwrap();
+            writeln!(
+                xml,
+                "    <link rel=\"alternate\" href=\"{}\"/>",
+                escape_xml(&entry.link)
+            )
+            .unwrap();
+            writeln!(
+                xml,
+                "    <published>{}</published>",
+                rfc3339(&entry.published)
+            )
+            .unwrap();
+            writeln!(xml, "    <updated>{}</updated>", rfc3339(&entry.updated)).unwrap();
+            writeln!(
+                xml,
+                "    <author><name>{}</name></author>",
+                escape_xml(&entry.author)
+            )
+            .unwrap();
+            for topic in &entry.topics {
+                writeln!(xml, "    <category term=\"{}\"/>", escape_xml(topic)).unwrap();
+            }
+            if let Some(cover) = &entry.cover {
+                writeln!(
+                    xml,
+                    "    <link rel=\"enclosure\" type=\"{}\" href=\"{}\"/>",
+                    mime_type(cover),
+                    escape_xml(&cover.to_string())
+                )
+                .unwrap();
+            }
+            writeln!(
+                xml,
+                "    <content type=\"html\">{}</content>",
+                escape_xml(&entry_html(entry))
+            )
+            .unwrap();
+            xml += "  </entry>\n";
+        }
+        xml += "</feed>\n";
+        xml
+    }
+
+    /// 生成 RSS 2.0 订阅，RSS 没有条目的编辑时间，pubDate 使用发帖时间
+    pub fn rss(&self, meta: &FeedMeta) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        xml += "<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\">\n";
+        xml += "  <channel>\n";
+        writeln!(xml, "    <title>{}</title>", escape_xml(&meta.title)).unwrap();
+        writeln!(xml, "    <link>{}</link>", escape_xml(&meta.link)).unwrap();
+        writeln!(
+            xml,
+            "    <description>{}</description>",
+            escape_xml(&meta.description)
+        )
+        .unwrap();
+        if let Some(self_link) = &meta.self_link {
+            writeln!(
+                xml,
+                "    <atom:link rel=\"self\" type=\"application/rss+xml\" href=\"{}\"/>",
+                escape_xml(self_link)
+            )
+            .unwrap();
+        }
+        let updated = self.updated().to_rfc2822();
+        writeln!(xml, "    <lastBuildDate>{updated}</lastBuildDate>").unwrap();
+        for entry in &self.entries {
+            xml += "    <item>\n";
+            writeln!(xml, "      <title>{}</title>", escape_xml(&entry.title)).unwrap();
+            writeln!(xml, "      <link>{}</link>", escape_xml(&entry.link)).unwrap();
+            writeln!(
+                xml,
+                "      <guid isPermaLink=\"true\">{}</guid>",
+                escape_xml(&entry.link)
+            )
+            .unwrap();
+            let published = entry.published.to_rfc2822();
+            writeln!(xml, "      <pubDate>{published}</pubDate>").unwrap();
+            for topic in &entry.topics {
+                writeln!(xml, "      <category>{}</category>", escape_xml(topic)).unwrap();
+            }
+            if let Some(cover) = &entry.cover {
+                // RSS 要求 length，不知道大小时按惯例填 0
+                writeln!(
+                    xml,
+                    "      <enclosure url=\"{}\" type=\"{}\" length=\"0\"/>",
+                    escape_xml(&cover.to_string()),
+                    mime_type(cover)
+                )
+                .unwrap();
+            }
+            writeln!(
+                xml,
+                "      <description>{}</description>",
+                escape_xml(&entry_html(entry))
+            )
+            .unwrap();
+            xml += "    </item>\n";
+        }
+        xml += "  </channel>\n</rss>\n";
+        xml
+    }
+}
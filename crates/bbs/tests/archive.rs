@@ -0,0 +1,214 @@
+use bbs::archive::{Archive, StoreOutcome, SyncOptions};
+use bbs::client::Client;
+use bbs::model::user_post::UserPost;
+use serde_json::{json, Value};
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+const UID: u32 = 288909600;
+
+/// 以录制的 userPost 中的第一条帖子为模板
+fn post_value(post_id: u32, subject: &str, last_modify_time: i64) -> Value {
+    let fixture = (bbs::fixture::load_dir(FIXTURES).unwrap().into_iter())
+        .find(|fixture| fixture.endpoint == "/post/wapi/userPost")
+        .unwrap();
+    let body: Value = serde_json::from_slice(&fixture.body).unwrap();
+    let mut post = body["data"]["list"][0].clone();
+    post["post"]["post_id"] = json!(post_id.to_string());
+    post["post"]["subject"] = json!(subject);
+    post["last_modify_time"] = json!(last_modify_time);
+    post
+}
+
+fn post(post_id: u32, subject: &str, last_modify_time: i64) -> UserPost {
+    serde_json::from_value(post_value(post_id, subject, last_modify_time)).unwrap()
+}
+
+fn ok(data: Value) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({"retcode": 0, "message": "OK", "data": data}))
+}
+
+fn user_post_list(posts: &[Value]) -> ResponseTemplate {
+    ok(json!({"list": posts, "is_last": true, "next_offset": "0"}))
+}
+
+fn client(server: &MockServer) -> Client {
+    Client::builder().base_url(server.uri()).build().unwrap()
+}
+
+#[test]
+fn store_revisions() {
+    let mut archive = Archive::open_in_memory().unwrap();
+    let first = post(1, "公告", 100);
+    assert_eq!(
+        archive.store(&first, false).unwrap(),
+        StoreOutcome::Inserted
+    );
+    assert_eq!(
+        archive.store(&first, true).unwrap(),
+        StoreOutcome::Unchanged
+    );
+    let mut second = post(1, "公告（更新）", 200);
+    assert_eq!(archive.store(&second, true).unwrap(), StoreOutcome::Updated);
+    // 删除状态变化也算新版本
+    second.post.is_deleted = 1;
+    assert_eq!(archive.store(&second, true).unwrap(), StoreOutcome::Updated);
+
+    let revisions = archive.revisions(1).unwrap();
+    let summary = revisions
+        .iter()
+        .map(|rev| {
+            let time = rev.last_modify_time.timestamp();
+            (rev.revision, time, rev.is_deleted, rev.is_full)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        [
+            (1, 100, false, false),
+            (2, 200, false, true),
+            (3, 200, true, true)
+        ]
+    );
+    assert_eq!(revisions[0].post.post.subject, "公告");
+
+    // 最新版本原样从 JSON 读回
+    let stored = archive.post(1).unwrap().unwrap();
+    assert_eq!(
+        serde_json::to_value(&stored).unwrap(),
+        serde_json::to_value(&second).unwrap()
+    );
+    assert!(archive.post(2).unwrap().is_none());
+    assert_eq!(archive.deleted_posts().unwrap().len(), 1);
+}
+
+#[test]
+fn search_escapes_wildcards() {
+    let mut archive = Archive::open_in_memory().unwrap();
+    for (post_id, subject) in [(1, "100% 掉率"), (2, "100 掉率"), (3, "a_b"), (4, "axb")] {
+        archive.store(&post(post_id, subject, 100), false).unwrap();
+    }
+    let ids = |keyword| {
+        let posts = archive.search(keyword).unwrap();
+        posts
+            .iter()
+            .map(|post| post.post.post_id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids("100%"), [1]);
+    assert_eq!(ids("a_b"), [3]);
+    assert_eq!(ids("掉率").len(), 2);
+    assert!(ids("\\").is_empty());
+}
+
+#[tokio::test]
+async fn sync_stops_after_unchanged() {
+    let mut archive = Archive::open_in_memory().unwrap();
+    for post_id in [2, 3, 4] {
+        archive.store(&post(post_id, "旧帖", 100), true).unwrap();
+    }
+    let listing = [1, 2, 3, 4, 5].map(|post_id| post_value(post_id, "旧帖", 100));
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/userPost"))
+        .and(query_param("uid", UID.to_string()))
+        .respond_with(user_post_list(&listing))
+        .expect(1)
+        .mount(&server)
+        .await;
+    // 只有新帖子请求完整正文，修改时间以列表中的为准
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/getPostFull"))
+        .and(query_param("post_id", "1"))
+        .respond_with(ok(json!({"post": post_value(1, "新帖", 150)})))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let options = SyncOptions {
+        stop_after_unchanged: 2,
+        full_content: true,
+    };
+    let report = archive
+        .sync_user(&client(&server), UID, &options)
+        .await
+        .unwrap();
+    assert_eq!(report.inserted, [1]);
+    assert!(report.updated.is_empty());
+    assert_eq!(report.unchanged, 2);
+    let revisions = archive.revisions(1).unwrap();
+    assert!(revisions[0].is_full);
+    assert_eq!(revisions[0].post.post.subject, "新帖");
+    assert_eq!(revisions[0].last_modify_time.timestamp(), 100);
+    // 连续两篇没有变化后停止，之后的帖子不会写入
+    assert!(archive.post(5).unwrap().is_none());
+}
+
+#[tokio::test]
+async fn check_deleted_posts() {
+    let mut archive = Archive::open_in_memory().unwrap();
+    for post_id in [1, 2, 3, 4] {
+        archive.store(&post(post_id, "帖子", 100), true).unwrap();
+    }
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/userPost"))
+        .respond_with(user_post_list(&[post_value(1, "帖子", 100)]))
+        .mount(&server)
+        .await;
+    // 2 返回已删除的帖子，3 已经无法访问，4 仍然可以访问
+    let mut deleted = post_value(2, "帖子", 300);
+    deleted["post"]["is_deleted"] = json!(1);
+    deleted["post"]["deleted_at"] = json!(1704042000);
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/getPostFull"))
+        .and(query_param("post_id", "2"))
+        .respond_with(ok(json!({"post": deleted})))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/getPostFull"))
+        .and(query_param("post_id", "3"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "retcode": -1,
+            "message": "帖子不存在",
+            "data": null,
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/getPostFull"))
+        .and(query_param("post_id", "4"))
+        .respond_with(ok(json!({"post": post_value(4, "帖子", 100)})))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let client = client(&server);
+    let mut marked = archive.check_deleted(&client, UID).await.unwrap();
+    marked.sort();
+    assert_eq!(marked, [2, 3]);
+
+    let post2 = archive.post(2).unwrap().unwrap();
+    assert_eq!(post2.post.deleted_at.timestamp(), 1704042000);
+    let revisions = archive.revisions(3).unwrap();
+    assert_eq!(revisions.len(), 2);
+    assert!(revisions[1].is_deleted);
+    assert!(revisions[1].is_full);
+    let post3 = &revisions[1].post.post;
+    assert_eq!(post3.is_deleted, 1);
+    assert!(post3.deleted_at > revisions[0].post.post.deleted_at);
+    assert!(archive.post(4).unwrap().unwrap().post.is_deleted == 0);
+    assert_eq!(archive.deleted_posts().unwrap().len(), 2);
+
+    // 已经标记删除的帖子不再请求，仍然可以访问的 4 每次都会重新检查
+    // 各接口的请求次数在 server 析构时检查
+    assert!(archive
+        .check_deleted(&client, UID)
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,169 @@
+#![feature(async_iterator)]
+
+use std::async_iter::AsyncIterator;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+use bbs::client::Client;
+use wiremock::matchers::{header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const EMPTY_LIST: &str =
+    r#"{"retcode":0,"message":"OK","data":{"list":[],"last_id":"","is_last":true}}"#;
+const RATE_LIMITED: &str = r#"{"retcode":-110,"message":"访问过于频繁","data":null}"#;
+
+fn client(server: &MockServer) -> bbs::client::ClientBuilder {
+    Client::builder()
+        .base_url(server.uri())
+        .retry_delay(Duration::from_millis(10))
+}
+
+/// 请求一页楼中楼，返回楼层数
+async fn sub_replies(client: &Client) -> Result<usize, bbs::api::Error> {
+    let mut replies = client.post(1).sub_replies(2);
+    let mut count = 0;
+    while let Some(reply) = std::future::poll_fn(|cx| Pin::new(&mut replies).poll_next(cx)).await {
+        reply?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+async fn mount_empty_list(server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/getSubReplies"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(EMPTY_LIST))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn base_url_and_headers() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/getSubReplies"))
+        .and(query_param("post_id", "1"))
+        .and(query_param("floor_id", "2"))
+        .and(header("User-Agent", "sr-wiki"))
+        .and(header("Referer", "https://www.miyoushe.com/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(EMPTY_LIST))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = client(&server)
+        .user_agent("sr-wiki")
+        .header(
+            reqwest::header::REFERER,
+            reqwest::header::HeaderValue::from_static("https://www.miyoushe.com/"),
+        )
+        .build()
+        .unwrap();
+    assert_eq!(sub_replies(&client).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn retry_server_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    mount_empty_list(&server).await;
+    let client = client(&server).build().unwrap();
+    assert_eq!(sub_replies(&client).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn retry_rate_limited_retcode() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(RATE_LIMITED))
+        .up_to_n_times(2)
+        .expect(2)
+        .mount(&server)
+        .await;
+    mount_empty_list(&server).await;
+    let client = client(&server).build().unwrap();
+    assert_eq!(sub_replies(&client).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn give_up_after_max_retries() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(RATE_LIMITED))
+        .expect(3)
+        .mount(&server)
+        .await;
+    let client = client(&server).max_retries(2).build().unwrap();
+    let err = sub_replies(&client).await.unwrap_err();
+    assert!(matches!(
+        err,
+        bbs::api::Error::Business { retcode: -110, .. }
+    ));
+}
+
+#[tokio::test]
+async fn rate_limit_shared_between_clones() {
+    let server = MockServer::start().await;
+    mount_empty_list(&server).await;
+    let client = client(&server).requests_per_second(10.).build().unwrap();
+    let start = Instant::now();
+    let cloned = client.clone();
+    let (a, b) = tokio::join!(sub_replies(&client), sub_replies(&cloned));
+    a.unwrap();
+    b.unwrap();
+    sub_replies(&client).await.unwrap();
+    // 第一个请求立即发出，之后每个请求间隔 100ms
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
+
+#[tokio::test]
+async fn lenient_mode_reports_unknown_fields() {
+    let fixtures = bbs::fixture::load_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"));
+    let fixture = (fixtures.unwrap().into_iter())
+        .find(|fixture| fixture.endpoint == "/post/wapi/userPost")
+        .unwrap();
+    let mut body: serde_json::Value = serde_json::from_slice(&fixture.body).unwrap();
+    let post = &mut body["data"]["list"][0];
+    post["post"]["new_field"] = serde_json::json!("new");
+    post["help_sys"] = serde_json::json!({"id": 1});
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/userPost"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&body))
+        .mount(&server)
+        .await;
+
+    let posts = |client: Client| async move {
+        let mut posts = client.user_post(288909600).async_iter();
+        let mut count = 0;
+        while let Some(post) = std::future::poll_fn(|cx| Pin::new(&mut posts).poll_next(cx)).await {
+            post?;
+            count += 1;
+        }
+        Ok::<_, bbs::api::Error>(count)
+    };
+    let strict = client(&server).build().unwrap();
+    let err = posts(strict.clone()).await.unwrap_err();
+    assert!(matches!(err, bbs::api::Error::Deserialization(_)));
+    assert!(strict.take_warnings().is_empty());
+
+    let lenient = client(&server).lenient(true).build().unwrap();
+    assert_eq!(posts(lenient.clone()).await.unwrap(), 1);
+    let warnings = lenient.take_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].endpoint, "/post/wapi/userPost");
+    let warnings = &warnings[0].warnings;
+    assert_eq!(
+        warnings.unknown_fields["data.list[0].post"]["new_field"],
+        "new"
+    );
+    assert_eq!(
+        warnings.unexpected_values["data.list[0].help_sys"],
+        serde_json::json!({"id": 1})
+    );
+    assert!(lenient.take_warnings().is_empty());
+}
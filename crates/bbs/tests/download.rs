@@ -0,0 +1,145 @@
+use bbs::download::{Downloader, Manifest, Media, MediaKind, VideoPreference};
+use bbs::model::media::{VideoResolution, VideoResolutionDefinition};
+use sha2::Digest;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const POSTER: &[u8] = b"\x89PNG\r\n\x1a\nposter of the next event";
+
+fn media(server: &MockServer, path: &str, kind: MediaKind) -> Media {
+    Media {
+        url: format!("{}{path}", server.uri()).parse().unwrap(),
+        kind,
+    }
+}
+
+fn sha256(bytes: &[u8]) -> String {
+    hex::encode(sha2::Sha256::digest(bytes))
+}
+
+#[tokio::test]
+async fn dedup_by_content() {
+    let server = MockServer::start().await;
+    for url in ["/cover.png", "/poster.png"] {
+        Mock::given(method("GET"))
+            .and(path(url))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(POSTER))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let downloader = Downloader::new(dir.path()).concurrency(2);
+    let media = [
+        media(&server, "/cover.png", MediaKind::Cover),
+        media(&server, "/poster.png", MediaKind::InlineImage),
+    ];
+    let entries = downloader.download_media(1, &media).await.unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].file, entries[1].file);
+    assert_eq!(entries[0].sha256, sha256(POSTER));
+    assert_eq!(entries[0].size, POSTER.len() as u64);
+    assert_eq!(
+        std::fs::read(dir.path().join(&entries[0].file)).unwrap(),
+        POSTER
+    );
+
+    // 已经下载过的链接直接使用 manifest 中的记录，expect(1) 保证不会再次请求
+    let entries = downloader.download_media(2, &media[..1]).await.unwrap();
+    assert_eq!(entries[0].post_id, 2);
+    let manifest = Manifest::load(downloader.manifest_path()).unwrap();
+    assert_eq!(manifest.entries.len(), 3);
+    assert_eq!(manifest.post(1).count(), 2);
+    assert_eq!(manifest.post(2).count(), 1);
+    // manifest 通过临时文件重命名写入，不会留下临时文件
+    assert!(!dir.path().join("manifest.json.tmp").exists());
+}
+
+#[tokio::test]
+async fn resume_partial_download() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/video.mp4"))
+        .and(header("Range", "bytes=8-"))
+        .respond_with(ResponseTemplate::new(206).set_body_bytes(&POSTER[8..]))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let dir = tempfile::tempdir().unwrap();
+    let video = media(&server, "/video.mp4", MediaKind::Video);
+    let partial = dir
+        .path()
+        .join("partial")
+        .join(sha256(video.url.to_string().as_bytes()) + ".part");
+    std::fs::create_dir_all(partial.parent().unwrap()).unwrap();
+    std::fs::write(&partial, &POSTER[..8]).unwrap();
+
+    let downloader = Downloader::new(dir.path());
+    let entries = downloader.download_media(1, &[video]).await.unwrap();
+    assert_eq!(entries[0].sha256, sha256(POSTER));
+    assert!(entries[0].file.to_string_lossy().ends_with(".mp4"));
+    assert_eq!(
+        std::fs::read(dir.path().join(&entries[0].file)).unwrap(),
+        POSTER
+    );
+    assert!(!partial.exists());
+}
+
+#[tokio::test]
+async fn keep_successful_downloads_on_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/ok.jpg"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(POSTER))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/missing.jpg"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+    let dir = tempfile::tempdir().unwrap();
+    let downloader = Downloader::new(dir.path());
+    let media = [
+        media(&server, "/ok.jpg", MediaKind::Image),
+        media(&server, "/missing.jpg", MediaKind::Image),
+    ];
+    assert!(downloader.download_media(1, &media).await.is_err());
+    let manifest = Manifest::load(downloader.manifest_path()).unwrap();
+    assert_eq!(manifest.entries.len(), 1);
+    assert_eq!(manifest.entries[0].url, media[0].url);
+}
+
+fn resolution(label: &str, width: u16, height: u16) -> VideoResolution {
+    serde_json::from_value(serde_json::json!({
+        "url": format!("https://example.com/{width}x{height}.mp4"),
+        "definition": label,
+        "height": height,
+        "width": width,
+        "bitrate": 1000,
+        "size": "1000",
+        "format": ".mp4",
+        "label": label,
+        "codec": "h264",
+    }))
+    .unwrap()
+}
+
+#[test]
+fn select_by_dimensions() {
+    // 标签和实际尺寸不一致时以尺寸为准，竖屏视频按短边比较
+    let resolutions = [
+        resolution("1080P", 1280, 720),
+        resolution("720P", 1080, 1920),
+        resolution("2K", 1440, 2560),
+    ];
+    let preference = VideoPreference::default();
+    let selected = preference.select(&resolutions).unwrap();
+    assert_eq!(selected.dimensions(), (1080, 1920));
+    let preference = VideoPreference {
+        definition: VideoResolutionDefinition::_480P,
+        ..VideoPreference::default()
+    };
+    let selected = preference.select(&resolutions).unwrap();
+    assert_eq!(selected.dimensions(), (720, 1280));
+}
@@ -0,0 +1,159 @@
+#![feature(async_iterator)]
+
+use std::async_iter::AsyncIterator;
+use std::pin::Pin;
+
+use bbs::api::{ForumSort, PostListIter, SearchSort, TopicSort};
+use bbs::client::Client;
+use bbs::model::GameId;
+use serde_json::{json, Value};
+use wiremock::matchers::{method, path, query_param, query_param_is_missing};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+const FORUM_PATH: &str = "/post/wapi/getForumPostList";
+const TOPIC_PATH: &str = "/post/wapi/getTopicPostList";
+const SEARCH_PATH: &str = "/post/wapi/searchPosts";
+
+/// 以录制的 userPost 中的第一条帖子为模板，替换帖子 ID
+fn post(post_id: u32) -> Value {
+    let fixture = (bbs::fixture::load_dir(FIXTURES).unwrap().into_iter())
+        .find(|fixture| fixture.endpoint == "/post/wapi/userPost")
+        .unwrap();
+    let body: Value = serde_json::from_slice(&fixture.body).unwrap();
+    let mut post = body["data"]["list"][0].clone();
+    post["post"]["post_id"] = json!(post_id.to_string());
+    post
+}
+
+/// 版块返回的字段名为 list，话题和搜索的为 posts
+fn page(field: &str, post_ids: &[u32], last_id: &str, is_last: bool) -> ResponseTemplate {
+    let posts = post_ids.iter().map(|&id| post(id)).collect::<Vec<_>>();
+    ResponseTemplate::new(200).set_body_json(json!({
+        "retcode": 0,
+        "message": "OK",
+        "data": {field: posts, "last_id": last_id, "is_last": is_last},
+    }))
+}
+
+async fn post_ids(mut posts: PostListIter) -> Vec<u32> {
+    let mut post_ids = Vec::new();
+    while let Some(post) = std::future::poll_fn(|cx| Pin::new(&mut posts).poll_next(cx)).await {
+        post_ids.push(post.unwrap().post.post_id);
+    }
+    post_ids
+}
+
+fn client(server: &MockServer) -> Client {
+    Client::builder().base_url(server.uri()).build().unwrap()
+}
+
+#[tokio::test]
+async fn forum_pages() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(FORUM_PATH))
+        .and(query_param("gids", "6"))
+        .and(query_param("forum_id", "53"))
+        .and(query_param("is_good", "false"))
+        .and(query_param("is_hot", "false"))
+        .and(query_param("sort_type", "1"))
+        .and(query_param("page_size", "20"))
+        .and(query_param_is_missing("last_id"))
+        .respond_with(page("list", &[1, 2], "cursor-1", false))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(FORUM_PATH))
+        .and(query_param("last_id", "cursor-1"))
+        .and(query_param("forum_id", "53"))
+        .respond_with(page("list", &[3], "cursor-2", true))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = client(&server);
+    let posts = client.forum(GameId::HonkaiStarRail, 53).async_iter();
+    assert_eq!(post_ids(posts).await, [1, 2, 3]);
+}
+
+#[tokio::test]
+async fn forum_sort() {
+    let server = MockServer::start().await;
+    for (sort_type, is_hot) in [("2", "false"), ("1", "true")] {
+        Mock::given(method("GET"))
+            .and(path(FORUM_PATH))
+            .and(query_param("is_hot", is_hot))
+            .and(query_param("sort_type", sort_type))
+            .and(query_param("is_good", "true"))
+            .and(query_param("page_size", "50"))
+            .respond_with(page("list", &[1], "", true))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+    let client = client(&server);
+    for sort in [ForumSort::LatestPost, ForumSort::Hot] {
+        let forum = (client.forum(GameId::HonkaiStarRail, 53))
+            .sort(sort)
+            .good(true)
+            .page_size(50);
+        assert_eq!(post_ids(forum.async_iter()).await, [1]);
+    }
+}
+
+#[tokio::test]
+async fn topic_pages() {
+    let server = MockServer::start().await;
+    for (list_type, post_id) in [("0", 1), ("1", 2), ("2", 3)] {
+        Mock::given(method("GET"))
+            .and(path(TOPIC_PATH))
+            .and(query_param("gids", "6"))
+            .and(query_param("game_id", "6"))
+            .and(query_param("topic_id", "1234"))
+            .and(query_param("list_type", list_type))
+            .and(query_param_is_missing("last_id"))
+            .respond_with(page("posts", &[post_id], "cursor-1", false))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+    // 未到最后一页时返回空列表也结束翻页
+    Mock::given(method("GET"))
+        .and(path(TOPIC_PATH))
+        .and(query_param("last_id", "cursor-1"))
+        .respond_with(page("posts", &[], "cursor-2", false))
+        .expect(3)
+        .mount(&server)
+        .await;
+    let client = client(&server);
+    let topic = |sort| client.topic(GameId::HonkaiStarRail, 1234).sort(sort);
+    assert_eq!(post_ids(topic(TopicSort::Hot).async_iter()).await, [1]);
+    assert_eq!(post_ids(topic(TopicSort::Latest).async_iter()).await, [2]);
+    assert_eq!(post_ids(topic(TopicSort::Good).async_iter()).await, [3]);
+}
+
+#[tokio::test]
+async fn search_pages() {
+    let server = MockServer::start().await;
+    for (sort_type, post_id) in [("1", 1), ("2", 2)] {
+        Mock::given(method("GET"))
+            .and(path(SEARCH_PATH))
+            .and(query_param("gids", "6"))
+            .and(query_param("keyword", "版本 & 前瞻"))
+            .and(query_param("sort_type", sort_type))
+            .and(query_param("size", "10"))
+            .and(query_param_is_missing("page_size"))
+            .respond_with(page("posts", &[post_id], "", true))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+    let client = client(&server);
+    for (sort, post_id) in [(SearchSort::Relevance, 1), (SearchSort::Latest, 2)] {
+        let search = (client.search(GameId::HonkaiStarRail, "版本 & 前瞻"))
+            .sort(sort)
+            .page_size(10);
+        assert_eq!(post_ids(search.async_iter()).await, [post_id]);
+    }
+}
@@ -0,0 +1,147 @@
+#![feature(async_iterator)]
+
+use std::async_iter::AsyncIterator;
+use std::path::Path;
+use std::pin::Pin;
+
+use bbs::client::Client;
+use bbs::model::Response;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+const SR_OFFICIAL_UID: u32 = 288909600;
+/// 录制话题帖子列表时使用的话题
+const SAMPLE_TOPIC_ID: u32 = 1234;
+
+async fn user_posts(client: &Client, user_id: u32) -> Result<Vec<u32>, bbs::api::Error> {
+    let mut posts = client.user_post(user_id).async_iter();
+    let mut post_ids = Vec::new();
+    while let Some(post) = std::future::poll_fn(|cx| Pin::new(&mut posts).poll_next(cx)).await {
+        post_ids.push(post?.post.post_id);
+    }
+    Ok(post_ids)
+}
+
+#[tokio::test]
+async fn record_then_replay() {
+    let fixtures = bbs::fixture::load_dir(FIXTURES).unwrap();
+    let fixture = (fixtures.iter())
+        .find(|fixture| fixture.endpoint == "/post/wapi/userPost")
+        .unwrap();
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/userPost"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(fixture.body.clone()))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let dir = tempfile::tempdir().unwrap();
+    let recorder = Client::builder()
+        .base_url(server.uri())
+        .record(dir.path())
+        .build()
+        .unwrap();
+    let recorded = user_posts(&recorder, SR_OFFICIAL_UID).await.unwrap();
+    assert!(!recorded.is_empty());
+    drop(server);
+
+    let replayer = Client::builder().replay(dir.path()).build().unwrap();
+    assert_eq!(
+        user_posts(&replayer, SR_OFFICIAL_UID).await.unwrap(),
+        recorded
+    );
+    let recorded = bbs::fixture::load_dir(dir.path()).unwrap();
+    assert_eq!(recorded.len(), 1);
+    assert_eq!(recorded[0].body, fixture.body);
+}
+
+#[tokio::test]
+async fn replay_missing_fixture() {
+    let client = Client::builder().replay(FIXTURES).build().unwrap();
+    let err = user_posts(&client, 1).await.unwrap_err();
+    assert!(matches!(err, bbs::api::Error::Fixture(_)));
+}
+
+async fn first<T>(
+    mut iter: impl AsyncIterator<Item = Result<T, bbs::api::Error>> + Unpin,
+) -> Result<Option<T>, bbs::api::Error> {
+    std::future::poll_fn(|cx| Pin::new(&mut iter).poll_next(cx))
+        .await
+        .transpose()
+}
+
+/// 依次请求各个接口的第一页，录制模式下即为 tests/fixtures 中的样本
+async fn record_samples(client: &Client) -> Result<(), bbs::api::Error> {
+    use bbs::api::ReplyOrder;
+    use bbs::model::GameId;
+
+    let post = first(client.user_post(SR_OFFICIAL_UID).async_iter()).await?;
+    let post = post.expect("官方账号没有帖子");
+    let post_api = client.post(post.post.post_id);
+    post_api.full().await?;
+    let replies = post_api.replies(ReplyOrder::Earliest, false);
+    if let Some(floor) = first(replies).await? {
+        first(post_api.sub_replies(floor.reply.floor_id)).await?;
+    }
+    let game_id = GameId::HonkaiStarRail;
+    first(client.forum(game_id, post.post.f_forum_id).async_iter()).await?;
+    first(client.topic(game_id, SAMPLE_TOPIC_ID).async_iter()).await?;
+    first(client.search(game_id, "版本更新说明").async_iter()).await?;
+    Ok(())
+}
+
+/// 重新录制样本，需要访问米游社：cargo test -p bbs --test fixture -- --ignored
+#[tokio::test]
+#[ignore = "需要网络"]
+async fn record_live_fixtures() {
+    let client = Client::builder().record(FIXTURES).build().unwrap();
+    record_samples(&client).await.unwrap();
+}
+
+/// 样本覆盖 record_samples 请求的每个接口，回放时不会缺文件
+#[tokio::test]
+async fn replay_samples() {
+    let client = Client::builder().replay(FIXTURES).build().unwrap();
+    record_samples(&client).await.unwrap();
+}
+
+fn deserialize<T>(path: &Path, body: &[u8]) -> Result<(), String>
+where
+    for<'de> T: serde::Deserialize<'de>,
+{
+    let res: Response<T> =
+        serde_json::from_slice(body).map_err(|err| format!("{}: {err}", path.display()))?;
+    match res.retcode {
+        0 => Ok(()),
+        retcode => Err(format!("{}: retcode {retcode}", path.display())),
+    }
+}
+
+/// 所有录制的响应都要能被当前的模型解析，新增的字段和枚举值会在这里暴露出来
+#[test]
+fn recorded_fixtures_deserialize() {
+    use bbs::model::post::{PostFull, PostList, ReplyList};
+    use bbs::model::user_post::UserPostList;
+
+    let fixtures = bbs::fixture::load_dir(FIXTURES).unwrap();
+    assert!(!fixtures.is_empty());
+    let errors = (fixtures.iter())
+        .filter_map(|fixture| {
+            let (path, body) = (fixture.path.as_path(), fixture.body.as_slice());
+            match fixture.endpoint.as_str() {
+                "/post/wapi/userPost" => deserialize::<UserPostList>(path, body),
+                "/post/wapi/getPostFull" => deserialize::<PostFull>(path, body),
+                "/post/wapi/getPostReplies" | "/post/wapi/getSubReplies" => {
+                    deserialize::<ReplyList>(path, body)
+                }
+                "/post/wapi/getForumPostList"
+                | "/post/wapi/getTopicPostList"
+                | "/post/wapi/searchPosts" => deserialize::<PostList>(path, body),
+                endpoint => Err(format!("{}: 未知的接口 {endpoint}", path.display())),
+            }
+            .err()
+        })
+        .collect::<Vec<_>>();
+    assert!(errors.is_empty(), "{}", errors.join("\n"));
+}
@@ -0,0 +1,186 @@
+#![feature(async_iterator)]
+
+use std::async_iter::AsyncIterator;
+use std::pin::Pin;
+
+use bbs::api::{ReplyIter, ReplyOrder};
+use bbs::client::Client;
+use serde_json::{json, Value};
+use wiremock::matchers::{method, path, query_param, query_param_is_missing};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+/// 录制的 userPost 中的第一条帖子，getPostFull 返回的结构和它相同
+fn user_post() -> Value {
+    let fixture = (bbs::fixture::load_dir(FIXTURES).unwrap().into_iter())
+        .find(|fixture| fixture.endpoint == "/post/wapi/userPost")
+        .unwrap();
+    let body: Value = serde_json::from_slice(&fixture.body).unwrap();
+    body["data"]["list"][0].clone()
+}
+
+fn reply(floor_id: u32) -> Value {
+    let user = user_post()["user"].clone();
+    json!({
+        "reply": {
+            "game_id": 6,
+            "post_id": "1",
+            "reply_id": (1000 + floor_id).to_string(),
+            "uid": user["uid"],
+            "f_reply_id": "0",
+            "floor_id": floor_id,
+            "content": format!("<p>{floor_id} 楼</p>"),
+            "is_deleted": 0,
+            "created_at": 1704038400,
+            "updated_at": 1704038400,
+        },
+        "user": user,
+        "stat": {"reply_num": 0, "like_num": 0},
+    })
+}
+
+fn page(floors: &[u32], last_id: &str, is_last: bool) -> ResponseTemplate {
+    let list = floors.iter().map(|&floor| reply(floor)).collect::<Vec<_>>();
+    ResponseTemplate::new(200).set_body_json(json!({
+        "retcode": 0,
+        "message": "OK",
+        "data": {"list": list, "last_id": last_id, "is_last": is_last},
+    }))
+}
+
+async fn floors(mut replies: ReplyIter) -> Vec<u32> {
+    let mut floors = Vec::new();
+    while let Some(reply) = std::future::poll_fn(|cx| Pin::new(&mut replies).poll_next(cx)).await {
+        floors.push(reply.unwrap().reply.floor_id);
+    }
+    floors
+}
+
+fn client(server: &MockServer) -> Client {
+    Client::builder().base_url(server.uri()).build().unwrap()
+}
+
+#[tokio::test]
+async fn replies_follow_last_id() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/getPostReplies"))
+        .and(query_param_is_missing("last_id"))
+        .respond_with(page(&[1, 2], "cursor-1", false))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/getPostReplies"))
+        .and(query_param("last_id", "cursor-1"))
+        .respond_with(page(&[3], "cursor-2", true))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = client(&server);
+    let replies = client.post(1).replies(ReplyOrder::Earliest, false);
+    // is_last 之后不再请求下一页
+    assert_eq!(floors(replies).await, [1, 2, 3]);
+}
+
+#[tokio::test]
+async fn replies_stop_at_empty_page() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/getSubReplies"))
+        .and(query_param_is_missing("last_id"))
+        .respond_with(page(&[2], "cursor-1", false))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/getSubReplies"))
+        .and(query_param("last_id", "cursor-1"))
+        .respond_with(page(&[], "cursor-2", false))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = client(&server);
+    // is_last 为 false 的空页也结束翻页，不会用 cursor-2 继续请求
+    assert_eq!(floors(client.post(1).sub_replies(2)).await, [2]);
+}
+
+#[tokio::test]
+async fn reply_order_query() {
+    let server = MockServer::start().await;
+    // 各排序方式对应的 is_hot、order_type，only_master 为 false 时不传
+    for (is_hot, order_type, only_master) in [
+        ("true", None, true),
+        ("false", Some("1"), false),
+        ("false", Some("2"), false),
+    ] {
+        let mock = Mock::given(method("GET"))
+            .and(path("/post/wapi/getPostReplies"))
+            .and(query_param("post_id", "1"))
+            .and(query_param("size", "20"))
+            .and(query_param("is_hot", is_hot));
+        let mock = match order_type {
+            Some(order_type) => mock.and(query_param("order_type", order_type)),
+            None => mock.and(query_param_is_missing("order_type")),
+        };
+        let mock = match only_master {
+            true => mock.and(query_param("only_master", "true")),
+            false => mock.and(query_param_is_missing("only_master")),
+        };
+        mock.respond_with(page(&[1], "", true))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/getSubReplies"))
+        .and(query_param("floor_id", "2"))
+        .and(query_param_is_missing("is_hot"))
+        .and(query_param_is_missing("order_type"))
+        .respond_with(page(&[2], "", true))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = client(&server);
+    let post = client.post(1);
+    assert_eq!(floors(post.replies(ReplyOrder::Hot, true)).await, [1]);
+    assert_eq!(floors(post.replies(ReplyOrder::Earliest, false)).await, [1]);
+    assert_eq!(floors(post.replies(ReplyOrder::Latest, false)).await, [1]);
+    assert_eq!(floors(post.sub_replies(2)).await, [2]);
+}
+
+#[tokio::test]
+async fn post_full() {
+    let post = user_post();
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/getPostFull"))
+        .and(query_param("post_id", "45678901"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "retcode": 0,
+            "message": "OK",
+            "data": {"post": post},
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/getPostFull"))
+        .and(query_param("post_id", "1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "retcode": -1,
+            "message": "帖子不存在",
+            "data": null,
+        })))
+        .mount(&server)
+        .await;
+
+    let client = client(&server);
+    let full = client.post(45678901).full().await.unwrap();
+    assert_eq!(full.post.post_id, 45678901);
+    assert_eq!(full.user.uid, 288909600);
+    let err = client.post(1).full().await.unwrap_err();
+    assert!(matches!(err, bbs::api::Error::Business { retcode: -1, .. }));
+}
@@ -0,0 +1,104 @@
+use bbs::client::Client;
+use bbs::syndication::{FeedMeta, FeedState, UpdateOptions};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const SR_OFFICIAL_UID: u32 = 288909600;
+
+fn user_post_fixture() -> serde_json::Value {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+    let fixture = (bbs::fixture::load_dir(dir).unwrap().into_iter())
+        .find(|fixture| fixture.endpoint == "/post/wapi/userPost")
+        .unwrap();
+    serde_json::from_slice(&fixture.body).unwrap()
+}
+
+async fn mount(server: &MockServer, body: &serde_json::Value) {
+    server.reset().await;
+    Mock::given(method("GET"))
+        .and(path("/post/wapi/userPost"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn incremental_update() {
+    let server = MockServer::start().await;
+    let mut body = user_post_fixture();
+    mount(&server, &body).await;
+    let client = Client::builder().base_url(server.uri()).build().unwrap();
+    let options = UpdateOptions {
+        full_content: false,
+        ..Default::default()
+    };
+    let dir = tempfile::tempdir().unwrap();
+    let state_path = dir.path().join("sr.json");
+
+    let mut state = FeedState::load(&state_path).unwrap();
+    let api = client.user_post(SR_OFFICIAL_UID);
+    let report = state.update(&api, &options).await.unwrap();
+    assert_eq!(report.added, [45678901]);
+    state.save(&state_path).unwrap();
+
+    let mut state = FeedState::load(&state_path).unwrap();
+    let report = state.update(&api, &options).await.unwrap();
+    assert!(report.added.is_empty() && report.updated.is_empty());
+
+    body["data"]["list"][0]["last_modify_time"] = serde_json::json!(1704124800);
+    body["data"]["list"][0]["post"]["subject"] =
+        serde_json::json!("「多重同调」版本更新说明（已更新）");
+    mount(&server, &body).await;
+    let report = state.update(&api, &options).await.unwrap();
+    assert_eq!(report.updated, [45678901]);
+    assert_eq!(state.entries.len(), 1);
+    assert_eq!(state.entries[0].title, "「多重同调」版本更新说明（已更新）");
+
+    body["data"]["list"][0]["post"]["is_deleted"] = serde_json::json!(1);
+    mount(&server, &body).await;
+    let report = state.update(&api, &options).await.unwrap();
+    assert_eq!(report.removed, [45678901]);
+    assert!(state.entries.is_empty());
+}
+
+#[tokio::test]
+async fn atom_and_rss() {
+    let server = MockServer::start().await;
+    mount(&server, &user_post_fixture()).await;
+    let client = Client::builder().base_url(server.uri()).build().unwrap();
+    let options = UpdateOptions {
+        full_content: false,
+        ..Default::default()
+    };
+    let mut state = FeedState::default();
+    (state.update(&client.sr_user_post(), &options).await).unwrap();
+    // 修改 updated_at 以区分发帖时间和编辑时间
+    state.entries[0].updated += chrono::TimeDelta::hours(1);
+
+    // XML 1.0 不允许的控制字符需要去掉
+    state.entries[0].title.insert(0, '\u{8}');
+    state.entries[0].content += "<p>\u{1B}[0m</p>";
+
+    let meta = FeedMeta::new("崩坏：星穹铁道 & 官方", "https://www.miyoushe.com/sr/")
+        .self_link("https://example.com/sr.atom");
+    let atom = state.atom(&meta);
+    assert!(atom.contains("<title>崩坏：星穹铁道 &amp; 官方</title>"));
+    assert!(atom.contains("<id>https://www.miyoushe.com/sr/article/45678901</id>"));
+    assert!(atom.contains("<published>2023-12-31T16:00:00Z</published>"));
+    assert!(atom.contains("<updated>2023-12-31T17:00:00Z</updated>"));
+    assert!(atom.contains("<link rel=\"enclosure\" type=\"image/png\""));
+    assert!(atom.contains("&lt;strong&gt;《崩坏：星穹铁道》版本更新说明&lt;/strong&gt;"));
+
+    let rss = state.rss(&meta);
+    assert!(rss.contains("<pubDate>Sun, 31 Dec 2023 16:00:00 +0000</pubDate>"));
+    assert!(rss.contains("<lastBuildDate>Sun, 31 Dec 2023 17:00:00 +0000</lastBuildDate>"));
+    assert!(rss.contains(
+        "<guid isPermaLink=\"true\">https://www.miyoushe.com/sr/article/45678901</guid>"
+    ));
+    assert!(rss.contains("length=\"0\""));
+
+    for xml in [atom, rss] {
+        assert!(!xml.contains(['\u{8}', '\u{1B}']));
+        assert!(xml.contains("&lt;p&gt;[0m&lt;/p&gt;"));
+    }
+}
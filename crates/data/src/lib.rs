@@ -10,6 +10,7 @@ use std::sync::OnceLock;
 #[derive(Default)]
 pub struct GameData {
     base: PathBuf,
+    language: Language,
     text_map: std::collections::HashMap<i128, Arc<str>, fnv::FnvBuildHasher>,
 
     // battle
@@ -184,12 +185,19 @@ pub struct GameData {
     /// 按照 MonsterConfig 中的 GroupID 字段重新聚合 MonsterConfig
     _monster_template_config_group:
         OnceLock<FnvMultiMap<u32, Arc<model::monster::MonsterTemplateConfig>>>,
+    /// 按照 MonsterConfig 中的 MonsterTemplateID 聚合 MonsterConfig，即同一怪物的各个难度
+    _monster_config_in_template: OnceLock<FnvMultiMap<u32, Arc<model::monster::MonsterConfig>>>,
     /// 按照 MessageGroupConfig 中的 MessageContactsID 和 MessageSectionIDList 将联系人和消息关联起来
     /// 形成从联系人到消息的映射
     _message_section_in_contacts:
         OnceLock<FnvMultiMap<u16, Arc<model::message::MessageSectionConfig>>>,
     _message_contacts_of_section:
         OnceLock<FnvHashMap<u32, Arc<model::message::MessageContactsConfig>>>,
+    /// 按所属主任务聚合 SubMission，1.0 之后子任务 ID 为主任务 ID 后接两位序号
+    _sub_mission_in_main_mission: OnceLock<FnvMultiMap<u32, Arc<model::mission::SubMission>>>,
+    /// 按照 MessageSectionConfig 中的 MainMissionLink 聚合，即结束后接取某一任务的短信
+    _message_section_of_main_mission:
+        OnceLock<FnvMultiMap<u32, Arc<model::message::MessageSectionConfig>>>,
 
     // 名称反向索引
     /// 按名称索引的 ExtraEffectConfig
@@ -198,12 +206,18 @@ pub struct GameData {
     _rogue_buff_by_name: OnceLock<FnvHashMap<Arc<str>, Arc<model::rogue::RogueBuff>>>,
     _rogue_tourn_buff_by_name:
         OnceLock<FnvHashMap<Arc<str>, Arc<model::rogue::tourn::RogueTournBuff>>>,
+    /// 按名称索引的奇物，同名奇物只保留表中最后一个
+    _rogue_miracle_by_name: OnceLock<FnvHashMap<Arc<str>, Arc<model::rogue::RogueMiracle>>>,
+    _rogue_magic_miracle_by_name: OnceLock<FnvHashMap<Arc<str>, Arc<model::rogue::RogueMiracle>>>,
+    _rogue_tourn_miracle_by_name:
+        OnceLock<FnvHashMap<Arc<str>, Arc<model::rogue::tourn::RogueTournMiracle>>>,
 }
 
 impl std::fmt::Debug for GameData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("GameData")
             .field("base", &self.base)
+            .field("language", &self.language)
             .finish_non_exhaustive()
     }
 }
@@ -221,21 +235,76 @@ impl Text for GameData {
     }
 }
 
+/// 文本语言，对应 TextMap 目录下的 TextMap<语言>.json
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Language {
+    /// 简体中文
+    #[default]
+    CHS,
+    /// 繁体中文
+    CHT,
+    DE,
+    EN,
+    ES,
+    FR,
+    ID,
+    JP,
+    KR,
+    PT,
+    RU,
+    TH,
+    VI,
+}
+
+impl Language {
+    fn text_map_file(self) -> &'static str {
+        match self {
+            Self::CHS => "TextMap/TextMapCHS.json",
+            Self::CHT => "TextMap/TextMapCHT.json",
+            Self::DE => "TextMap/TextMapDE.json",
+            Self::EN => "TextMap/TextMapEN.json",
+            Self::ES => "TextMap/TextMapES.json",
+            Self::FR => "TextMap/TextMapFR.json",
+            Self::ID => "TextMap/TextMapID.json",
+            Self::JP => "TextMap/TextMapJP.json",
+            Self::KR => "TextMap/TextMapKR.json",
+            Self::PT => "TextMap/TextMapPT.json",
+            Self::RU => "TextMap/TextMapRU.json",
+            Self::TH => "TextMap/TextMapTH.json",
+            Self::VI => "TextMap/TextMapVI.json",
+        }
+    }
+}
+
 impl GameData {
     pub fn new(base: impl Into<PathBuf>) -> Self {
+        Self::with_language(base, Language::CHS)
+    }
+
+    /// 使用指定语言的文本，其余数据和语言无关
+    pub fn with_language(base: impl Into<PathBuf>, language: Language) -> Self {
         let base = base.into();
-        let file = File::open(base.join("TextMap/TextMapCHS.json"))
-            .or_else(|_| File::open(base.join("TextMap/TextMapCN.json")))
+        let file = File::open(base.join(language.text_map_file()))
+            // 早期数据集中简体中文文件名为 TextMapCN.json
+            .or_else(|err| match language {
+                Language::CHS => File::open(base.join("TextMap/TextMapCN.json")),
+                _ => Err(err),
+            })
             .unwrap();
         let text_map_reader = BufReader::new(file);
         let text_map = serde_json::from_reader(text_map_reader).unwrap();
         GameData {
             base,
+            language,
             text_map,
             ..GameData::default()
         }
     }
 
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
     fn load<K, V>(&self, dir: &str) -> std::io::Result<FnvIndexMap<K, Arc<V>>>
     where
         K: std::cmp::Eq + std::hash::Hash,
@@ -258,6 +327,33 @@ impl GameData {
             ))
     }
 
+    /// 奇物展示名称，模拟宇宙和差分宇宙的展示配置 ID 不重叠，依次查找
+    fn miracle_display_name(&self, id: u16) -> Option<&Arc<str>> {
+        self._rogue_miracle_display()
+            .get(&id)
+            .or_else(|| self._rogue_tourn_miracle_display().get(&id))
+            .and_then(|display| self.text_map.get(&display.miracle_name.hash.get()))
+    }
+
+    /// 模拟宇宙和不可知域的奇物表结构相同，1.2 及之前没有展示配置，名称直接在奇物表中
+    fn miracle_by_name(
+        &self,
+        miracles: &FnvIndexMap<u16, Arc<model::rogue::RogueMiracle>>,
+    ) -> FnvHashMap<Arc<str>, Arc<model::rogue::RogueMiracle>> {
+        miracles
+            .values()
+            .filter_map(|miracle| {
+                let name = match miracle.miracle_display_id {
+                    Some(id) => self.miracle_display_name(id.get()),
+                    None => miracle
+                        .miracle_name
+                        .and_then(|text| self.text_map.get(&text.hash.get())),
+                };
+                name.map(|name| (Arc::clone(name), Arc::clone(miracle)))
+            })
+            .collect()
+    }
+
     fn load_main_sub<I, S, V>(&self, dir: &str) -> std::io::Result<FnvMultiMap<I, Arc<V>>>
     where
         I: std::cmp::Eq + std::hash::Hash,
@@ -420,12 +516,18 @@ pub trait SealedGameData {
     #[rustfmt::skip]
     fn _monster_template_config_group(&self) -> &FnvMultiMap<u32, Arc<model::monster::MonsterTemplateConfig>>;
     #[rustfmt::skip]
+    fn _monster_config_in_template(&self) -> &FnvMultiMap<u32, Arc<model::monster::MonsterConfig>>;
+    #[rustfmt::skip]
     fn _challenge_maze_in_group(&self) -> &FnvMultiMap<u16, Arc<model::challenge::ChallengeMazeConfig>>;
     fn _localbook_in_book_series(&self) -> &FnvMultiMap<u16, Arc<model::book::LocalbookConfig>>;
     #[rustfmt::skip]
     fn _message_section_in_contacts(&self) -> &FnvMultiMap<u16, Arc<model::message::MessageSectionConfig>>;
     #[rustfmt::skip]
     fn _message_contacts_of_section(&self) -> &FnvHashMap<u32, Arc<model::message::MessageContactsConfig>>;
+    #[rustfmt::skip]
+    fn _sub_mission_in_main_mission(&self) -> &FnvMultiMap<u32, Arc<model::mission::SubMission>>;
+    #[rustfmt::skip]
+    fn _message_section_of_main_mission(&self) -> &FnvMultiMap<u32, Arc<model::message::MessageSectionConfig>>;
 
     #[rustfmt::skip]
     fn _current_challenge_group_config<F>(&self, iter: F)
@@ -440,6 +542,11 @@ pub trait SealedGameData {
     fn _rogue_buff_by_name(&self) -> &FnvHashMap<Arc<str>, Arc<model::rogue::RogueBuff>>;
     #[rustfmt::skip]
     fn _rogue_tourn_buff_by_name(&self) -> &FnvHashMap<Arc<str>, Arc<model::rogue::tourn::RogueTournBuff>>;
+    fn _rogue_miracle_by_name(&self) -> &FnvHashMap<Arc<str>, Arc<model::rogue::RogueMiracle>>;
+    #[rustfmt::skip]
+    fn _rogue_magic_miracle_by_name(&self) -> &FnvHashMap<Arc<str>, Arc<model::rogue::RogueMiracle>>;
+    #[rustfmt::skip]
+    fn _rogue_tourn_miracle_by_name(&self) -> &FnvHashMap<Arc<str>, Arc<model::rogue::tourn::RogueTournMiracle>>;
 
     #[rustfmt::skip]
     fn _load_story(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<model::story::Story>;
@@ -622,6 +729,15 @@ impl SealedGameData for GameData {
         })
     }
 
+    fn _monster_config_in_template(&self) -> &FnvMultiMap<u32, Arc<model::monster::MonsterConfig>> {
+        self._monster_config_in_template.get_or_init(|| {
+            self._monster_config()
+                .values()
+                .map(|monster| (monster.monster_template_id, Arc::clone(monster)))
+                .collect()
+        })
+    }
+
     fn _challenge_maze_in_group(
         &self,
     ) -> &FnvMultiMap<u16, Arc<model::challenge::ChallengeMazeConfig>> {
@@ -714,6 +830,35 @@ impl SealedGameData for GameData {
         })
     }
 
+    fn _sub_mission_in_main_mission(&self) -> &FnvMultiMap<u32, Arc<model::mission::SubMission>> {
+        self._sub_mission_in_main_mission.get_or_init(|| {
+            self._sub_mission()
+                .values()
+                .map(|sub| {
+                    let main_id = sub
+                        .main_mission_id
+                        .map_or(sub.sub_missoin_id / 100, NonZero::get);
+                    (main_id, Arc::clone(sub))
+                })
+                .collect()
+        })
+    }
+
+    fn _message_section_of_main_mission(
+        &self,
+    ) -> &FnvMultiMap<u32, Arc<model::message::MessageSectionConfig>> {
+        self._message_section_of_main_mission.get_or_init(|| {
+            self._message_section_config()
+                .values()
+                .filter_map(|section| {
+                    section
+                        .main_mission_link
+                        .map(|id| (id.get(), Arc::clone(section)))
+                })
+                .collect()
+        })
+    }
+
     fn _extra_effect_config_by_name(
         &self,
     ) -> &FnvHashMap<Arc<str>, Arc<model::misc::ExtraEffectConfig>> {
@@ -764,6 +909,32 @@ impl SealedGameData for GameData {
         })
     }
 
+    fn _rogue_miracle_by_name(&self) -> &FnvHashMap<Arc<str>, Arc<model::rogue::RogueMiracle>> {
+        self._rogue_miracle_by_name
+            .get_or_init(|| self.miracle_by_name(self._rogue_miracle()))
+    }
+
+    fn _rogue_magic_miracle_by_name(
+        &self,
+    ) -> &FnvHashMap<Arc<str>, Arc<model::rogue::RogueMiracle>> {
+        self._rogue_magic_miracle_by_name
+            .get_or_init(|| self.miracle_by_name(self._rogue_magic_miracle()))
+    }
+
+    fn _rogue_tourn_miracle_by_name(
+        &self,
+    ) -> &FnvHashMap<Arc<str>, Arc<model::rogue::tourn::RogueTournMiracle>> {
+        self._rogue_tourn_miracle_by_name.get_or_init(|| {
+            self._rogue_tourn_miracle()
+                .values()
+                .filter_map(|miracle| {
+                    self.miracle_display_name(miracle.miracle_display_id)
+                        .map(|name| (Arc::clone(name), Arc::clone(miracle)))
+                })
+                .collect()
+        })
+    }
+
     fn _load_story(
         &self,
         path: impl AsRef<std::path::Path>,
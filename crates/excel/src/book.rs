@@ -1,7 +1,8 @@
 use std::borrow::Cow;
 
-use base::Wiki;
+use base::{Name, Template, Wiki};
 
+use crate::wiki::{render_installed, Site, WikiContext};
 use crate::{ExcelOutput, FromModel};
 
 #[derive(Clone, Debug)]
@@ -51,26 +52,77 @@ impl<'a, Data: ExcelOutput> FromModel<'a, Data> for BookSeriesConfig<'a, Data> {
     }
 }
 
-impl<Data: ExcelOutput + format::GameData> Wiki for BookSeriesConfig<'_, Data> {
-    fn wiki(&self) -> std::borrow::Cow<'static, str> {
-        let mut wiki = String::from("{{书籍");
-        let mut formatter = format::Formatter::new(self.game)
-            .media_wiki_syntax(true)
-            .newline_after_block(true);
+/// 模板 book_series.jinja 的上下文
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct BookSeriesContext {
+    /// 已按 wiki 语法格式化的书名
+    pub name: String,
+    /// 总卷数
+    pub num: u8,
+    pub comments: String,
+    /// 所属世界 ID
+    pub world: u8,
+    /// 所属世界名称
+    pub world_name: String,
+    /// 第一卷物品图标的文件名（不含扩展名），用于区分书籍、信件、石碑等，大地图或者剧情中散落的阅读物为 0
+    pub icon: u32,
+    /// 按图标区分的类型在站点上的名称，如 BWIKI 的「书籍」「信件」，未知图标为空
+    pub kind: &'static str,
+    pub books: Vec<BookContext>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct BookContext {
+    pub name: String,
+    /// 卷号，总卷数不少于 10 卷时补零到两位
+    pub volume: String,
+    /// 已按 wiki 语法格式化的内容，末尾的一个换行已去掉
+    pub content: String,
+}
+
+/// 图标到阅读物类型，按顺序分别是 雅利洛 | 空间站黑塔 | 仙舟罗浮 | 匹诺康尼，特殊图标单独备注
+/// 140236 目前只有罗浮的《钟珊的来信》和匹诺康尼的《关于财富学院代表的联名投诉信》
+/// 石碑、拓印、如意只出现在罗浮，便条、录像带只出现在匹诺康尼，回响只出现在翁法罗斯
+fn book_kind(site: Site, icon: u32) -> &'static str {
+    match (site, icon) {
+        (Site::Bwiki, 0) => "资料",
+        (Site::Bwiki, 190001 | 190004 | 190007 | 190016 | 190017) => "书籍",
+        (Site::Bwiki, 190002 | 190005 | 190008 | 190015 | 190018) => "资料",
+        (Site::Bwiki, 190003 | 190006 | 190009) => "信件",
+        (Site::Bwiki, 140236) => "信件2",
+        (Site::Bwiki, 190010 | 190020) => "石碑",
+        (Site::Bwiki, 190011) => "拓印",
+        (Site::Bwiki, 190012) => "如意",
+        (Site::Bwiki, 190013) => "便条",
+        (Site::Bwiki, 190014) => "录像带",
+        (Site::Bwiki, 190019) => "回响",
+        (Site::Fandom, 0) => "Document",
+        (Site::Fandom, 190001 | 190004 | 190007 | 190016 | 190017) => "Book",
+        (Site::Fandom, 190002 | 190005 | 190008 | 190015 | 190018) => "Document",
+        (Site::Fandom, 190003 | 190006 | 190009 | 140236) => "Letter",
+        (Site::Fandom, 190010 | 190020) => "Stone Tablet",
+        (Site::Fandom, 190011) => "Rubbing",
+        (Site::Fandom, 190012) => "Ruyi",
+        (Site::Fandom, 190013) => "Note",
+        (Site::Fandom, 190014) => "Videotape",
+        (Site::Fandom, 190019) => "Echo",
+        _ => "",
+    }
+}
+
+impl<Data: ExcelOutput + format::GameData> WikiContext for BookSeriesConfig<'_, Data> {
+    const TEMPLATE: &'static str = "book_series";
+    type Context<'c>
+        = BookSeriesContext
+    where
+        Self: 'c;
+
+    fn wiki_context(&self, site: Site) -> BookSeriesContext {
+        let mut formatter = site.formatter(self.game).newline_after_block(true);
         let books = self
             .game
             .localbook_in_book_series(self.id)
             .collect::<Vec<_>>();
-        wiki.push_str("\n|书籍名=");
-        wiki.push_str(&formatter.format(self.name, &[]));
-        wiki.push_str("\n|卷数=");
-        wiki.push_str(&self.num.to_string());
-        wiki.push_str("\n|编号=");
-        wiki.push_str("\n|描述=");
-        wiki.push_str(&formatter.format(self.comments, &[]));
-        wiki.push_str("\n|所属=");
-        wiki.push_str(&self.world.id.to_string());
-        wiki.push_str("\n|类型=");
         let icon = None
             .or_else(|| self.game.item_config(books[0].id))
             .or_else(|| self.game.item_config_book(books[0].id))
@@ -81,46 +133,57 @@ impl<Data: ExcelOutput + format::GameData> Wiki for BookSeriesConfig<'_, Data> {
             .map(|(id, _)| id)
             .and_then(|id| id.parse::<u32>().ok())
             .unwrap_or_default();
-        wiki.push_str(match icon {
-            0 => "资料", // 大地图或者剧情中散落的阅读物
-            // 按顺序分别是 雅利洛 | 空间站黑塔 | 仙舟罗浮 | 匹诺康尼
-            // 特殊图标单独备注
-            190001 | 190004 | 190007 | 190016 | 190017 => "书籍",
-            190002 | 190005 | 190008 | 190015 | 190018 => "资料",
-            190003 | 190006 | 190009 => "信件",
-            140236 => "信件2", // 目前只有罗浮的《钟珊的来信》和匹诺康尼的《关于财富学院代表的联名投诉信》
-            190010 | 190020 => "石碑", // 只出现在罗浮
-            190011 => "拓印",  // 只出现在罗浮
-            190012 => "如意",  // 只出现在罗浮
-            190013 => "便条",  // 只出现在匹诺康尼
-            190014 => "录像带", // 只出现在匹诺康尼
-            190019 => "回响",  // 只出现在翁法罗斯
-            _ => unreachable!("可能是新版本新增不同类型的图书 {} {}", self.name, icon),
-        });
-        wiki.push_str("\n|实装版本=");
-        wiki.push_str("\n|相关角色=");
-        wiki.push_str("\n|相关任务=");
-        wiki.push_str("\n}}");
-        for (index, book) in books.iter().enumerate() {
-            wiki.push_str("\n\n{{书籍/分卷");
-            wiki.push_str("\n|名称=");
-            wiki.push_str(&formatter.format(book.inside_name, &[]));
-            wiki.push_str("\n|卷数=");
-            wiki.push_str(&format!(
-                "{:01$}",
-                index + 1,
-                if self.num < 10 { 1 } else { 2 },
-            ));
-            wiki.push_str("\n|实装版本=");
-            wiki.push_str("\n|获取方式=");
-            wiki.push_str("\n|内容=");
-            wiki.push_str(&formatter.format(book.content, &[]));
-            if wiki.as_bytes().last() != Some(&b'\n') {
-                wiki.push('\n');
-            }
-            wiki.push_str("}}");
+        let width = if self.num < 10 { 1 } else { 2 };
+        BookSeriesContext {
+            name: formatter.format(self.name, &[]),
+            num: self.num,
+            comments: formatter.format(self.comments, &[]),
+            world: self.world.id,
+            world_name: formatter.format(self.world.name, &[]),
+            icon,
+            kind: book_kind(site, icon),
+            books: books
+                .iter()
+                .enumerate()
+                .map(|(index, book)| {
+                    let mut content = formatter.format(book.content, &[]);
+                    if content.ends_with('\n') {
+                        content.pop();
+                    }
+                    BookContext {
+                        name: formatter.format(book.inside_name, &[]),
+                        volume: format!("{:01$}", index + 1, width),
+                        content,
+                    }
+                })
+                .collect(),
         }
-        Cow::Owned(wiki)
+    }
+}
+
+impl<Data: ExcelOutput + format::GameData> Name for BookSeriesConfig<'_, Data> {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    // 书名中可能有 {NICKNAME} 之类的占位符和富文本标签
+    fn wiki_name(&self) -> Cow<'_, str> {
+        Cow::Owned(
+            format::Formatter::new(self.game)
+                .normal_form(true)
+                .format(self.name, &[]),
+        )
+    }
+}
+
+impl<Data: ExcelOutput + format::GameData> Wiki for BookSeriesConfig<'_, Data> {
+    fn wiki(&self) -> std::borrow::Cow<'static, str> {
+        render_installed(self)
+    }
+
+    /// 页面开头的 {{书籍}}，各分卷的 {{书籍/分卷}} 不包括在内
+    fn wiki_template(&self) -> Option<Template> {
+        crate::wiki::main_template(&self.wiki())
     }
 }
 
@@ -1,12 +1,13 @@
 use std::{borrow::Cow, collections::HashMap, num::NonZero};
 
-use base::{FnvIndexMap, FnvMultiMap, Name, Wiki};
+use base::{FnvIndexMap, Name, Template, Wiki};
 pub use model::{
     challenge::{ChallengeGroupType, ChallengeStoryType, ChallengeTargetType},
     monster::MonsterRank,
     Element,
 };
 
+use crate::wiki::{render_installed, Site, WikiContext};
 use crate::{ExcelOutput, FromModel};
 
 #[derive(educe::Educe)]
@@ -17,12 +18,12 @@ pub struct ChallengeGroupConfig<'a, Data: ExcelOutput + ?Sized> {
     pub id: u16,
     pub name: &'a str,
     pub reward_line_group: Vec<ChallengeRewardLine<'a>>,
-    pub pre_mission: crate::mission::MainMission<'a>,
+    pub pre_mission: crate::mission::MainMission<'a, Data>,
     pub global_schedule: Option<crate::misc::ScheduleDataGlobal>,
     // 两个常驻混沌回忆没有 schedule_data
     pub schedule_data: Option<crate::misc::ScheduleData>,
     pub maze_buff: Option<crate::misc::MazeBuff<'a>>,
-    pub map_entrance: Option<crate::map::MapEntrance<'a>>,
+    pub map_entrance: Option<crate::map::MapEntrance<'a, Data>>,
     pub mapping_info: Vec<crate::map::MappingInfo<'a, Data>>,
     pub world: Option<crate::map::WorldDataConfig<'a>>,
     pub r#type: ChallengeGroupType,
@@ -98,9 +99,6 @@ impl<'a, Data: ExcelOutput> ChallengeGroupConfig<'a, Data> {
         Element::Thunder,
         Element::Wind,
     ];
-    const CHNUM: [&'static str; 12] = [
-        "一", "二", "三", "四", "五", "六", "七", "八", "九", "十", "十一", "十二",
-    ];
 
     // 第几期，混沌回忆特殊一些
     pub fn issue(&self) -> u16 {
@@ -136,38 +134,46 @@ impl<'a, Data: ExcelOutput> ChallengeGroupConfig<'a, Data> {
         })
     }
 
-    fn wiki_write_sched(&self, wiki: &mut String) {
-        if let Some(sched) = &self.schedule_data {
-            wiki.push_str("\n|开始时间=");
-            wiki.push_str(&sched.begin_time.format("%Y/%m/%d %H:%M").to_string());
-            wiki.push_str("\n|结束时间=");
-            let end_time = sched.end_time - chrono::TimeDelta::nanoseconds(1);
-            wiki.push_str(&end_time.format("%Y/%m/%d %H:%M").to_string());
-        }
+    fn sched_times(&self) -> (Option<String>, Option<String>) {
+        let Some(sched) = &self.schedule_data else {
+            return (None, None);
+        };
+        let end_time = sched.end_time - chrono::TimeDelta::nanoseconds(1);
+        (
+            Some(sched.begin_time.format("%Y/%m/%d %H:%M").to_string()),
+            Some(end_time.format("%Y/%m/%d %H:%M").to_string()),
+        )
     }
 
-    fn special_monster_wiki(
+    fn special_monster_wikis(
         &self,
         specials: FnvIndexMap<u32, &crate::monster::MonsterConfig<Data>>,
         mut floors: HashMap<u32, Vec<u8>>,
-    ) -> Cow<'static, str> {
-        let mut wiki = String::new();
+    ) -> Vec<String> {
         for floors in floors.values_mut() {
             floors.sort();
             floors.dedup();
         }
-        if !specials.is_empty() {
-            wiki.push_str("\n{{折叠面板（特殊敌方）|tsdf");
-            wiki.push_str(&format!("{:03}", self.issue()));
-            wiki.push('|');
-            for (id, monster) in specials {
-                let special_wiki = monster.special_wiki(self.name, &floors[&id]);
-                wiki.push_str(&special_wiki);
-                wiki.push('\n');
-            }
-            wiki.push_str("}}");
+        specials
+            .into_iter()
+            .map(|(id, monster)| monster.special_wiki(self.name, &floors[&id]))
+            .collect()
+    }
+
+    /// 特殊敌方，BWIKI 为 {{特殊敌方}} 折叠面板的内容，其它站点为页面名
+    fn special_monster_contexts(
+        &self,
+        site: Site,
+        specials: FnvIndexMap<u32, &crate::monster::MonsterConfig<Data>>,
+        floors: HashMap<u32, Vec<u8>>,
+    ) -> Vec<String> {
+        match site {
+            Site::Bwiki => self.special_monster_wikis(specials, floors),
+            Site::Fandom => specials
+                .values()
+                .map(|monster| monster.page_name(site).into_owned())
+                .collect(),
         }
-        Cow::Owned(wiki)
     }
 
     fn monster_score(&self, monster: &crate::monster::MonsterConfig<Data>) -> u32 {
@@ -200,99 +206,48 @@ impl<'a, Data: ExcelOutput> ChallengeGroupConfig<'a, Data> {
 
 // 混沌回忆相关方法
 impl<Data: ExcelOutput + format::GameData> ChallengeGroupConfig<'_, Data> {
-    fn wiki_write_buff(
-        &self,
-        wiki: &mut String,
-        formatter: &mut format::Formatter<Data>,
-        title: &str,
-        buff: Option<&crate::misc::MazeBuff>,
-    ) {
-        if let Some(buff) = buff {
-            wiki.push_str("\n|");
-            wiki.push_str(title);
-            wiki.push('=');
-            wiki.push_str(&formatter.format(buff.desc, &buff.params));
-        }
-    }
-
-    fn memory_wiki_write_event(
-        &self,
-        wiki: &mut String,
-        floor: u8,
-        team: u8,
-        event: &crate::battle::StageConfig<Data>,
-        weaknesses: &[Element],
-    ) {
-        let floor_team = format!("\n|其{}{}队", Self::CHNUM[floor as usize - 1], team);
-        for (wave_no, wave) in event.monster_list.iter().enumerate() {
-            wiki.push_str(&floor_team);
-            wiki.push_str(&(wave_no + 1).to_string());
-            wiki.push_str("波=");
-            let monster_names: String = wave
-                .iter()
-                .map(crate::monster::MonsterConfig::wiki_name)
-                .intersperse(Cow::Borrowed("、"))
-                .collect();
-            wiki.push_str(&monster_names);
-        }
-        let weakness: String = weaknesses
-            .iter()
-            .rev()
-            .map(Element::wiki)
-            .intersperse(Cow::Borrowed("、"))
-            .collect();
-        wiki.push_str(&floor_team);
-        wiki.push_str("建议属性=");
-        wiki.push_str(&weakness);
-    }
-
-    fn memory_wiki(&self) -> Cow<'static, str> {
-        let mut formatter = format::Formatter::new(self.game).media_wiki_syntax(true);
-        if self.id < 1007 {
-            // 更早以往的混沌回忆机制不太一样
-            // 比如同一层存在两个敌方 Boss 左右分立（具体见游戏内常驻回忆「永屹之城的遗秘」回忆其六）
-            // 左右分立对应到数据上是 event_list_1.len() == 2
-            return Cow::Borrowed("<!-- 过旧数据，不考虑兼容 -->");
-        }
-        let mut wiki = String::from("{{混沌回忆单期3");
-        wiki.push_str("\n|期数=");
-        wiki.push_str(&format!("{:03}", self.issue()));
-        wiki.push_str("\n|名称=");
-        wiki.push_str(self.name);
-        self.wiki_write_sched(&mut wiki);
-        self.wiki_write_buff(
-            &mut wiki,
-            &mut formatter,
-            "记忆紊流",
-            self.maze_buff.as_ref(),
-        );
+    fn memory_wiki_context(&self, site: Site) -> ChallengeMemoryContext<'_> {
+        let mut formatter = site.formatter(self.game);
+        let (begin_time, end_time) = self.sched_times();
         let mazes = self.mazes();
         for maze in mazes {
             assert_eq!(maze.event_list_1.len(), 1, "上半场景中无分立敌方首领");
             assert_eq!(maze.event_list_2.len(), 1, "下半场景中无分立敌方首领");
         }
+        let team = |event: &crate::battle::StageConfig<Data>, weaknesses: &[Element]| {
+            ChallengeMemoryTeamContext {
+                waves: event
+                    .monster_list
+                    .iter()
+                    .map(|wave| {
+                        wave.iter()
+                            .map(|monster| monster.page_name(site).into_owned())
+                            .collect()
+                    })
+                    .collect(),
+                weaknesses: weaknesses
+                    .iter()
+                    .rev()
+                    .map(|&element| site.element(element))
+                    .collect(),
+            }
+        };
         let mut specials = FnvIndexMap::default();
         let mut floors = HashMap::<_, Vec<u8>>::new();
+        let mut floor_contexts = Vec::new();
         for (index, maze) in mazes.iter().enumerate() {
             let floor = if maze.floor != 0 {
                 maze.floor
             } else {
                 index as u8 + 1
             };
-            self.memory_wiki_write_event(
-                &mut wiki,
-                floor,
-                1,
-                &maze.event_list_1[0],
-                maze.damage_type_1,
-            );
-            self.memory_wiki_write_event(
-                &mut wiki,
+            floor_contexts.push(ChallengeMemoryFloorContext {
                 floor,
-                2,
-                &maze.event_list_2[0],
-                maze.damage_type_2,
-            );
+                teams: vec![
+                    team(&maze.event_list_1[0], maze.damage_type_1),
+                    team(&maze.event_list_2[0], maze.damage_type_2),
+                ],
+            });
             [&maze.event_list_1[0], &maze.event_list_2[0]]
                 .into_iter()
                 .flat_map(|event| &event.monster_list)
@@ -303,32 +258,48 @@ impl<Data: ExcelOutput + format::GameData> ChallengeGroupConfig<'_, Data> {
                     specials.insert(monster.id, monster);
                 });
         }
-        wiki.push_str("\n}}");
-        wiki.push_str(&self.special_monster_wiki(specials, floors));
-        wiki.push_str("\n<br />\n<br />\n----");
-        Cow::Owned(wiki)
+        ChallengeMemoryContext {
+            issue: self.issue(),
+            name: self.name,
+            begin_time,
+            end_time,
+            buff: self
+                .maze_buff
+                .as_ref()
+                .map(|buff| formatter.format(buff.desc, &buff.params)),
+            floors: floor_contexts,
+            special_monsters: self.special_monster_contexts(site, specials, floors),
+        }
     }
 }
 
+impl<Data: ExcelOutput + format::GameData> ChallengeGroupConfig<'_, Data> {}
+
 // 虚构叙事相关方法
 impl<Data: ExcelOutput + format::GameData> ChallengeGroupConfig<'_, Data> {
+    /// 一波敌方中满足条件的敌方及其数量，按首次出现的顺序
     fn aggregate_monster<F>(
-        monster: &[crate::monster::MonsterConfig<Data>],
-        monster_counts: &FnvIndexMap<Cow<'_, str>, usize>,
+        site: Site,
+        monsters: &[crate::monster::MonsterConfig<Data>],
         is_elite: F,
-    ) -> String
+    ) -> Vec<ChallengeMonsterCountContext>
     where
         F: Fn(&crate::monster::MonsterConfig<Data>) -> bool,
     {
-        monster
+        let mut monster_counts = FnvIndexMap::<Cow<'_, str>, usize>::default();
+        for monster in monsters {
+            *monster_counts.entry(monster.page_name(site)).or_default() += 1;
+        }
+        monsters
             .iter()
             .filter(|&monster| is_elite(monster))
-            .map(crate::monster::MonsterConfig::wiki_name)
+            .map(|monster| monster.page_name(site))
             .collect::<indexmap::IndexSet<_, fnv::FnvBuildHasher>>()
             .into_iter()
-            .map(|name| format!("{name}:{}", monster_counts[&name]))
-            .map(Cow::Owned)
-            .intersperse(Cow::Borrowed("、"))
+            .map(|name| ChallengeMonsterCountContext {
+                count: monster_counts[&name],
+                name: name.into_owned(),
+            })
             .collect()
     }
 
@@ -357,232 +328,58 @@ impl<Data: ExcelOutput + format::GameData> ChallengeGroupConfig<'_, Data> {
         }
     }
 
-    /// story_write_event 解析一层中每一波次的敌人的信息
-    /// 如第四层下半第 3 波的信息，会往 wiki 中写入 "|其四2队3波敌人=......"
-    fn story_wiki_write_event(
+    /// 计算每一种弱点在一队中能拿到的分数
+    /// 就是假如只将含有某种弱点的怪物全部击败，所能获得的总分
+    fn story_weakness_scores(
         &self,
-        wiki: &mut String,
-        floor: u8,
-        team: u8,
+        site: Site,
         event: &crate::battle::StageConfig<Data>,
         weakness: &[Element],
-    ) {
-        let floor_team = format!("\n|其{}{}队", Self::CHNUM[floor as usize - 1], team);
-        let infinite_group = event.infinite_group().unwrap();
+    ) -> Vec<ChallengeWeaknessScoreContext> {
         let mut weakness_scores =
             fnv::FnvHashMap::from_iter(Self::ELEMENTS.into_iter().map(|element| (element, 0)));
-        let mut max_teammate = [0; 3];
-        let story_type = self
-            .extra()
-            .story_type
-            .unwrap_or(ChallengeStoryType::Normal);
-        for (wave_no, wave) in infinite_group.wave_list.iter().enumerate() {
-            max_teammate[wave_no] = wave.max_teammate_count;
-            let wave_no = (wave_no + 1).to_string();
-            let monsters = &wave.monster_group_list[0].monster_list;
-            let monster_counts = monsters
-                .iter()
-                .map(|monster| (monster.wiki_name(), ()))
-                .collect::<FnvMultiMap<_, _>>()
-                .into_iter()
-                .map(|(name, vec)| (name, vec.len()))
-                .collect::<FnvIndexMap<_, _>>();
-            if story_type == ChallengeStoryType::Normal {
-                // 计算每一种弱点在每一波中能拿到的分数
-                // 就是假如只将含有某种弱点的怪物全部击败，所能获得的总分
-                for monster in monsters {
-                    let weaknesses = if monster.wiki_name() == "王下一桶" {
-                        &Self::ELEMENTS // 「王下一桶」作为全属性弱点对待
-                    } else {
-                        monster.stance_weak_list
-                    };
-                    for weakness in weaknesses {
-                        *weakness_scores.entry(*weakness).or_default() +=
-                            self.monster_score(monster)
-                    }
+        for wave in event.infinite_group().unwrap().wave_list {
+            for monster in &wave.monster_group_list[0].monster_list {
+                let weaknesses = if monster.wiki_name() == "王下一桶" {
+                    &Self::ELEMENTS // 「王下一桶」作为全属性弱点对待
+                } else {
+                    monster.stance_weak_list
+                };
+                for weakness in weaknesses {
+                    *weakness_scores.entry(*weakness).or_default() += self.monster_score(monster)
                 }
             }
-            let normal_names =
-                Self::aggregate_monster(monsters, &monster_counts, |monster| match story_type {
-                    ChallengeStoryType::Normal => self.monster_score(monster) == 500,
-                    ChallengeStoryType::Fever => monster.is_minion(),
-                });
-            wiki.push_str(&floor_team);
-            wiki.push_str(&wave_no);
-            wiki.push_str("波=");
-            wiki.push_str(&normal_names);
-            let elite_names =
-                Self::aggregate_monster(monsters, &monster_counts, |monster| match story_type {
-                    ChallengeStoryType::Normal => self.monster_score(monster) > 500,
-                    ChallengeStoryType::Fever => !monster.is_minion(),
-                });
-            wiki.push_str(&floor_team);
-            wiki.push_str(&wave_no);
-            wiki.push_str("波特殊敌方=");
-            wiki.push_str(&elite_names);
-        }
-        wiki.push_str(&floor_team);
-        wiki.push_str("敌方上限=");
-        if max_teammate[0] == max_teammate[1] && max_teammate[1] == max_teammate[2] {
-            wiki.push_str(&max_teammate[0].to_string());
-        } else {
-            let max_teammate: String = max_teammate
-                .iter()
-                .map(u8::to_string)
-                .map(Cow::Owned)
-                .intersperse(Cow::Borrowed("/"))
-                .collect();
-            wiki.push_str(&max_teammate);
         }
-        let weaknesses: String = weakness
-            .iter()
-            .rev()
-            .map(Element::wiki)
-            .intersperse(Cow::Borrowed("、"))
-            .collect();
-        wiki.push_str(&floor_team);
-        wiki.push_str("建议属性=");
-        wiki.push_str(&weaknesses);
-        if story_type == ChallengeStoryType::Normal {
-            // 按顺序打印出所有弱点积分
-            let mut weakness_scores = weakness_scores.into_iter().collect::<Vec<_>>();
-            // 按照弱点积分从大到小排序
-            // 建议属性无条件排到前面。当积分一致时候，在建议属性中的按照建议顺序排序，否则按照英文字典序
-            weakness_scores.sort_by(|l, r| {
-                let l_in_weak = weakness.iter().position(|&weakness| weakness == l.0);
-                let r_in_weak = weakness.iter().position(|&weakness| weakness == r.0);
-                match (l_in_weak, r_in_weak) {
-                    (None, None) => {
-                        if l.1 != r.1 {
-                            return u32::cmp(&r.1, &l.1); // 注意排序是逆向的
-                        }
-                        u8::cmp(&(l.0 as u8), &(r.0 as u8))
-                    }
-                    (None, Some(_)) => std::cmp::Ordering::Greater,
-                    (Some(_), None) => std::cmp::Ordering::Less,
-                    (Some(l_index), Some(r_index)) => {
-                        if l.1 != r.1 {
-                            return u32::cmp(&r.1, &l.1); // 注意排序是逆向的
-                        }
-                        usize::cmp(&l_index, &r_index)
+        let mut weakness_scores = weakness_scores.into_iter().collect::<Vec<_>>();
+        // 按照弱点积分从大到小排序
+        // 建议属性无条件排到前面。当积分一致时候，在建议属性中的按照建议顺序排序，否则按照英文字典序
+        weakness_scores.sort_by(|l, r| {
+            let l_in_weak = weakness.iter().position(|&weakness| weakness == l.0);
+            let r_in_weak = weakness.iter().position(|&weakness| weakness == r.0);
+            match (l_in_weak, r_in_weak) {
+                (None, None) => {
+                    if l.1 != r.1 {
+                        return u32::cmp(&r.1, &l.1); // 注意排序是逆向的
                     }
+                    u8::cmp(&(l.0 as u8), &(r.0 as u8))
                 }
-            });
-            let weakness_scores: String = weakness_scores
-                .into_iter()
-                .map(|(weakness, score)| format!("{}:{score}", weakness.wiki()))
-                .map(Cow::Owned)
-                .intersperse(Cow::Borrowed("、"))
-                .collect();
-            wiki.push_str(&floor_team);
-            wiki.push_str("弱点分数=");
-            wiki.push_str(&weakness_scores);
-        }
-    }
-
-    fn story_wiki_write_wave_ability(
-        wiki: &mut String,
-        floor: u8,
-        team: u8,
-        event: &crate::battle::StageConfig<Data>,
-    ) {
-        let floor_team = format!("\n|其{}{}队", Self::CHNUM[floor as usize - 1], team);
-        let infinite_group = event.infinite_group().unwrap();
-        for (wave_no, wave) in infinite_group.wave_list.iter().enumerate() {
-            match wave.ability {
-                "FantasticStory_Wave_Ability_0001" => {
-                    let wave_no = (wave_no + 1).to_string();
-                    wiki.push_str(&floor_team);
-                    wiki.push_str(&wave_no);
-                    wiki.push_str("波攻击增幅=");
-                    let attack_ratio = f32::round(100. * wave.param_list[0]) as u16;
-                    if attack_ratio != 0 {
-                        wiki.push_str(&attack_ratio.to_string());
-                        wiki.push('%');
-                    }
-                    wiki.push_str(&floor_team);
-                    wiki.push_str(&wave_no);
-                    wiki.push_str("波生命增幅=");
-                    let hp_ratio = f32::round(100. * wave.param_list[1]) as u16;
-                    if hp_ratio != 0 {
-                        wiki.push_str(&hp_ratio.to_string());
-                        wiki.push('%');
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (Some(l_index), Some(r_index)) => {
+                    if l.1 != r.1 {
+                        return u32::cmp(&r.1, &l.1); // 注意排序是逆向的
                     }
+                    usize::cmp(&l_index, &r_index)
                 }
-                "" => (), // 旧版虚构叙事，懒得处理了
-                // 虚构叙事定制的 Ability，目前只有给怪物增幅攻击和生命。
-                // 具体配置文件见 Config/ConfigAbility/BattleEvent/FantasticStory_Wave_Ability.json
-                // assert 的 ability 的效果是按 param_list 分别增幅攻击和生命上限
-                // 解析 Config 非常复杂，先写死，如果有改动的话，就人工处理吧
-                _ => unreachable!(),
             }
-        }
-    }
-
-    fn story_special_monster_wiki(
-        &self,
-        mazes: &[ChallengeMazeConfig<Data>],
-        infinite_groups: &[crate::battle::StageInfiniteGroup<Data>],
-    ) -> Cow<'static, str> {
-        let mut specials = FnvIndexMap::default();
-        let mut floors = HashMap::<_, Vec<u8>>::new();
-        for (index, maze) in mazes.iter().enumerate() {
-            let floor = if maze.floor != 0 {
-                maze.floor
-            } else {
-                index as u8 + 1
-            };
-            infinite_groups[index * 2..index * 2 + 2]
-                .iter()
-                .flat_map(|group| &group.wave_list)
-                .flat_map(|wave| &wave.monster_group_list)
-                .flat_map(|group| &group.monster_list)
-                .filter(|monster| monster.is_special())
-                .for_each(|monster| {
-                    floors.entry(monster.id).or_default().push(floor);
-                    specials.insert(monster.id, monster);
-                });
-        }
-        self.special_monster_wiki(specials, floors)
-    }
-
-    fn story_reinforce_wiki(&self, mazes: &[ChallengeMazeConfig<Data>]) -> String {
-        let mut wiki = String::from("{{虚构叙事增援序列");
-        fn handle<Data: ExcelOutput>(
-            wiki: &mut String,
-            event: &crate::battle::StageConfig<Data>,
-            floor: u8,
-            team: u8,
-        ) {
-            let infinite_group = event.infinite_group().unwrap();
-            for (wave_no, wave) in infinite_group.wave_list.into_iter().enumerate() {
-                let wave_no = (wave_no + 1).to_string();
-                let monsters = &wave.monster_group_list[0].monster_list;
-                let reinforces: String = monsters
-                    .iter()
-                    .map(crate::monster::MonsterConfig::wiki_name)
-                    .intersperse(Cow::Borrowed("、"))
-                    .collect();
-                wiki.push_str("\n|其");
-                wiki.push_str(ChallengeGroupConfig::<Data>::CHNUM[floor as usize - 1]);
-                wiki.push_str(if team == 1 { "上半" } else { "下半" });
-                wiki.push('第');
-                wiki.push_str(&wave_no);
-                wiki.push_str("波=");
-                wiki.push_str(&reinforces);
-            }
-        }
-        for (index, maze) in mazes.iter().enumerate() {
-            let floor = if maze.floor != 0 {
-                maze.floor
-            } else {
-                index as u8 + 1
-            };
-            handle(&mut wiki, &maze.event_list_1[0], floor, 1);
-            handle(&mut wiki, &maze.event_list_2[0], floor, 2);
-        }
-        wiki.push_str("\n}}");
-        wiki
+        });
+        weakness_scores
+            .into_iter()
+            .map(|(element, score)| ChallengeWeaknessScoreContext {
+                element: site.element(element),
+                score,
+            })
+            .collect()
     }
 
     /// 用来确保 monster_score 返回的值是正确的
@@ -621,19 +418,15 @@ impl<Data: ExcelOutput + format::GameData> ChallengeGroupConfig<'_, Data> {
         }
     }
 
-    fn story_wiki_elite_group_in_comments(
+    /// 各波次敌方的属性增幅（降幅），用于提示虚构叙事敌方阵容是否换代
+    fn story_elite_groups(
         &self,
         mazes: &[ChallengeMazeConfig<Data>],
-    ) -> Cow<'static, str> {
-        let mut wiki = String::new();
-        // 下面的注释用于提示虚构叙事敌方阵容是否换代
-        let mut elite_groups = std::collections::HashMap::<u16, crate::monster::EliteGroup>::new();
-        let mut elite_application = multimap::MultiMap::<u16, String>::new();
+    ) -> Vec<ChallengeEliteGroupContext> {
+        let percent = |ratio: f32| f64::round(ratio as f64 * 10000.) / 100.;
+        let mut elite_groups = FnvIndexMap::<u16, ChallengeEliteGroupContext>::default();
         for maze in mazes {
-            for (half, event) in [
-                ("上半", &maze.event_list_1[0]),
-                ("下半", &maze.event_list_2[0]),
-            ] {
+            for (team, event) in [(1, &maze.event_list_1[0]), (2, &maze.event_list_2[0])] {
                 for (wave_no, wave) in event.infinite_group().unwrap().wave_list.iter().enumerate()
                 {
                     const EMPTY_ELITE_GROUP: crate::monster::EliteGroup =
@@ -649,138 +442,170 @@ impl<Data: ExcelOutput + format::GameData> ChallengeGroupConfig<'_, Data> {
                         .elite_group
                         .as_ref()
                         .unwrap_or(&EMPTY_ELITE_GROUP);
-                    elite_application.insert(
-                        group.id,
-                        format!("第{}层{}第{}波", maze.floor, half, wave_no + 1),
-                    );
-                    elite_groups.insert(group.id, group.clone());
+                    elite_groups
+                        .entry(group.id)
+                        .or_insert_with(|| ChallengeEliteGroupContext {
+                            id: group.id,
+                            attack_ratio: percent(group.attack_ratio),
+                            defence_ratio: percent(group.defence_ratio),
+                            hp_ratio: percent(group.hp_ratio),
+                            speed_ratio: percent(group.speed_ratio),
+                            stance_ratio: percent(group.stance_ratio),
+                            waves: Vec::new(),
+                        })
+                        .waves
+                        .push([maze.floor, team, wave_no as u8 + 1]);
                 }
             }
         }
-        if elite_groups.len() == 1 {
-            wiki.push_str(&format!(
-                "<!-- 当期敌方属性增幅（降幅）： {:?} -->",
-                elite_groups.into_values().next().unwrap()
-            ));
-        } else {
-            wiki.push_str("<!-- 当期敌方属性增幅（降幅）：\n");
-            for (id, group) in &elite_groups {
-                let applied = elite_application.get_vec(id).unwrap().join("、");
-                wiki.push_str(&format!("{applied}：{group:?}\n"));
-            }
-            wiki.push_str("-->");
-        }
-        Cow::Owned(wiki)
+        elite_groups.into_values().collect()
     }
 
-    fn story_wiki(&self) -> Cow<'static, str> {
-        let mut formatter = format::Formatter::new(self.game).media_wiki_syntax(true);
+    fn story_wiki_context(&self, site: Site) -> ChallengeStoryContext<'_> {
+        let mut formatter = site.formatter(self.game);
         let mazes = self.mazes();
         // 开头两个 assert 确保数据一致性
         self.story_wiki_assertions(mazes);
         self.story_wiki_score_assertions(mazes, self.extra());
         let extra = self.extra();
+        let story_type = extra.story_type.unwrap_or(ChallengeStoryType::Normal);
+        let fever = story_type == ChallengeStoryType::Fever;
         assert_eq!(extra.buff_list.len(), 3, "虚构叙事固定三个增益");
-        if extra.story_type == Some(ChallengeStoryType::Fever) {
+        if fever {
             assert_eq!(
                 extra.sub_maze_buff_list.len(),
                 3,
                 "新版虚构虚构叙事固定三个战意机制"
             );
         }
-
-        let mut wiki = String::from("{{虚构叙事单期");
-        if extra.story_type == Some(ChallengeStoryType::Fever) {
-            // 虚构叙事第 11 期开始更新为新版虚构叙事
-            // 主要是新增战意和 Boss 共享血量
-            // BWIKI 同时换模板
-            wiki.push('2');
-        }
-        wiki.push_str("\n|期数=");
-        wiki.push_str(&format!("{:03}", self.issue()));
-        wiki.push_str("\n|名称=");
-        wiki.push_str(self.name);
-        self.wiki_write_sched(&mut wiki);
-        match extra.story_type.unwrap_or(ChallengeStoryType::Normal) {
-            ChallengeStoryType::Normal => {
-                self.wiki_write_buff(
-                    &mut wiki,
-                    &mut formatter,
-                    "怪诞逸闻",
-                    self.maze_buff.as_ref(),
-                );
-            }
-            ChallengeStoryType::Fever => {
-                // 0 => 战意机制
-                // 1 => 战熄潮平
-                // 2 => 战意汹涌
-                wiki.push_str("\n|战意机制名称=");
-                wiki.push_str(extra.sub_maze_buff_list[0].name);
-                wiki.push_str("\n|战意机制效果=");
-                let buff = &extra.sub_maze_buff_list[0];
-                wiki.push_str(&formatter.format(buff.desc, &buff.params));
-                wiki.push_str("\n|战熄潮平=");
-                let buff = &extra.sub_maze_buff_list[1];
-                wiki.push_str(&formatter.format(buff.desc, &buff.params));
-                wiki.push_str("\n|战意汹涌=");
-                let buff = &extra.sub_maze_buff_list[2];
-                wiki.push_str(&formatter.format(buff.desc, &buff.params));
-            }
-        }
-        for (index, buff) in extra.buff_list.iter().enumerate() {
-            wiki.push_str("\n|荒腔");
-            wiki.push_str(&(index + 1).to_string());
-            wiki.push('=');
-            wiki.push_str(buff.name);
-            wiki.push_str("\n|荒腔走板其");
-            wiki.push_str(Self::CHNUM[index]);
-            wiki.push('=');
-            wiki.push_str(&formatter.format(buff.desc, &buff.params));
-            if extra.story_type == Some(ChallengeStoryType::Fever) {
-                wiki.push_str("\n|荒腔走板其");
-                wiki.push_str(Self::CHNUM[index]);
-                wiki.push_str("战意机制");
-                wiki.push('=');
-                wiki.push_str(&formatter.format(buff.simple_desc, &buff.params));
+        let (begin_time, end_time) = self.sched_times();
+        let team = |event: &crate::battle::StageConfig<Data>, weaknesses: &[Element]| {
+            let infinite_group = event.infinite_group().unwrap();
+            let waves =
+                infinite_group
+                    .wave_list
+                    .iter()
+                    .map(|wave| {
+                        let (attack_ratio, hp_ratio) = match wave.ability {
+                            "FantasticStory_Wave_Ability_0001" => (
+                                Some(f32::round(100. * wave.param_list[0]) as u16),
+                                Some(f32::round(100. * wave.param_list[1]) as u16),
+                            ),
+                            "" => (None, None), // 旧版虚构叙事，懒得处理了
+                            // 虚构叙事定制的 Ability，目前只有给怪物增幅攻击和生命。
+                            // 具体配置文件见 Config/ConfigAbility/BattleEvent/FantasticStory_Wave_Ability.json
+                            // assert 的 ability 的效果是按 param_list 分别增幅攻击和生命上限
+                            // 解析 Config 非常复杂，先写死，如果有改动的话，就人工处理吧
+                            _ => unreachable!(),
+                        };
+                        let monsters = &wave.monster_group_list[0].monster_list;
+                        ChallengeStoryWaveContext {
+                            monsters: monsters
+                                .iter()
+                                .map(|monster| monster.page_name(site).into_owned())
+                                .collect(),
+                            normal: Self::aggregate_monster(site, monsters, |monster| {
+                                match story_type {
+                                    ChallengeStoryType::Normal => {
+                                        self.monster_score(monster) == 500
+                                    }
+                                    ChallengeStoryType::Fever => monster.is_minion(),
+                                }
+                            }),
+                            elite: Self::aggregate_monster(site, monsters, |monster| {
+                                match story_type {
+                                    ChallengeStoryType::Normal => self.monster_score(monster) > 500,
+                                    ChallengeStoryType::Fever => !monster.is_minion(),
+                                }
+                            }),
+                            max_teammate: wave.max_teammate_count,
+                            attack_ratio,
+                            hp_ratio,
+                        }
+                    })
+                    .collect();
+            ChallengeStoryTeamContext {
+                waves,
+                weaknesses: weaknesses
+                    .iter()
+                    .rev()
+                    .map(|&element| site.element(element))
+                    .collect(),
+                weakness_scores: match story_type {
+                    ChallengeStoryType::Normal => {
+                        self.story_weakness_scores(site, event, weaknesses)
+                    }
+                    ChallengeStoryType::Fever => Vec::new(),
+                },
             }
-        }
-        for maze in mazes {
-            assert_eq!(maze.event_list_1.len(), 1, "只有一个 event");
-            assert_eq!(maze.event_list_2.len(), 1, "只有一个 event");
-        }
-        for maze in mazes {
-            self.story_wiki_write_event(
-                &mut wiki,
-                maze.floor,
-                1,
-                &maze.event_list_1[0],
-                maze.damage_type_1,
-            );
-            self.story_wiki_write_event(
-                &mut wiki,
-                maze.floor,
-                2,
-                &maze.event_list_2[0],
-                maze.damage_type_2,
-            );
-        }
-        for maze in mazes {
-            Self::story_wiki_write_wave_ability(&mut wiki, maze.floor, 1, &maze.event_list_1[0]);
-            Self::story_wiki_write_wave_ability(&mut wiki, maze.floor, 2, &maze.event_list_2[0]);
-        }
-        wiki.push_str("\n}}");
+        };
         let infinite_groups = mazes
             .iter()
             .flat_map(|maze| [&maze.event_list_1[0], &maze.event_list_2[0]])
             .map(crate::battle::StageConfig::infinite_group)
             .map(Option::unwrap)
             .collect::<Vec<_>>();
-        wiki.push_str(&self.story_special_monster_wiki(mazes, &infinite_groups));
-        wiki.push_str("\n<br />\n<br />\n----\n\n");
-        wiki.push_str(&self.story_reinforce_wiki(mazes));
-        wiki.push_str("\n\n");
-        wiki.push_str(&self.story_wiki_elite_group_in_comments(mazes));
-        Cow::Owned(wiki)
+        let mut specials = FnvIndexMap::default();
+        let mut special_floors = HashMap::<_, Vec<u8>>::new();
+        let mut floors = Vec::new();
+        for (index, maze) in mazes.iter().enumerate() {
+            let floor = if maze.floor != 0 {
+                maze.floor
+            } else {
+                index as u8 + 1
+            };
+            floors.push(ChallengeStoryFloorContext {
+                floor,
+                teams: vec![
+                    team(&maze.event_list_1[0], maze.damage_type_1),
+                    team(&maze.event_list_2[0], maze.damage_type_2),
+                ],
+            });
+            infinite_groups[index * 2..index * 2 + 2]
+                .iter()
+                .flat_map(|group| &group.wave_list)
+                .flat_map(|wave| &wave.monster_group_list)
+                .flat_map(|group| &group.monster_list)
+                .filter(|monster| monster.is_special())
+                .for_each(|monster| {
+                    special_floors.entry(monster.id).or_default().push(floor);
+                    specials.insert(monster.id, monster);
+                });
+        }
+        let buff = match story_type {
+            ChallengeStoryType::Normal => self.maze_buff.as_ref(),
+            ChallengeStoryType::Fever => extra.sub_maze_buff_list.first(),
+        }
+        .map(|buff| ChallengeBuffContext::new(&mut formatter, buff));
+        let grit = match story_type {
+            ChallengeStoryType::Normal => Vec::new(),
+            ChallengeStoryType::Fever => extra.sub_maze_buff_list[1..]
+                .iter()
+                .map(|buff| formatter.format(buff.desc, &buff.params))
+                .collect(),
+        };
+        let options = extra
+            .buff_list
+            .iter()
+            .map(|buff| ChallengeStoryOptionContext {
+                name: buff.name,
+                desc: formatter.format(buff.desc, &buff.params),
+                grit: fever.then(|| formatter.format(buff.simple_desc, &buff.params)),
+            })
+            .collect();
+        ChallengeStoryContext {
+            issue: self.issue(),
+            name: self.name,
+            begin_time,
+            end_time,
+            fever,
+            buff,
+            grit,
+            options,
+            floors,
+            special_monsters: self.special_monster_contexts(site, specials, special_floors),
+            elite_groups: self.story_elite_groups(mazes),
+        }
     }
 }
 
@@ -811,121 +636,287 @@ impl<Data: ExcelOutput + format::GameData> ChallengeGroupConfig<'_, Data> {
         }
     }
 
-    fn boss_wiki_write_tags(
-        &self,
-        wiki: &mut String,
-        team: u8,
-        event: &crate::battle::StageConfig<Data>,
-        extra: &ChallengeGroupExtra,
-    ) {
-        let mut formatter = format::Formatter::new(self.game).media_wiki_syntax(true);
-        let half = if team == 1 { "上半" } else { "下半" };
-        wiki.push_str("\n|");
-        wiki.push_str(half);
-        wiki.push_str("名称=");
-        wiki.push_str(event.name);
-        let monster = &event.monster_list[0][0];
-        wiki.push_str("\n|");
-        wiki.push_str(half);
-        wiki.push_str("首领=");
-        let name = if monster.name != "无望冽风的幻灭者" {
-            monster.wiki_name()
-        } else {
-            Cow::Borrowed("无望冽风的幻灭者•虚妄之母")
-        };
-        wiki.push_str(&name);
-        let guide = self.game.monster_guide_config(monster.id).unwrap();
-        for (tag_no, tag) in guide.tag_list.iter().enumerate() {
-            let tag_no = (tag_no + 1).to_string();
-            wiki.push_str("\n|");
-            wiki.push_str(half);
-            wiki.push_str("特性");
-            wiki.push_str(&tag_no);
-            wiki.push_str("类型=");
-            if tag_no == "1" || tag_no == "2" {
-                wiki.push_str("固有特性");
-            } else {
-                wiki.push_str("难度");
-                wiki.push_str(&tag_no);
-                wiki.push_str("增加");
-            }
-            wiki.push_str("\n|");
-            wiki.push_str(half);
-            wiki.push_str("特性");
-            wiki.push_str(&tag_no);
-            wiki.push_str("名称=");
-            wiki.push_str(tag.name);
-            wiki.push_str("\n|");
-            wiki.push_str(half);
-            wiki.push_str("特性");
-            wiki.push_str(&tag_no);
-            wiki.push('=');
-            let mut description = formatter.format(
-                tag.brief_description,
-                if let Some(skill) = &tag.skill {
-                    skill.params.as_slice()
-                } else {
-                    tag.parameter_list.as_slice()
-                },
-            );
-            let mut effect_explain = String::new();
-            for effect in &tag.effect {
-                let effect_wiki = String::from("{{效果说明|") + effect.name + "}}";
-                description = description.replace(&effect_wiki, &format!("<u>{}</u>", effect.name));
-                effect_explain.push_str("<br />'''· ");
-                effect_explain.push_str(effect.name);
-                effect_explain.push_str("'''<br />");
-                effect_explain.push_str(&formatter.format(effect.desc, &[]));
-            }
-            wiki.push_str(&description);
-            if !effect_explain.is_empty() {
-                wiki.push_str("<br />");
-                wiki.push_str(&effect_explain);
+    fn boss_wiki_context(&self, site: Site) -> ChallengeBossContext<'_> {
+        let mut formatter = site.formatter(self.game);
+        let mazes = self.mazes();
+        self.boss_wiki_assertions(mazes);
+        let extra = self.extra();
+        let (begin_time, end_time) = self.sched_times();
+        // 各层首领相同，只取最后一层
+        let maze = &mazes[3];
+        let halves = [
+            (&maze.event_list_1[0], &extra.buff_list_1),
+            (&maze.event_list_2[0], &extra.buff_list_2),
+        ]
+        .into_iter()
+        .map(|(event, buff_list)| {
+            assert_eq!(buff_list.len(), 3, "末日幻影固定 3 个增益");
+            let monster = &event.monster_list[0][0];
+            let guide = self.game.monster_guide_config(monster.id).unwrap();
+            let features = guide
+                .tag_list
+                .iter()
+                .map(|tag| {
+                    let params = match &tag.skill {
+                        Some(skill) => skill.params.as_slice(),
+                        None => tag.parameter_list.as_slice(),
+                    };
+                    ChallengeBossFeatureContext {
+                        name: tag.name,
+                        desc: formatter.format(tag.brief_description, params),
+                        effects: tag
+                            .effect
+                            .iter()
+                            .map(|effect| ChallengeBuffContext {
+                                name: effect.name,
+                                desc: formatter.format(effect.desc, &[]),
+                            })
+                            .collect(),
+                    }
+                })
+                .collect();
+            // BWIKI 上的页面名带有第二阶段的名称
+            let boss = match site {
+                Site::Bwiki if monster.name == "无望冽风的幻灭者" => {
+                    String::from("无望冽风的幻灭者•虚妄之母")
+                }
+                _ => monster.page_name(site).into_owned(),
+            };
+            ChallengeBossHalfContext {
+                name: event.name,
+                boss,
+                features,
+                guide: guide.context(site, &mut formatter),
+                buffs: buff_list
+                    .iter()
+                    .map(|buff| ChallengeBuffContext::new(&mut formatter, buff))
+                    .collect(),
             }
+        })
+        .collect();
+        ChallengeBossContext {
+            issue: self.issue(),
+            name: self.name,
+            begin_time,
+            end_time,
+            buff: ChallengeBuffContext::new(&mut formatter, &mazes[0].maze_buff),
+            halves,
         }
-        let buff_list = if team == 1 {
-            extra.buff_list_1.as_slice()
-        } else {
-            extra.buff_list_2.as_slice()
-        };
-        assert_eq!(buff_list.len(), 3, "末日幻影固定 3 个增益");
-        for (buff_no, buff) in buff_list.iter().enumerate() {
-            let no = (team as usize * 3 + buff_no - 2).to_string();
-            wiki.push_str("\n|终焉公理");
-            wiki.push_str(&no);
-            wiki.push_str("名称=");
-            wiki.push_str(buff.name);
-            wiki.push_str("\n|终焉公理");
-            wiki.push_str(&no);
-            wiki.push('=');
-            wiki.push_str(&formatter.format(buff.desc, &buff.params));
+    }
+}
+
+/// 模板 challenge_group.jinja 的上下文，type 字段区分混沌回忆、虚构叙事、末日幻影
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(tag = "type")]
+pub enum ChallengeGroupContext<'a> {
+    /// 混沌回忆，默认模板转交 challenge_memory.jinja 渲染
+    Memory(ChallengeMemoryContext<'a>),
+    /// 1.6 版本以前的混沌回忆，机制不一样，不生成页面
+    OutdatedMemory,
+    /// 虚构叙事，默认模板转交 challenge_story.jinja 渲染
+    Story(ChallengeStoryContext<'a>),
+    /// 末日幻影，默认模板转交 challenge_boss.jinja 渲染
+    Boss(ChallengeBossContext<'a>),
+}
+
+/// 有名称的增益、效果
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChallengeBuffContext<'a> {
+    pub name: &'a str,
+    /// 已按 wiki 语法格式化的效果
+    pub desc: String,
+}
+
+impl<'a> ChallengeBuffContext<'a> {
+    fn new<Data: format::GameData>(
+        formatter: &mut format::Formatter<Data>,
+        buff: &crate::misc::MazeBuff<'a>,
+    ) -> Self {
+        Self {
+            name: buff.name,
+            desc: formatter.format(buff.desc, &buff.params),
         }
     }
+}
 
-    fn boss_wiki(&self) -> Cow<'static, str> {
-        let mut formatter = format::Formatter::new(self.game).media_wiki_syntax(true);
-        let mazes = self.mazes();
-        self.boss_wiki_assertions(mazes);
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChallengeMemoryContext<'a> {
+    /// 第几期
+    pub issue: u16,
+    pub name: &'a str,
+    /// 开始时间，格式为 2024/01/01 04:00，常驻混沌回忆为 none
+    pub begin_time: Option<String>,
+    /// 结束时间，已减去 1 纳秒，即 2024/01/01 03:59
+    pub end_time: Option<String>,
+    /// 已按 wiki 语法格式化的记忆紊流效果
+    pub buff: Option<String>,
+    pub floors: Vec<ChallengeMemoryFloorContext>,
+    /// BWIKI 为特殊敌方的折叠面板内容，其它站点为特殊敌方的页面名，每个敌方一项
+    pub special_monsters: Vec<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChallengeMemoryFloorContext {
+    /// 第几层，从 1 开始
+    pub floor: u8,
+    /// 上半、下半两队
+    pub teams: Vec<ChallengeMemoryTeamContext>,
+}
 
-        let mut wiki = String::from("{{末日幻影单期");
-        wiki.push_str("\n|期数=");
-        wiki.push_str(&format!("{:03}", self.issue()));
-        wiki.push_str("\n|名称=");
-        wiki.push_str(self.name);
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChallengeMemoryTeamContext {
+    /// 每一波敌方的页面名
+    pub waves: Vec<Vec<String>>,
+    /// 建议属性
+    pub weaknesses: Vec<Cow<'static, str>>,
+}
 
-        self.wiki_write_sched(&mut wiki);
-        self.wiki_write_buff(
-            &mut wiki,
-            &mut formatter,
-            "末法余烬",
-            Some(&mazes[0].maze_buff),
-        );
-        let maze = &mazes[3];
-        let extra = self.extra();
-        self.boss_wiki_write_tags(&mut wiki, 1, &maze.event_list_1[0], extra);
-        self.boss_wiki_write_tags(&mut wiki, 2, &maze.event_list_2[0], extra);
-        wiki.push_str("\n}}\n<br />\n<br />\n----");
-        Cow::Owned(wiki)
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChallengeStoryContext<'a> {
+    /// 第几期
+    pub issue: u16,
+    pub name: &'a str,
+    /// 格式同 ChallengeMemoryContext
+    pub begin_time: Option<String>,
+    pub end_time: Option<String>,
+    /// 第 11 期开始的新版虚构叙事，新增战意机制
+    pub fever: bool,
+    /// 旧版为怪诞逸闻，新版为战意机制
+    pub buff: Option<ChallengeBuffContext<'a>>,
+    /// 新版的战熄潮平、战意汹涌效果，旧版为空
+    pub grit: Vec<String>,
+    /// 荒腔走板，三选一的增益
+    pub options: Vec<ChallengeStoryOptionContext<'a>>,
+    pub floors: Vec<ChallengeStoryFloorContext>,
+    /// 格式同 ChallengeMemoryContext
+    pub special_monsters: Vec<String>,
+    /// 各波次敌方的属性增幅（降幅），用于提示敌方阵容是否换代
+    pub elite_groups: Vec<ChallengeEliteGroupContext>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChallengeStoryOptionContext<'a> {
+    pub name: &'a str,
+    pub desc: String,
+    /// 新版虚构叙事中对战意机制的影响
+    pub grit: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChallengeStoryFloorContext {
+    /// 第几层，从 1 开始
+    pub floor: u8,
+    /// 上半、下半两队
+    pub teams: Vec<ChallengeStoryTeamContext>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChallengeStoryTeamContext {
+    pub waves: Vec<ChallengeStoryWaveContext>,
+    /// 建议属性
+    pub weaknesses: Vec<Cow<'static, str>>,
+    /// 只击败带有某种弱点的敌方所能获得的总分，从高到低，建议属性排在最前，新版虚构叙事为空
+    pub weakness_scores: Vec<ChallengeWeaknessScoreContext>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChallengeWeaknessScoreContext {
+    pub element: Cow<'static, str>,
+    pub score: u32,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChallengeStoryWaveContext {
+    /// 本波次所有敌方的页面名，同名敌方重复出现
+    pub monsters: Vec<String>,
+    /// 普通敌方及其数量
+    pub normal: Vec<ChallengeMonsterCountContext>,
+    /// 精英、首领等特殊敌方及其数量
+    pub elite: Vec<ChallengeMonsterCountContext>,
+    /// 场上同时存在的敌方上限
+    pub max_teammate: u8,
+    /// 攻击增幅百分数，旧版虚构叙事没有增幅时为 none
+    pub attack_ratio: Option<u16>,
+    /// 生命增幅百分数，旧版虚构叙事没有增幅时为 none
+    pub hp_ratio: Option<u16>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChallengeMonsterCountContext {
+    /// 页面名
+    pub name: String,
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChallengeEliteGroupContext {
+    pub id: u16,
+    /// 各项属性倍率的百分数，保留两位小数
+    pub attack_ratio: f64,
+    pub defence_ratio: f64,
+    pub hp_ratio: f64,
+    pub speed_ratio: f64,
+    pub stance_ratio: f64,
+    /// 使用该组别的波次，依次为层数、队伍（1 为上半）、波次，均从 1 开始
+    pub waves: Vec<[u8; 3]>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChallengeBossContext<'a> {
+    /// 第几期
+    pub issue: u16,
+    pub name: &'a str,
+    /// 格式同 ChallengeMemoryContext
+    pub begin_time: Option<String>,
+    pub end_time: Option<String>,
+    /// 末法余烬
+    pub buff: ChallengeBuffContext<'a>,
+    /// 上半、下半
+    pub halves: Vec<ChallengeBossHalfContext<'a>>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChallengeBossHalfContext<'a> {
+    pub name: &'a str,
+    /// 首领的页面名
+    pub boss: String,
+    /// 首领特性，前两项为固有特性，之后依次为难度 3、4 增加的特性
+    pub features: Vec<ChallengeBossFeatureContext<'a>>,
+    /// 终焉公理，三选一的增益
+    pub buffs: Vec<ChallengeBuffContext<'a>>,
+    /// 首领图鉴，模板见 monster_guide.jinja
+    pub guide: crate::monster::guide::MonsterGuideContext<'a>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChallengeBossFeatureContext<'a> {
+    pub name: &'a str,
+    pub desc: String,
+    /// 描述中提到的名词及其说明
+    pub effects: Vec<ChallengeBuffContext<'a>>,
+}
+
+impl<Data: ExcelOutput + format::GameData> WikiContext for ChallengeGroupConfig<'_, Data> {
+    const TEMPLATE: &'static str = "challenge_group";
+    type Context<'c>
+        = ChallengeGroupContext<'c>
+    where
+        Self: 'c;
+
+    fn wiki_context(&self, site: Site) -> ChallengeGroupContext<'_> {
+        match self.r#type {
+            // 更早以往的混沌回忆机制不太一样
+            // 比如同一层存在两个敌方 Boss 左右分立（具体见游戏内常驻回忆「永屹之城的遗秘」回忆其六）
+            // 左右分立对应到数据上是 event_list_1.len() == 2
+            ChallengeGroupType::Memory if self.id < 1007 => ChallengeGroupContext::OutdatedMemory,
+            ChallengeGroupType::Memory => {
+                ChallengeGroupContext::Memory(self.memory_wiki_context(site))
+            }
+            ChallengeGroupType::Story => {
+                ChallengeGroupContext::Story(self.story_wiki_context(site))
+            }
+            ChallengeGroupType::Boss => ChallengeGroupContext::Boss(self.boss_wiki_context(site)),
+        }
     }
 }
 
@@ -959,11 +950,12 @@ impl<Data: ExcelOutput + format::GameData> Wiki for ChallengeGroupConfig<'_, Dat
     ///       * maze.event_list_1[0].elite_group.hp_ratio
     ///   ```
     fn wiki(&self) -> Cow<'static, str> {
-        match self.r#type {
-            ChallengeGroupType::Memory => self.memory_wiki(),
-            ChallengeGroupType::Story => self.story_wiki(),
-            ChallengeGroupType::Boss => self.boss_wiki(),
-        }
+        render_installed(self)
+    }
+
+    /// 页面开头的单期模板，特殊敌方、增援序列等其余部分不包括在内
+    fn wiki_template(&self) -> Option<Template> {
+        crate::wiki::main_template(&self.wiki())
     }
 }
 
@@ -1003,8 +995,8 @@ pub struct ChallengeMazeConfig<'a, Data: ExcelOutput + ?Sized> {
     pub id: u16,
     pub name: &'a str,
     pub group: ChallengeGroupConfig<'a, Data>,
-    pub map_entrance: crate::map::MapEntrance<'a>,
-    pub map_entrance_2: Option<crate::map::MapEntrance<'a>>,
+    pub map_entrance: crate::map::MapEntrance<'a, Data>,
+    pub map_entrance_2: Option<crate::map::MapEntrance<'a, Data>>,
     pub pre_level: u8,
     pub pre_challenge_maze_id: u16,
     /// 本 Maze 所在层数
@@ -0,0 +1,454 @@
+//! 把一整类数据批量导出成 wiki 页面文件
+//!
+//! 每条记录输出一个 `<页面名>.wiki` 文件，另外在目录下生成：
+//! - `index.txt`：每行为 `文件名\t页面名`
+//! - `skipped.txt`：每行为 `序号\t页面名\t原因`，记录跳过或者生成时 panic 的记录
+//!
+//! 实现了 WikiContext 的类型用 [`export`] 按给定的模板渲染，模板出错时只跳过出错的记录；
+//! 只实现了 Wiki 的类型用 [`export_wiki`]
+//!
+//! 数据里常有新增的 ID 没有适配（比如新的图标、新的枚举值），生成时会直接 panic，
+//! 这里每条记录单独 catch_unwind，一条出错不影响其他页面的导出
+//! 注意 panic 信息仍然会由默认的 panic hook 打印到 stderr
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Write;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use base::{Name, Wiki};
+
+use crate::wiki::{WikiContext, WikiTemplates};
+use crate::ExcelOutput;
+
+/// 导出结果
+#[derive(Clone, Debug, Default)]
+pub struct ExportReport {
+    /// 已写入的页面，按导出顺序
+    pub pages: Vec<ExportedPage>,
+    /// 跳过的记录
+    pub skipped: Vec<SkippedRecord>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ExportedPage {
+    /// wiki_name 给出的页面名
+    pub title: String,
+    /// 相对于导出目录的文件名
+    pub file: PathBuf,
+}
+
+#[derive(Clone, Debug)]
+pub struct SkippedRecord {
+    /// 记录在输入中的序号，从 0 开始
+    pub index: usize,
+    /// 页面名，没能取得页面名时为空
+    pub title: String,
+    pub reason: SkipReason,
+}
+
+#[derive(Clone, Debug)]
+pub enum SkipReason {
+    /// 页面名为空，或者去掉文件名中的非法字符后为空
+    EmptyName,
+    /// 文件名和之前的页面重复，保留先导出的那个
+    DuplicateName(PathBuf),
+    /// 生成的页面内容为空
+    EmptyPage,
+    /// 渲染模板出错，内容为错误信息
+    Template(String),
+    /// 读取记录、生成页面名或者页面内容时 panic，内容为 panic 信息
+    Panic(String),
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyName => f.write_str("页面名为空"),
+            Self::DuplicateName(file) => write!(f, "和 {} 重名", file.display()),
+            Self::EmptyPage => f.write_str("页面内容为空"),
+            Self::Template(message) => write!(f, "渲染失败：{message}"),
+            Self::Panic(message) => write!(f, "panic：{message}"),
+        }
+    }
+}
+
+impl ExportReport {
+    /// 汇总信息，比如「导出 120 个页面，跳过 3 条记录（2 条 panic）」
+    pub fn summary(&self) -> String {
+        let panicked = (self.skipped.iter())
+            .filter(|record| matches!(record.reason, SkipReason::Panic(_)))
+            .count();
+        format!(
+            "导出 {} 个页面，跳过 {} 条记录（{} 条 panic）",
+            self.pages.len(),
+            self.skipped.len(),
+            panicked
+        )
+    }
+}
+
+/// 把页面名转成文件名，替换掉 Windows 和 Unix 下不能出现在文件名中的字符
+pub fn sanitize_file_name(title: &str) -> String {
+    let name = title
+        .chars()
+        .map(|char| match char {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            char if char.is_control() => '_',
+            char => char,
+        })
+        .collect::<String>();
+    // Windows 下文件名不能以空格或者 . 结尾，开头的 . 在 Unix 下是隐藏文件
+    name.trim_matches(|char: char| char == '.' || char.is_whitespace())
+        .to_string()
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        return message.to_string();
+    }
+    if let Some(message) = payload.downcast_ref::<String>() {
+        return message.clone();
+    }
+    String::from("未知错误")
+}
+
+/// 用 `templates` 渲染每条记录，写入 `dir` 下的 `<页面名>.wiki`，目录不存在时会创建
+///
+/// 只有 IO 错误会中断导出，单条记录的问题都记录在返回的 [`ExportReport`] 中并同时写入 `skipped.txt`
+pub fn export<T: Name + WikiContext>(
+    templates: &WikiTemplates,
+    dir: impl AsRef<Path>,
+    records: impl IntoIterator<Item = T>,
+) -> std::io::Result<ExportReport> {
+    export_with(dir, records, |record| {
+        (templates.render(record))
+            .map(Cow::Owned)
+            .map_err(|err| SkipReason::Template(err.to_string()))
+    })
+}
+
+/// 同 [`export`]，但是对每条记录调用 `Wiki::wiki`
+pub fn export_wiki<T: Name + Wiki>(
+    dir: impl AsRef<Path>,
+    records: impl IntoIterator<Item = T>,
+) -> std::io::Result<ExportReport> {
+    export_with(dir, records, |record| Ok(record.wiki()))
+}
+
+/// 读取记录时连续 panic 的上限
+///
+/// 不是所有迭代器在 panic 后都会推进（比如 from_fn 里的闭包每次都从同一个位置开始），
+/// 连续 panic 达到这个次数时视为迭代器卡住，停止读取后续记录
+const MAX_CONSECUTIVE_PANICS: usize = 16;
+
+fn export_with<T: Name>(
+    dir: impl AsRef<Path>,
+    records: impl IntoIterator<Item = T>,
+    render: impl Fn(&T) -> Result<Cow<'static, str>, SkipReason>,
+) -> std::io::Result<ExportReport> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+    let mut report = ExportReport::default();
+    // 键为小写的文件名，Windows 和 macOS 下文件名不区分大小写
+    let mut files = HashMap::<String, usize>::new();
+    let mut records = records.into_iter();
+    let mut panics = 0;
+    for index in 0.. {
+        // 记录本身是在迭代时由 FromModel 构造的，构造时的 unwrap 也可能 panic
+        // 标准库的 Map 在调用闭包前已经推进了内部迭代器，panic 后可以继续迭代下一条
+        let record = match catch_unwind(AssertUnwindSafe(|| records.next())) {
+            Ok(Some(record)) => record,
+            Ok(None) => break,
+            Err(payload) => {
+                panics += 1;
+                let mut message = panic_message(payload);
+                if panics >= MAX_CONSECUTIVE_PANICS {
+                    message += &format!("（连续 {panics} 次，停止读取后续记录）");
+                }
+                report.skipped.push(SkippedRecord {
+                    index,
+                    title: String::new(),
+                    reason: SkipReason::Panic(message),
+                });
+                if panics >= MAX_CONSECUTIVE_PANICS {
+                    break;
+                }
+                continue;
+            }
+        };
+        panics = 0;
+        let title = match catch_unwind(AssertUnwindSafe(|| record.wiki_name().into_owned())) {
+            Ok(title) => title,
+            Err(payload) => {
+                report.skipped.push(SkippedRecord {
+                    index,
+                    title: record.name().to_string(),
+                    reason: SkipReason::Panic(panic_message(payload)),
+                });
+                continue;
+            }
+        };
+        let file_name = sanitize_file_name(&title);
+        let key = file_name.to_lowercase();
+        let wiki = if file_name.is_empty() {
+            Err(SkipReason::EmptyName)
+        } else if let Some(&exported) = files.get(&key) {
+            Err(SkipReason::DuplicateName(
+                report.pages[exported].file.clone(),
+            ))
+        } else {
+            match catch_unwind(AssertUnwindSafe(|| render(&record))) {
+                Ok(Ok(wiki)) if wiki.trim().is_empty() => Err(SkipReason::EmptyPage),
+                Ok(wiki) => wiki,
+                Err(payload) => Err(SkipReason::Panic(panic_message(payload))),
+            }
+        };
+        let wiki = match wiki {
+            Ok(wiki) => wiki,
+            Err(reason) => {
+                report.skipped.push(SkippedRecord {
+                    index,
+                    title,
+                    reason,
+                });
+                continue;
+            }
+        };
+        let file = PathBuf::from(file_name.clone() + ".wiki");
+        std::fs::write(dir.join(&file), wiki.as_bytes())?;
+        files.insert(key, report.pages.len());
+        report.pages.push(ExportedPage { title, file });
+    }
+    let mut index = std::io::BufWriter::new(std::fs::File::create(dir.join("index.txt"))?);
+    for page in &report.pages {
+        writeln!(index, "{}\t{}", page.file.display(), page.title)?;
+    }
+    index.flush()?;
+    let mut skipped = std::io::BufWriter::new(std::fs::File::create(dir.join("skipped.txt"))?);
+    for record in &report.skipped {
+        let reason = record.reason.to_string().replace('\n', " ");
+        writeln!(skipped, "{}\t{}\t{}", record.index, record.title, reason)?;
+    }
+    skipped.flush()?;
+    Ok(report)
+}
+
+/// 导出所有怪物的变种，原型怪物（模板指向自身的）不导出
+pub fn export_monsters<Data: ExcelOutput + format::GameData>(
+    game: &Data,
+    templates: &WikiTemplates,
+    dir: impl AsRef<Path>,
+) -> std::io::Result<ExportReport> {
+    let monsters = game.list_monster_config().filter(|monster| {
+        monster
+            .template
+            .as_ref()
+            .is_some_and(|tpl| tpl.id != monster.id)
+    });
+    export(templates, dir, monsters)
+}
+
+/// 导出所有书籍
+pub fn export_book_series<Data: ExcelOutput + format::GameData>(
+    game: &Data,
+    templates: &WikiTemplates,
+    dir: impl AsRef<Path>,
+) -> std::io::Result<ExportReport> {
+    export(templates, dir, game.list_book_series_config())
+}
+
+/// 导出所有短信联系人
+pub fn export_message_contacts<Data: ExcelOutput + format::GameData>(
+    game: &Data,
+    dir: impl AsRef<Path>,
+) -> std::io::Result<ExportReport> {
+    export_wiki(dir, game.list_message_contacts_config())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use base::{Name, Wiki};
+
+    use super::{export, export_wiki, sanitize_file_name, SkipReason};
+    use crate::wiki::{Site, WikiContext, WikiTemplates};
+
+    /// 页面名和内容固定的记录，内容为 "panic" 时生成页面会 panic
+    struct Page(&'static str, &'static str);
+
+    impl Name for Page {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn wiki_name(&self) -> Cow<'_, str> {
+            if self.0 == "panic" {
+                panic!("页面名出错");
+            }
+            Cow::Borrowed(self.0)
+        }
+    }
+
+    impl Wiki for Page {
+        fn wiki(&self) -> Cow<'static, str> {
+            if self.1 == "panic" {
+                panic!("内容出错：{}", self.0);
+            }
+            Cow::Borrowed(self.1)
+        }
+    }
+
+    #[derive(serde::Serialize)]
+    struct PageContext {
+        text: &'static str,
+    }
+
+    impl WikiContext for Page {
+        const TEMPLATE: &'static str = "page";
+        type Context<'c> = PageContext;
+
+        fn wiki_context(&self, _: Site) -> PageContext {
+            PageContext { text: self.1 }
+        }
+    }
+
+    fn read(path: impl AsRef<std::path::Path>) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn sanitize() {
+        assert_eq!(sanitize_file_name("开拓者"), "开拓者");
+        assert_eq!(
+            sanitize_file_name("a/b\\c:d*e?f\"g<h>i|j"),
+            "a_b_c_d_e_f_g_h_i_j"
+        );
+        assert_eq!(sanitize_file_name("换\n行\t"), "换_行_");
+        assert_eq!(sanitize_file_name(" .hidden. "), "hidden");
+        assert_eq!(sanitize_file_name("..."), "");
+    }
+
+    #[test]
+    fn duplicate_and_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let records = [
+            Page("甲", "一"),
+            Page("a:b", "二"),
+            Page("a?b", "三"),
+            Page("...", "四"),
+            Page("乙", " \n"),
+            Page("A_B", "五"),
+        ];
+        let report = export_wiki(dir.path(), records).unwrap();
+        assert_eq!(report.pages.len(), 2);
+        assert_eq!(report.pages[1].title, "a:b");
+        assert_eq!(report.pages[1].file.to_str(), Some("a_b.wiki"));
+        assert_eq!(read(dir.path().join("a_b.wiki")), "二");
+        let reasons = report
+            .skipped
+            .iter()
+            .map(|record| (record.index, record.reason.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reasons,
+            [
+                (2, String::from("和 a_b.wiki 重名")),
+                (3, String::from("页面名为空")),
+                (4, String::from("页面内容为空")),
+                (5, String::from("和 a_b.wiki 重名")),
+            ]
+        );
+        assert_eq!(
+            read(dir.path().join("index.txt")),
+            "甲.wiki\t甲\na_b.wiki\ta:b\n"
+        );
+        assert_eq!(
+            read(dir.path().join("skipped.txt")),
+            "2\ta?b\t和 a_b.wiki 重名\n3\t...\t页面名为空\n4\t乙\t页面内容为空\n5\tA_B\t和 \
+             a_b.wiki 重名\n"
+        );
+        assert_eq!(
+            report.summary(),
+            "导出 2 个页面，跳过 4 条记录（0 条 panic）"
+        );
+    }
+
+    #[test]
+    fn panic_isolated() {
+        let dir = tempfile::tempdir().unwrap();
+        // 构造记录、生成页面名、生成页面内容时的 panic 都只跳过当前记录
+        let records = (0..4).map(|index| match index {
+            0 => panic!("构造出错"),
+            1 => Page("panic", "一"),
+            2 => Page("甲", "panic"),
+            _ => Page("乙", "二"),
+        });
+        let report = export_wiki(dir.path(), records).unwrap();
+        assert_eq!(report.pages.len(), 1);
+        assert_eq!(report.pages[0].title, "乙");
+        let skipped = report
+            .skipped
+            .iter()
+            .map(|record| (record.index, record.title.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(skipped, [(0, ""), (1, "panic"), (2, "甲")]);
+        assert!(std::matches!(
+            &report.skipped[2].reason,
+            SkipReason::Panic(message) if message == "内容出错：甲"
+        ));
+        assert_eq!(
+            report.summary(),
+            "导出 1 个页面，跳过 3 条记录（3 条 panic）"
+        );
+    }
+
+    #[test]
+    fn stuck_iterator_stops() {
+        let dir = tempfile::tempdir().unwrap();
+        // panic 后不会推进的迭代器，连续 panic 达到上限后停止
+        let records = std::iter::from_fn(|| -> Option<Page> { panic!("构造出错") });
+        let report = export_wiki(dir.path(), records).unwrap();
+        assert!(report.pages.is_empty());
+        assert_eq!(report.skipped.len(), super::MAX_CONSECUTIVE_PANICS);
+        assert!(std::matches!(
+            &report.skipped.last().unwrap().reason,
+            SkipReason::Panic(message) if message.ends_with("停止读取后续记录）")
+        ));
+    }
+
+    #[test]
+    fn template_error_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut templates = WikiTemplates::empty_for(Site::Fandom);
+        templates
+            .add_template(
+                "page",
+                "{{<< text >>}}<% if text == \"二\" %><< missing >><% endif %>",
+            )
+            .unwrap();
+        let records = [Page("甲", "一"), Page("乙", "二"), Page("丙", "三")];
+        let report = export(&templates, dir.path(), records).unwrap();
+        assert_eq!(report.pages.len(), 2);
+        assert_eq!(read(dir.path().join("丙.wiki")), "{{三}}");
+        // 渲染出错只跳过出错的记录
+        assert_eq!(report.skipped[0].index, 1);
+        assert!(std::matches!(
+            report.skipped[0].reason,
+            SkipReason::Template(_)
+        ));
+        assert!(!dir.path().join("乙.wiki").exists());
+
+        // 没有对应的模板时每条记录都跳过
+        let dir = tempfile::tempdir().unwrap();
+        let templates = WikiTemplates::empty_for(Site::Fandom);
+        let report = export(&templates, dir.path(), [Page("甲", "一")]).unwrap();
+        assert!(report.pages.is_empty());
+        assert!(std::matches!(
+            report.skipped[0].reason,
+            SkipReason::Template(_)
+        ));
+    }
+}
@@ -3,6 +3,7 @@
 pub mod battle;
 pub mod book;
 pub mod challenge;
+pub mod export;
 pub mod item;
 pub mod map;
 pub mod message;
@@ -12,6 +13,7 @@ pub mod monster;
 pub mod rogue;
 pub mod story;
 pub mod talk;
+pub mod wiki;
 
 pub mod prelude {
     pub use crate::battle::*;
@@ -26,6 +28,7 @@ pub mod prelude {
     pub use crate::rogue::tourn::*;
     pub use crate::rogue::*;
     pub use crate::talk::*;
+    pub use crate::wiki::{Site, WikiContext, WikiTemplates};
 }
 
 pub trait FromModel<'a, Data: ExcelOutput>
@@ -155,7 +158,7 @@ pub trait ExcelOutput: data::Text {
     declare!(item_config_equipment, u32 => item::ItemConfig);
     declare!(item_use_data, u32 => item::ItemUseData);
     // map
-    declare!(map_entrance, u32 => map::MapEntrance);
+    declare!(map_entrance, u32 => map::MapEntrance<Self>);
     main_sub_declare!(mapping_info, u32 => map::MappingInfo<Self>);
     declare!(maze_floor, u32 => map::MazeFloor);
     declare!(maze_plane, u32 => map::MazePlane);
@@ -182,8 +185,8 @@ pub trait ExcelOutput: data::Text {
     declare!(text_join_config, u8 => misc::TextJoinConfig);
     declare!(text_join_item, u16 => misc::TextJoinItem);
     // mission
-    declare!(main_mission, u32 => mission::MainMission);
-    declare!(mission_chapter_config, u32 => mission::MissionChapterConfig);
+    declare!(main_mission, u32 => mission::MainMission<Self>);
+    declare!(mission_chapter_config, u32 => mission::MissionChapterConfig<'_>);
     declare!(sub_mission, u32 => mission::SubMission);
     // monster
     declare!(elite_group, u16 => monster::EliteGroup);
@@ -198,7 +201,7 @@ pub trait ExcelOutput: data::Text {
     declare!(npc_monster_data, u32 => monster::NPCMonsterData);
     // monster guide
     declare!(monster_difficulty_guide, u16 => monster::guide::MonsterDifficultyGuide);
-    declare!(monster_guide_config, u32 => monster::guide::MonsterGuideConfig);
+    declare!(monster_guide_config, u32 => monster::guide::MonsterGuideConfig<Self>);
     declare!(monster_guide_phase, u16 => monster::guide::MonsterGuidePhase);
     declare!(monster_guide_skill, u32 => monster::guide::MonsterGuideSkill);
     declare!(monster_guide_skill_text, u32 => monster::guide::MonsterGuideSkillText);
@@ -211,13 +214,13 @@ pub trait ExcelOutput: data::Text {
     declare!(rogue_handbook_miracle, u16 => rogue::RogueHandbookMiracle);
     declare!(rogue_handbook_miracle_type, u16 => rogue::RogueHandbookMiracleType);
     main_sub_declare!(rogue_maze_buff, u32 => misc::MazeBuff);
-    declare!(rogue_miracle, u16 => rogue::RogueMiracle);
+    declare!(rogue_miracle, u16 => rogue::RogueMiracle<Self>);
     declare!(rogue_miracle_effect_display, u16 => rogue::RogueMiracleEffectDisplay);
     declare!(rogue_miracle_display, u16 => rogue::RogueMiracleDisplay);
     declare!(rogue_monster, u32 => rogue::RogueMonster);
     declare!(rogue_monster_group, u32 => rogue::RogueMonsterGroup);
     // rogue magic
-    declare!(rogue_magic_miracle, u16 => rogue::RogueMiracle);
+    declare!(rogue_magic_miracle, u16 => rogue::RogueMiracle<Self>);
     // rogue tourn
     declare!(rogue_bonus, u16 => rogue::tourn::RogueBonus);
     main_sub_declare!(rogue_tourn_buff, u32 => rogue::tourn::RogueTournBuff<Self>);
@@ -226,9 +229,9 @@ pub trait ExcelOutput: data::Text {
     declare!(rogue_tourn_formula, u32 => rogue::tourn::RogueTournFormula<Self>);
     declare!(rogue_tourn_formula_display, u32 => rogue::tourn::RogueTournFormulaDisplay);
     declare!(rogue_tourn_handbook_miracle, u16 => rogue::tourn::RogueTournHandbookMiracle);
-    declare!(rogue_tourn_miracle, u16 => rogue::tourn::RogueTournMiracle);
+    declare!(rogue_tourn_miracle, u16 => rogue::tourn::RogueTournMiracle<Self>);
     declare!(rogue_tourn_miracle_display, u16 => rogue::RogueMiracleDisplay);
-    declare!(rogue_tourn_titan_bless, u16 => rogue::tourn::RogueTournTitanBless);
+    declare!(rogue_tourn_titan_bless, u16 => rogue::tourn::RogueTournTitanBless<Self>);
     declare!(rogue_tourn_weekly_challenge, u8 => rogue::tourn::RogueTournWeeklyChallenge<Self>);
     declare!(rogue_tourn_weekly_display, u16 => rogue::tourn::RogueTournWeeklyDisplay<Self>);
     // talk
@@ -241,7 +244,15 @@ pub trait ExcelOutput: data::Text {
     #[rustfmt::skip]
     fn message_contacts_of_section(&self, section_id: u32) -> Option<message::MessageContactsConfig<Self>>;
     #[rustfmt::skip]
+    fn sub_mission_in_main_mission(&self, main_id: u32) -> impl Iterator<Item = mission::SubMission<'_>>;
+    /// 结束后接取该主任务的短信
+    #[rustfmt::skip]
+    fn message_section_of_main_mission(&self, main_id: u32) -> impl Iterator<Item = message::MessageSectionConfig<'_, Self>>;
+    #[rustfmt::skip]
     fn monster_template_config_group(&self, id: u32) -> impl Iterator<Item = monster::MonsterTemplateConfig<Self>>;
+    /// 同一怪物模板的各个难度
+    #[rustfmt::skip]
+    fn monster_config_in_template(&self, id: u32) -> impl Iterator<Item = monster::MonsterConfig<'_, Self>>;
     fn challenge_maze_in_group(&self, id: u16) -> Vec<challenge::ChallengeMazeConfig<Self>>;
     fn current_challenge_boss_group_config(&self) -> Option<challenge::ChallengeGroupConfig<Self>>;
     fn current_challenge_group_config(&self) -> Option<challenge::ChallengeGroupConfig<Self>>;
@@ -251,8 +262,13 @@ pub trait ExcelOutput: data::Text {
     fn localbook_in_book_series(&self, id: u16) -> impl Iterator<Item = book::LocalbookConfig<Self>>;
 
     // 按名称索引
+    fn extra_effect_config_by_name(&self, name: &str) -> Option<misc::ExtraEffectConfig<'_>>;
     fn rogue_buff_by_name(&self, name: &str) -> Option<rogue::RogueBuff<Self>>;
     fn rogue_tourn_buff_by_name(&self, name: &str) -> Option<rogue::tourn::RogueTournBuff<Self>>;
+    fn rogue_miracle_by_name(&self, name: &str) -> Option<rogue::RogueMiracle<'_, Self>>;
+    fn rogue_magic_miracle_by_name(&self, name: &str) -> Option<rogue::RogueMiracle<'_, Self>>;
+    #[rustfmt::skip]
+    fn rogue_tourn_miracle_by_name(&self, name: &str) -> Option<rogue::tourn::RogueTournMiracle<'_, Self>>;
     fn story(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<story::Story>;
 }
 
@@ -293,7 +309,7 @@ impl ExcelOutput for data::GameData {
     implement!(item_config_equipment, u32 => item::ItemConfig);
     implement!(item_use_data, u32 => item::ItemUseData);
     // map
-    implement!(map_entrance, u32 => map::MapEntrance);
+    implement!(map_entrance, u32 => map::MapEntrance<Self>);
     main_sub_implement!(mapping_info, u32 => map::MappingInfo<Self>);
     implement!(maze_floor, u32 => map::MazeFloor);
     implement!(maze_plane, u32 => map::MazePlane);
@@ -320,8 +336,8 @@ impl ExcelOutput for data::GameData {
     implement!(text_join_config, u8 => misc::TextJoinConfig);
     implement!(text_join_item, u16 => misc::TextJoinItem);
     // mission
-    implement!(main_mission, u32 => mission::MainMission);
-    implement!(mission_chapter_config, u32 => mission::MissionChapterConfig);
+    implement!(main_mission, u32 => mission::MainMission<Self>);
+    implement!(mission_chapter_config, u32 => mission::MissionChapterConfig<'_>);
     implement!(sub_mission, u32 => mission::SubMission);
     // monster
     implement!(elite_group, u16 => monster::EliteGroup);
@@ -336,7 +352,7 @@ impl ExcelOutput for data::GameData {
     implement!(npc_monster_data, u32 => monster::NPCMonsterData);
     // monster guide
     implement!(monster_difficulty_guide, u16 => monster::guide::MonsterDifficultyGuide);
-    implement!(monster_guide_config, u32 => monster::guide::MonsterGuideConfig);
+    implement!(monster_guide_config, u32 => monster::guide::MonsterGuideConfig<Self>);
     implement!(monster_guide_phase, u16 => monster::guide::MonsterGuidePhase);
     implement!(monster_guide_skill, u32 => monster::guide::MonsterGuideSkill);
     implement!(monster_guide_skill_text, u32 => monster::guide::MonsterGuideSkillText);
@@ -349,13 +365,13 @@ impl ExcelOutput for data::GameData {
     implement!(rogue_handbook_miracle, u16 => rogue::RogueHandbookMiracle);
     implement!(rogue_handbook_miracle_type, u16 => rogue::RogueHandbookMiracleType);
     main_sub_implement!(rogue_maze_buff, u32 => misc::MazeBuff);
-    implement!(rogue_miracle, u16 => rogue::RogueMiracle);
+    implement!(rogue_miracle, u16 => rogue::RogueMiracle<Self>);
     implement!(rogue_miracle_effect_display, u16 => rogue::RogueMiracleEffectDisplay);
     implement!(rogue_miracle_display, u16 => rogue::RogueMiracleDisplay);
     implement!(rogue_monster, u32 => rogue::RogueMonster);
     implement!(rogue_monster_group, u32 => rogue::RogueMonsterGroup);
     // rogue magic
-    implement!(rogue_magic_miracle, u16 => rogue::RogueMiracle);
+    implement!(rogue_magic_miracle, u16 => rogue::RogueMiracle<Self>);
     // rogue tourn
     implement!(rogue_bonus, u16 => rogue::tourn::RogueBonus);
     main_sub_implement!(rogue_tourn_buff, u32 => rogue::tourn::RogueTournBuff<Self>);
@@ -364,9 +380,9 @@ impl ExcelOutput for data::GameData {
     implement!(rogue_tourn_formula, u32 => rogue::tourn::RogueTournFormula<Self>);
     implement!(rogue_tourn_formula_display, u32 => rogue::tourn::RogueTournFormulaDisplay);
     implement!(rogue_tourn_handbook_miracle, u16 => rogue::tourn::RogueTournHandbookMiracle);
-    implement!(rogue_tourn_miracle, u16 => rogue::tourn::RogueTournMiracle);
+    implement!(rogue_tourn_miracle, u16 => rogue::tourn::RogueTournMiracle<Self>);
     implement!(rogue_tourn_miracle_display, u16 => rogue::RogueMiracleDisplay);
-    implement!(rogue_tourn_titan_bless, u16 => rogue::tourn::RogueTournTitanBless);
+    implement!(rogue_tourn_titan_bless, u16 => rogue::tourn::RogueTournTitanBless<Self>);
     implement!(rogue_tourn_weekly_challenge, u8 => rogue::tourn::RogueTournWeeklyChallenge<Self>);
     implement!(rogue_tourn_weekly_display, u16 => rogue::tourn::RogueTournWeeklyDisplay<Self>);
     // talk
@@ -411,6 +427,42 @@ impl ExcelOutput for data::GameData {
         )
     }
 
+    fn monster_config_in_template(
+        &self,
+        id: u32,
+    ) -> impl Iterator<Item = monster::MonsterConfig<'_, Self>> {
+        self._monster_config_in_template()
+            .get_vec(&id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|monster| monster::MonsterConfig::from_model(self, monster))
+    }
+
+    fn sub_mission_in_main_mission(
+        &self,
+        main_id: u32,
+    ) -> impl Iterator<Item = mission::SubMission<'_>> {
+        self._sub_mission_in_main_mission()
+            .get_vec(&main_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|sub| mission::SubMission::from_model(self, sub))
+    }
+
+    fn message_section_of_main_mission(
+        &self,
+        main_id: u32,
+    ) -> impl Iterator<Item = message::MessageSectionConfig<'_, Self>> {
+        self._message_section_of_main_mission()
+            .get_vec(&main_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|section| message::MessageSectionConfig::from_model(self, section))
+    }
+
     fn challenge_maze_in_group(&self, id: u16) -> Vec<challenge::ChallengeMazeConfig<Self>> {
         self._challenge_maze_in_group()
             .get_vec(&id)
@@ -450,6 +502,12 @@ impl ExcelOutput for data::GameData {
             .map(|model| book::LocalbookConfig::from_model(self, model))
     }
 
+    fn extra_effect_config_by_name(&self, name: &str) -> Option<misc::ExtraEffectConfig<'_>> {
+        self._extra_effect_config_by_name()
+            .get(name)
+            .map(|model| misc::ExtraEffectConfig::from_model(self, model))
+    }
+
     fn rogue_tourn_buff_by_name(&self, name: &str) -> Option<rogue::tourn::RogueTournBuff<Self>> {
         self._rogue_tourn_buff_by_name()
             .get(name)
@@ -462,6 +520,27 @@ impl ExcelOutput for data::GameData {
             .map(|model| rogue::RogueBuff::from_model(self, model))
     }
 
+    fn rogue_miracle_by_name(&self, name: &str) -> Option<rogue::RogueMiracle<'_, Self>> {
+        self._rogue_miracle_by_name()
+            .get(name)
+            .map(|model| rogue::RogueMiracle::from_model(self, model))
+    }
+
+    fn rogue_magic_miracle_by_name(&self, name: &str) -> Option<rogue::RogueMiracle<'_, Self>> {
+        self._rogue_magic_miracle_by_name()
+            .get(name)
+            .map(|model| rogue::RogueMiracle::from_model(self, model))
+    }
+
+    fn rogue_tourn_miracle_by_name(
+        &self,
+        name: &str,
+    ) -> Option<rogue::tourn::RogueTournMiracle<'_, Self>> {
+        self._rogue_tourn_miracle_by_name()
+            .get(name)
+            .map(|model| rogue::tourn::RogueTournMiracle::from_model(self, model))
+    }
+
     fn story(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<story::Story> {
         Ok(story::Story::from_model(self, self._load_story(path)?))
     }
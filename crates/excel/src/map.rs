@@ -8,18 +8,19 @@ use model::Element;
 
 use crate::{ExcelOutput, FromModel};
 
-#[derive(Clone, Debug)]
-pub struct MapEntrance<'a> {
+#[derive(educe::Educe)]
+#[educe(Clone, Debug)]
+pub struct MapEntrance<'a, Data: ?Sized> {
     pub id: u32,
     pub r#type: MapEntranceType,
     pub plane: MazePlane<'a>,
     pub floor: MazeFloor<'a>,
-    pub begin_main_mission_list: Vec<crate::mission::MainMission<'a>>,
-    pub finish_main_mission_list: Vec<crate::mission::MainMission<'a>>,
+    pub begin_main_mission_list: Vec<crate::mission::MainMission<'a, Data>>,
+    pub finish_main_mission_list: Vec<crate::mission::MainMission<'a, Data>>,
     pub finish_sub_mission_list: Vec<crate::mission::SubMission<'a>>,
 }
 
-impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MapEntrance<'a> {
+impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MapEntrance<'a, Data> {
     type Model = model::map::MapEntrance;
     fn from_model(game: &'a Data, model: &'a Self::Model) -> Self {
         Self {
@@ -66,7 +67,7 @@ pub struct MappingInfo<'a, Data: ExcelOutput + ?Sized> {
     pub desc: &'a str,
     pub show_monster_list: Vec<crate::monster::MonsterConfig<'a, Data>>,
     pub display_item_list: Vec<crate::item::ItemList<'a>>,
-    pub entrance: Option<MapEntrance<'a>>,
+    pub entrance: Option<MapEntrance<'a, Data>>,
 }
 
 impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MappingInfo<'a, Data> {
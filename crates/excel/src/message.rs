@@ -1,9 +1,10 @@
 use std::borrow::Cow;
 use std::num::NonZero;
 
-use base::Wiki;
+use base::{Name, Template, Wiki};
 pub use model::message::{EmojiGender, EmojiGroupType, MessageItemType, MessageSender};
 
+use crate::wiki::{render_installed, Site, WikiContext};
 use crate::{ExcelOutput, FromModel};
 
 #[derive(Clone, Debug)]
@@ -259,6 +260,15 @@ impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MessageContactsConfig<'a, Da
     }
 }
 
+impl<Data: ExcelOutput> Name for MessageContactsConfig<'_, Data> {
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn wiki_name(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self.name)
+    }
+}
+
 impl<Data: ExcelOutput + format::GameData> Wiki for MessageContactsConfig<'_, Data> {
     fn wiki(&self) -> std::borrow::Cow<'static, str> {
         let mut wiki = String::new();
@@ -409,7 +419,7 @@ pub struct MessageSectionConfig<'a, Data: ExcelOutput + ?Sized> {
     pub id: u32,
     pub start_message_item_list: Vec<MessageItemConfig<'a, Data>>,
     pub is_perform_message: bool,
-    pub main_mission_link: Option<crate::mission::MainMission<'a>>,
+    pub main_mission_link: Option<crate::mission::MainMission<'a, Data>>,
     _contacts: std::sync::OnceLock<MessageContactsConfig<'a, Data>>,
 }
 
@@ -493,6 +503,19 @@ impl<Data: ExcelOutput> MessageSectionConfig<'_, Data> {
         }
     }
 
+    /// 图片路径，目录全部用小写，文件名保持大写，方便拿去查询解包数据
+    fn image_path(game: &Data, content_id: u32) -> String {
+        let path = game.message_item_image(content_id).unwrap().image_path;
+        let segments = path.split('/').collect::<Vec<_>>();
+        segments[..segments.len() - 1]
+            .iter()
+            .map(|segment| segment.to_ascii_lowercase())
+            .map(Cow::Owned)
+            .chain([Cow::Borrowed(segments[segments.len() - 1])])
+            .intersperse(Cow::Borrowed("/"))
+            .collect()
+    }
+
     fn try_get_emoji(game: &Data, content_id: u32) -> EmojiConfig {
         game.emoji_config(content_id).unwrap_or({
             // 1.2 版本及之前没有解包 emoji 信息，尝试一下手动组装
@@ -538,20 +561,7 @@ impl<Data: ExcelOutput + format::GameData> MessageSectionConfig<'_, Data> {
             game: &Data,
             message: &MessageItemConfig<Data>,
         ) -> Cow<'static, str> {
-            let path = game
-                .message_item_image(message.content_id)
-                .unwrap()
-                .image_path;
-            // 处理一下图片路径，目录全部用小写，文件名保持大写，方便拿去查询
-            // 解包数据中
-            let segments = path.split('/').collect::<Vec<_>>();
-            let path = segments[..segments.len() - 1]
-                .iter()
-                .map(|segment| segment.to_ascii_lowercase())
-                .map(Cow::Owned)
-                .chain([Cow::Borrowed(segments[segments.len() - 1])])
-                .intersperse(Cow::Borrowed("/"))
-                .collect::<String>();
+            let path = MessageSectionConfig::image_path(game, message.content_id);
             Cow::Owned(format!("{}<!-- {} -->", message.main_text, path))
         }
         let text = match message.r#type {
@@ -739,36 +749,239 @@ impl<Data: ExcelOutput + format::GameData> MessageSectionConfig<'_, Data> {
 
 impl<Data: ExcelOutput + format::GameData> Wiki for MessageSectionConfig<'_, Data> {
     fn wiki(&self) -> std::borrow::Cow<'static, str> {
-        let mut wiki = String::from("{{角色对话|模板开始|");
-        let mut indent = String::from("\n");
-        let mut formatter = format::Formatter::new(self.game).media_wiki_syntax(true);
-        let contacts = self.contacts();
-        wiki.push_str(&formatter.format(contacts.name, &[]));
-        // 非自机角色的短信签名需要作为参数传入（自机角色由模板自动查询了）
-        let contacts_type = contacts
-            .r#type
+        render_installed(self)
+    }
+
+    /// 对话开头的 {{角色对话|模板开始|联系人|签名}}，对话内容和结尾不包括在内
+    fn wiki_template(&self) -> Option<Template> {
+        crate::wiki::main_template(&self.wiki())
+    }
+}
+
+/// 模板 message_section.jinja 的上下文
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MessageSectionContext {
+    /// 联系人名称
+    pub contacts: String,
+    /// 联系人类型名称，如「角色」
+    pub contacts_type: String,
+    /// 联系人签名
+    pub signature: String,
+    pub messages: Vec<MessageContext>,
+    /// 短信结束后接取的任务
+    pub mission: Option<String>,
+    /// 接取任务的类型：Branch、Companion、Daily、Gap、Main
+    pub mission_type: Option<model::mission::MainMissionType>,
+}
+
+/// 一条消息或者一组选项
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(tag = "type")]
+pub enum MessageContext {
+    Item {
+        /// NPC、Player、PlayerAuto、System
+        sender: MessageSender,
+        /// 发送者名称，开拓者为 none
+        contacts: Option<String>,
+        /// Image、Link、Raid、Sticker、Text、Video
+        kind: MessageItemType,
+        /// 已按 wiki 语法格式化的文本，表情在 BWIKI 上为表情编号，在 Fandom 上为表情的关键词
+        text: String,
+        /// 图片或表情的路径
+        image: Option<String>,
+    },
+    /// 需要玩家选择的选项，各选项之后的消息各自分支，分支结束后汇合到下一条消息
+    Selection {
+        /// 选项均为表情
+        sticker: bool,
+        options: Vec<MessageOptionContext>,
+    },
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MessageOptionContext {
+    pub text: String,
+    /// 表情选项的表情路径
+    pub image: Option<String>,
+    /// 选择该选项之后，到分支汇合之前的消息
+    pub messages: Vec<MessageContext>,
+}
+
+impl<Data: ExcelOutput + format::GameData> MessageSectionConfig<'_, Data> {
+    /// BWIKI 用表情编号，Fandom 用表情关键词
+    fn emoji_text(site: Site, emoji: &EmojiConfig) -> String {
+        match site {
+            Site::Bwiki => emoji.wiki().into_owned(),
+            Site::Fandom => emoji.keywords.to_string(),
+        }
+    }
+
+    /// 同 wiki_message_single_item_content
+    fn context_item(
+        &self,
+        site: Site,
+        formatter: &mut format::Formatter<Data>,
+        message: &MessageItemConfig<Data>,
+    ) -> MessageContext {
+        let (text, image) = match message.r#type {
+            MessageItemType::Sticker => {
+                let emoji = Self::try_get_emoji(self.game, message.content_id);
+                (Self::emoji_text(site, &emoji), Some(emoji.path.to_string()))
+            }
+            // 1.2 及之前图片的类型也是 Text
+            MessageItemType::Image | MessageItemType::Text if message.content_id != 0 => (
+                formatter.format(message.main_text, &[]),
+                Some(Self::image_path(self.game, message.content_id)),
+            ),
+            _ => (formatter.format(message.main_text, &[]), None),
+        };
+        let contacts = match message.sender {
+            MessageSender::NPC => Some(
+                message
+                    .contacts
+                    .as_ref()
+                    .map(|contacts| contacts.name)
+                    .unwrap_or_else(|| self.contacts().name),
+            )
+            .filter(|&contacts| contacts != "{NICKNAME}"),
+            MessageSender::Player | MessageSender::PlayerAuto => {
+                message.contacts.as_ref().map(|contacts| contacts.name)
+            }
+            MessageSender::System => None,
+        };
+        MessageContext::Item {
+            sender: message.sender,
+            contacts: contacts.map(|contacts| formatter.format(contacts, &[])),
+            kind: message.r#type,
+            text,
+            image,
+        }
+    }
+
+    /// 同 wiki_message_single_selection_content
+    fn context_selection(
+        &self,
+        site: Site,
+        messages: &mut Vec<MessageContext>,
+        formatter: &mut format::Formatter<Data>,
+        selections: &[MessageItemConfig<Data>],
+    ) -> Option<MessageItemConfig<'_, Data>> {
+        let sticker = selections
+            .iter()
+            .all(|message| message.r#type == MessageItemType::Sticker);
+        let next_ids = selections
+            .iter()
+            .map(|message| message.id)
+            .collect::<Vec<_>>();
+        let convergence = self.next_convergence_node(&next_ids);
+        let convergence_id = convergence
             .as_ref()
-            .map(|r#type| r#type.name)
+            .map(|message| message.id)
             .unwrap_or_default();
-        if contacts_type != "角色" && !contacts.signature_text.is_empty() {
-            wiki.push('|');
-            wiki.push_str(contacts.signature_text);
+        let options = selections
+            .iter()
+            .map(|message| {
+                let (text, image) = if sticker {
+                    let emoji = Self::try_get_emoji(self.game, message.content_id);
+                    (Self::emoji_text(site, &emoji), Some(emoji.path.to_string()))
+                } else {
+                    (formatter.format(message.option_text, &[]), None)
+                };
+                let mut branch = Vec::new();
+                self.context_next_message(site, &mut branch, formatter, message, convergence_id);
+                MessageOptionContext {
+                    text,
+                    image,
+                    messages: branch,
+                }
+            })
+            .collect();
+        messages.push(MessageContext::Selection { sticker, options });
+        convergence
+    }
+
+    /// 同 wiki_next_message
+    fn context_next_message(
+        &self,
+        site: Site,
+        messages: &mut Vec<MessageContext>,
+        formatter: &mut format::Formatter<Data>,
+        message: &MessageItemConfig<Data>,
+        convergence_id: u32,
+    ) {
+        if message.id == convergence_id {
+            return;
         }
-        wiki.push_str("}}");
-        indent.push_str(Self::INDENT);
-        self.wiki_message_content(&mut wiki, &mut formatter, &mut indent);
-        if let Some(mission) = &self.main_mission_link {
-            wiki.push_str(&indent);
-            wiki.push_str("{{接取任务|");
-            wiki.push_str(&mission.r#type.wiki());
-            wiki.push('|');
-            wiki.push_str(mission.name);
-            wiki.push_str("}}");
+        messages.push(self.context_item(site, formatter, message));
+        if message.next_item_id_list.len() == 1 {
+            let next = self
+                .game
+                .message_item_config(message.next_item_id_list[0])
+                .unwrap();
+            if next.id != convergence_id && !next.option_text.is_empty() {
+                // 只有一个选项
+                messages.push(MessageContext::Selection {
+                    sticker: false,
+                    options: vec![MessageOptionContext {
+                        text: formatter.format(next.option_text, &[]),
+                        image: None,
+                        messages: Vec::new(),
+                    }],
+                });
+            }
+            self.context_next_message(site, messages, formatter, &next, convergence_id);
+        }
+        if message.next_item_id_list.len() > 1 {
+            let selections = message
+                .next_item_id_list
+                .iter()
+                .map(|&id| self.game.message_item_config(id))
+                .map(Option::unwrap)
+                .collect::<Vec<_>>();
+            if let Some(next) = self.context_selection(site, messages, formatter, &selections) {
+                self.context_next_message(site, messages, formatter, &next, convergence_id);
+            }
+        }
+    }
+}
+
+impl<Data: ExcelOutput + format::GameData> WikiContext for MessageSectionConfig<'_, Data> {
+    const TEMPLATE: &'static str = "message_section";
+    type Context<'c>
+        = MessageSectionContext
+    where
+        Self: 'c;
+
+    fn wiki_context(&self, site: Site) -> MessageSectionContext {
+        let mut formatter = site.formatter(self.game);
+        let contacts = self.contacts();
+        let mut messages = Vec::new();
+        if self.start_message_item_list.len() == 1 {
+            let start = &self.start_message_item_list[0];
+            self.context_next_message(site, &mut messages, &mut formatter, start, 0);
+        } else {
+            let start = &self.start_message_item_list;
+            if let Some(next) = self.context_selection(site, &mut messages, &mut formatter, start) {
+                self.context_next_message(site, &mut messages, &mut formatter, &next, 0);
+            }
+        }
+        MessageSectionContext {
+            contacts: formatter.format(contacts.name, &[]),
+            contacts_type: contacts
+                .r#type
+                .as_ref()
+                .map(|r#type| r#type.name.to_string())
+                .unwrap_or_default(),
+            signature: contacts.signature_text.to_string(),
+            messages,
+            mission: self
+                .main_mission_link
+                .as_ref()
+                .map(|mission| mission.name.to_string()),
+            mission_type: self
+                .main_mission_link
+                .as_ref()
+                .map(|mission| mission.r#type),
         }
-        indent.truncate(indent.len() - Self::INDENT_LENGTH);
-        wiki.push_str(&indent);
-        wiki.push_str("{{角色对话|模板结束}}");
-        indent.truncate(indent.len() - Self::INDENT_LENGTH);
-        Cow::Owned(wiki)
     }
 }
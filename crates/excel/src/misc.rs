@@ -66,6 +66,40 @@ impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MazeBuff<'a> {
     }
 }
 
+/// 分等级的描述，各等级文案相同、参数不同，比如 MazeBuff 的各个等级、同一怪物技能在不同难度下的参数
+pub trait Leveled {
+    fn desc(&self) -> &str;
+    fn params(&self) -> &[format::Argument<'_>];
+}
+
+impl Leveled for MazeBuff<'_> {
+    fn desc(&self) -> &str {
+        self.desc
+    }
+
+    fn params(&self) -> &[format::Argument<'_>] {
+        &self.params
+    }
+}
+
+/// 将同一描述的各个等级合并为一段描述，等级间变化的参数以 10%/12%/14% 形式呈现
+/// 各等级文案不同（比如强化后追加了效果）时无法合并，返回 None
+pub fn format_levels<'a, T: Leveled + 'a, Data: format::GameData>(
+    levels: impl IntoIterator<Item = &'a T>,
+    formatter: &mut format::Formatter<Data>,
+) -> Option<String> {
+    let levels = levels.into_iter().collect::<Vec<_>>();
+    let first = levels.first()?;
+    if levels.iter().any(|level| level.desc() != first.desc()) {
+        return None;
+    }
+    let params = levels
+        .iter()
+        .map(|level| level.params())
+        .collect::<Vec<_>>();
+    Some(formatter.format_levels(first.desc(), &params))
+}
+
 #[derive(educe::Educe)]
 #[educe(Clone, Debug)]
 pub struct RewardData<'a> {
@@ -86,6 +120,46 @@ pub struct RewardData<'a> {
     pub is_special: bool,
 }
 
+impl RewardData<'_> {
+    /// 展开为物品和数量，星琼作为物品 1 列在最前
+    /// 角色等不在物品表中的奖励会被略去
+    pub fn items<'a, Data: ExcelOutput>(
+        &self,
+        game: &'a Data,
+    ) -> Vec<(crate::item::ItemConfig<'a>, u32)> {
+        const HCOIN: u32 = 1;
+        let hcoin = Some((HCOIN, self.hcoin as u32)).filter(|&(_, count)| count != 0);
+        hcoin
+            .into_iter()
+            .chain(std::iter::zip(self.item_ids, self.counts).map(|(&id, &count)| (id, count)))
+            .filter(|&(id, _)| id != 0)
+            .filter_map(|(id, count)| {
+                None.or_else(|| game.item_config(id))
+                    .or_else(|| game.item_config_avatar_rank(id))
+                    .or_else(|| game.item_config_equipment(id))
+                    .or_else(|| game.item_config_book(id))
+                    .map(|item| (item, count))
+            })
+            .collect()
+    }
+}
+
+/// 奖励中的一项，用于各模板的上下文
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RewardItemContext<'a> {
+    pub name: &'a str,
+    pub count: u32,
+}
+
+impl<'a> From<(crate::item::ItemConfig<'a>, u32)> for RewardItemContext<'a> {
+    fn from((item, count): (crate::item::ItemConfig<'a>, u32)) -> Self {
+        Self {
+            name: item.name,
+            count,
+        }
+    }
+}
+
 fn flat_array<T: std::fmt::Debug, const N: usize>(
     values: &[T; N],
     f: &mut std::fmt::Formatter<'_>,
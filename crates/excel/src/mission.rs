@@ -1,27 +1,47 @@
+use std::borrow::Cow;
 use std::num::NonZero;
 
+use base::{Template, Wiki};
 pub use model::mission::MainMissionType;
 
+use crate::wiki::{render_installed, Site, WikiContext};
 use crate::{misc::RewardData, ExcelOutput, FromModel};
 
 #[derive(Clone, Debug)]
-pub struct MissionChapterConfig {
+pub struct MissionChapterConfig<'a> {
     pub id: u32,
+    /// 章节名，如「第一章」
+    pub name: &'a str,
+    /// 章节标题
+    pub stage_name: &'a str,
+    pub desc: &'a str,
     pub display_priority: u32,
 }
 
-impl<Data: ExcelOutput> FromModel<'_, Data> for MissionChapterConfig {
+impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MissionChapterConfig<'a> {
     type Model = model::mission::MissionChapterConfig;
-    fn from_model(_game: &Data, model: &Self::Model) -> Self {
+    fn from_model(game: &'a Data, model: &'a Self::Model) -> Self {
+        // 章节的文本是字符串 key 而不是哈希值
+        let text = |key: &str| {
+            model::Text::from_key(key)
+                .map(|text| game.text(text))
+                .unwrap_or_default()
+        };
         Self {
             id: model.id,
+            name: text(&model.chapter_name),
+            stage_name: text(model.stage_name.as_deref().unwrap_or_default()),
+            desc: text(&model.chapter_desc),
             display_priority: model.chapter_display_priority,
         }
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct MainMission<'a> {
+#[derive(educe::Educe)]
+#[educe(Clone, Debug)]
+pub struct MainMission<'a, Data: ?Sized> {
+    #[educe(Debug(ignore))]
+    game: &'a Data,
     pub id: u32,
     pub r#type: MainMissionType,
     pub world: Option<crate::map::WorldDataConfig<'a>>,
@@ -31,14 +51,15 @@ pub struct MainMission<'a> {
     pub track_weight: Option<NonZero<u8>>,
     pub reward: Option<RewardData<'a>>,
     pub display_reward: Option<RewardData<'a>>,
-    pub chapter: Option<MissionChapterConfig>,
+    pub chapter: Option<MissionChapterConfig<'a>>,
     pub sub_reward_list: Vec<RewardData<'a>>,
 }
 
-impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MainMission<'a> {
+impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MainMission<'a, Data> {
     type Model = model::mission::MainMission;
     fn from_model(game: &'a Data, model: &Self::Model) -> Self {
         Self {
+            game,
             id: model.main_mission_id,
             r#type: model.r#type,
             world: model
@@ -75,6 +96,116 @@ impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MainMission<'a> {
     }
 }
 
+impl<'a, Data: ExcelOutput> MainMission<'a, Data> {
+    /// 完成后追踪的下一个任务
+    pub fn next_track(&self) -> Option<MainMission<'a, Data>> {
+        self.next_track_main_mission
+            .map(NonZero::get)
+            .and_then(|id| self.game.main_mission(id))
+    }
+
+    /// 子任务 ID 为主任务 ID 后接两位序号，1.0 之后 SubMission 中不再记录所属的主任务
+    pub fn sub_missions(&self) -> impl Iterator<Item = SubMission<'a>> + '_ {
+        self.game.sub_mission_in_main_mission(self.id)
+    }
+
+    /// 结束后接取本任务的短信
+    pub fn message_sections(
+        &self,
+    ) -> impl Iterator<Item = crate::message::MessageSectionConfig<'a, Data>> + '_ {
+        self.game.message_section_of_main_mission(self.id)
+    }
+}
+
+/// 模板 main_mission.jinja 的上下文
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MainMissionContext<'a> {
+    pub name: &'a str,
+    /// Main、Branch、Companion、Daily、Gap
+    pub r#type: MainMissionType,
+    pub chapter: Option<MissionChapterContext<'a>>,
+    /// 所在世界，如「雅利洛-VI」
+    pub world: Option<&'a str>,
+    /// 任务奖励
+    pub rewards: Vec<crate::misc::RewardItemContext<'a>>,
+    /// 子任务，按 ID 排序，已略去没有目标文本的
+    pub objectives: Vec<SubMissionContext>,
+    /// 完成后追踪的下一个任务
+    pub next: Option<&'a str>,
+    /// 结束后接取本任务的短信的联系人
+    pub messages: Vec<&'a str>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MissionChapterContext<'a> {
+    pub name: &'a str,
+    pub stage_name: &'a str,
+    pub desc: &'a str,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SubMissionContext {
+    pub target: String,
+    pub desc: String,
+}
+
+impl<Data: ExcelOutput + format::GameData> WikiContext for MainMission<'_, Data> {
+    const TEMPLATE: &'static str = "main_mission";
+    type Context<'c>
+        = MainMissionContext<'c>
+    where
+        Self: 'c;
+
+    fn wiki_context(&self, site: Site) -> MainMissionContext<'_> {
+        let mut formatter = site.formatter(self.game);
+        let mut sub_missions = self
+            .sub_missions()
+            .filter(|sub| !sub.target.is_empty())
+            .collect::<Vec<_>>();
+        sub_missions.sort_by_key(|sub| sub.id);
+        let objectives = sub_missions
+            .iter()
+            .map(|sub| SubMissionContext {
+                target: formatter.format(sub.target, &[]),
+                desc: formatter.format(sub.description, &[]),
+            })
+            .collect();
+        MainMissionContext {
+            name: self.name,
+            r#type: self.r#type,
+            chapter: self.chapter.as_ref().map(|chapter| MissionChapterContext {
+                name: chapter.name,
+                stage_name: chapter.stage_name,
+                desc: chapter.desc,
+            }),
+            world: self.world.as_ref().map(|world| world.name),
+            rewards: self
+                .reward
+                .iter()
+                .flat_map(|reward| reward.items(self.game))
+                .map(crate::misc::RewardItemContext::from)
+                .collect(),
+            objectives,
+            next: self.next_track().map(|mission| mission.name),
+            messages: self
+                .message_sections()
+                .filter_map(|section| self.game.message_contacts_of_section(section.id))
+                .map(|contacts| contacts.name)
+                .collect(),
+        }
+    }
+}
+
+impl<Data: ExcelOutput + format::GameData> Wiki for MainMission<'_, Data> {
+    fn wiki(&self) -> Cow<'static, str> {
+        render_installed(self)
+    }
+
+    fn wiki_template(&self) -> Option<Template> {
+        crate::wiki::main_template(&self.wiki())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SubMission<'a> {
     pub id: u32,
@@ -1,7 +1,11 @@
 // 末日幻影 Boss 注释
 
+use std::borrow::Cow;
 use std::num::NonZero;
 
+use base::{Template, Wiki};
+
+use crate::wiki::{render_installed, Site, WikiContext};
 use crate::{ExcelOutput, FromModel};
 
 #[derive(Clone, Debug)]
@@ -9,7 +13,7 @@ pub struct MonsterDifficultyGuide<'a> {
     pub id: u16,
     pub description: &'a str,
     pub skill: Option<crate::monster::MonsterSkillConfig<'a>>,
-    pub parameter_list: &'a [f32],
+    pub parameter_list: Vec<format::Argument<'a>>,
 }
 
 impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MonsterDifficultyGuide<'a> {
@@ -23,13 +27,17 @@ impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MonsterDifficultyGuide<'a> {
                 .map(NonZero::get)
                 .map(|id| game.monster_skill_config(id))
                 .map(Option::unwrap),
-            parameter_list: &model.parameter_list,
+            parameter_list: format::Argument::from_array(&model.parameter_list),
         }
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct MonsterGuideConfig<'a> {
+#[derive(educe::Educe)]
+#[educe(Clone, Debug)]
+/// 末日幻影首领的图鉴，id 即首领的 MonsterConfig ID
+pub struct MonsterGuideConfig<'a, Data: ?Sized> {
+    #[educe(Debug(ignore))]
+    game: &'a Data,
     pub id: u32,
     pub difficulty: u8,
     pub difficulty_list: &'a [u8],
@@ -40,10 +48,11 @@ pub struct MonsterGuideConfig<'a> {
     pub text_guide_list: Vec<MonsterTextGuide<'a>>,
 }
 
-impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MonsterGuideConfig<'a> {
+impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MonsterGuideConfig<'a, Data> {
     type Model = model::monster::guide::MonsterGuideConfig;
     fn from_model(game: &'a Data, model: &'a Self::Model) -> Self {
         Self {
+            game,
             id: model.monster_id,
             difficulty: model.difficulty,
             difficulty_list: &model.difficulty_list,
@@ -143,6 +152,7 @@ pub struct MonsterGuideSkillText<'a> {
     pub id: u32,
     pub difficulty: u8,
     pub description: &'a str,
+    pub parameter_list: Vec<format::Argument<'a>>,
     pub effect_list: Vec<crate::misc::ExtraEffectConfig<'a>>,
 }
 
@@ -153,6 +163,7 @@ impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MonsterGuideSkillText<'a> {
             id: model.skill_text_id,
             difficulty: model.difficulty,
             description: game.text(model.skill_description),
+            parameter_list: format::Argument::from_array(&model.parameter_list),
             effect_list: model
                 .effect_id_list
                 .iter()
@@ -205,7 +216,7 @@ impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MonsterGuideTag<'a> {
 pub struct MonsterTextGuide<'a> {
     pub id: u16,
     pub description: &'a str,
-    pub parameter_list: &'a [f32],
+    pub parameter_list: Vec<format::Argument<'a>>,
 }
 
 impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MonsterTextGuide<'a> {
@@ -214,7 +225,265 @@ impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MonsterTextGuide<'a> {
         Self {
             id: model.text_guide_id,
             description: game.text(model.text_guide_description),
-            parameter_list: &model.parameter_list,
+            parameter_list: format::Argument::from_array(&model.parameter_list),
+        }
+    }
+}
+
+/// 模板 monster_guide.jinja 的上下文
+/// 模板中通过 guide 访问，以便嵌入末日幻影页面时用 `with guide = half.guide` 复用
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MonsterGuidePageContext<'a> {
+    pub guide: MonsterGuideContext<'a>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MonsterGuideContext<'a> {
+    /// 首领的页面名
+    pub name: String,
+    /// 难度星级，1 ~ 4
+    pub difficulty: u8,
+    pub difficulty_list: &'a [u8],
+    /// 简要攻略
+    pub brief: String,
+    /// 首领特性
+    pub tags: Vec<MonsterGuideTagContext<'a>>,
+    /// 各阶段的技能说明
+    pub phases: Vec<MonsterGuidePhaseContext<'a>>,
+    /// 各难度的注意事项
+    pub difficulty_notes: Vec<MonsterGuideNoteContext<'a>>,
+    /// 文字攻略
+    pub text_notes: Vec<String>,
+}
+
+/// 描述中带下划线的名词及其说明
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MonsterGuideEffectContext<'a> {
+    pub name: &'a str,
+    pub desc: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MonsterGuideTagContext<'a> {
+    pub name: &'a str,
+    pub brief: String,
+    /// 详细说明，大部分为空
+    pub detail: String,
+    pub effects: Vec<MonsterGuideEffectContext<'a>>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MonsterGuidePhaseContext<'a> {
+    pub name: &'a str,
+    pub difficulty: u8,
+    pub desc: String,
+    /// 应对方法
+    pub answer: String,
+    pub skills: Vec<MonsterGuideSkillContext<'a>>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MonsterGuideSkillContext<'a> {
+    pub name: &'a str,
+    pub difficulty: u8,
+    /// 技能的各条说明
+    pub texts: Vec<String>,
+    pub effects: Vec<MonsterGuideEffectContext<'a>>,
+    /// 应对方法
+    pub answer: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MonsterGuideNoteContext<'a> {
+    pub desc: String,
+    /// 注意事项关联的技能
+    pub skill: Option<&'a str>,
+    pub skill_desc: Option<String>,
+}
+
+fn effect_context<'a: 'e, 'e, Data: format::GameData>(
+    formatter: &mut format::Formatter<Data>,
+    effects: impl IntoIterator<Item = &'e crate::misc::ExtraEffectConfig<'a>>,
+) -> Vec<MonsterGuideEffectContext<'a>> {
+    effects
+        .into_iter()
+        .map(|effect| MonsterGuideEffectContext {
+            name: effect.name,
+            desc: formatter.format(effect.desc, &effect.desc_params),
+        })
+        .collect()
+}
+
+impl<'a, Data: ExcelOutput + format::GameData> MonsterGuideConfig<'a, Data> {
+    /// 首领的 MonsterConfig
+    pub fn monster(&self) -> Option<crate::monster::MonsterConfig<'a, Data>> {
+        None.or_else(|| self.game.monster_config(self.id))
+            .or_else(|| self.game.monster_unique_config(self.id))
+    }
+
+    pub(crate) fn context(
+        &self,
+        site: Site,
+        formatter: &mut format::Formatter<Data>,
+    ) -> MonsterGuideContext<'a> {
+        let tags = self
+            .tag_list
+            .iter()
+            .map(|tag| {
+                let params = match &tag.skill {
+                    Some(skill) => skill.params.as_slice(),
+                    None => tag.parameter_list.as_slice(),
+                };
+                MonsterGuideTagContext {
+                    name: tag.name,
+                    brief: formatter.format(tag.brief_description, params),
+                    detail: formatter.format(tag.detail_description, params),
+                    effects: effect_context(formatter, &tag.effect),
+                }
+            })
+            .collect();
+        let phases = self
+            .phase_list
+            .iter()
+            .map(|phase| MonsterGuidePhaseContext {
+                name: phase.name,
+                difficulty: phase.difficulty,
+                desc: formatter.format(phase.description, &[]),
+                answer: formatter.format(phase.answer, &[]),
+                skills: phase
+                    .skill_list
+                    .iter()
+                    .map(|skill| MonsterGuideSkillContext {
+                        name: skill.name,
+                        difficulty: skill.difficulty,
+                        texts: skill
+                            .text_list
+                            .iter()
+                            .map(|text| formatter.format(text.description, &text.parameter_list))
+                            .collect(),
+                        effects: effect_context(
+                            formatter,
+                            skill.text_list.iter().flat_map(|text| &text.effect_list),
+                        ),
+                        answer: formatter.format(skill.answer, &[]),
+                    })
+                    .collect(),
+            })
+            .collect();
+        let difficulty_notes = self
+            .difficulty_guide_list
+            .iter()
+            .map(|note| MonsterGuideNoteContext {
+                desc: formatter.format(note.description, &note.parameter_list),
+                skill: note.skill.as_ref().map(|skill| skill.name),
+                skill_desc: note
+                    .skill
+                    .as_ref()
+                    .map(|skill| formatter.format(skill.desc, &skill.params)),
+            })
+            .collect();
+        let text_notes = self
+            .text_guide_list
+            .iter()
+            .map(|note| formatter.format(note.description, &note.parameter_list))
+            .collect();
+        MonsterGuideContext {
+            name: self
+                .monster()
+                .map(|monster| monster.page_name(site).into_owned())
+                .unwrap_or_default(),
+            difficulty: self.difficulty,
+            difficulty_list: self.difficulty_list,
+            brief: formatter.format(self.brief_guide, &[]),
+            tags,
+            phases,
+            difficulty_notes,
+            text_notes,
+        }
+    }
+
+    /// 不带任何标记的纯文本，用于终端输出等场合
+    pub fn plain_text(&self) -> String {
+        use std::fmt::Write;
+        let mut formatter = format::Formatter::new(self.game);
+        let guide = self.context(Site::Bwiki, &mut formatter);
+        let mut text = String::new();
+        let name = self
+            .monster()
+            .map(|monster| monster.name)
+            .unwrap_or_default();
+        writeln!(text, "{name}（难度 {}）", guide.difficulty).unwrap();
+        if !guide.brief.is_empty() {
+            writeln!(text, "{}", guide.brief).unwrap();
+        }
+        let write_effects = |text: &mut String, effects: &[MonsterGuideEffectContext]| {
+            for effect in effects {
+                writeln!(text, "    · {}：{}", effect.name, effect.desc).unwrap();
+            }
+        };
+        if !guide.tags.is_empty() {
+            writeln!(text, "\n特性").unwrap();
+        }
+        for tag in &guide.tags {
+            writeln!(text, "  {}：{}", tag.name, tag.brief).unwrap();
+            write_effects(&mut text, &tag.effects);
         }
+        for phase in &guide.phases {
+            writeln!(text, "\n{}", phase.name).unwrap();
+            if !phase.desc.is_empty() {
+                writeln!(text, "  {}", phase.desc).unwrap();
+            }
+            for skill in &phase.skills {
+                writeln!(text, "  {}", skill.name).unwrap();
+                for line in &skill.texts {
+                    writeln!(text, "    {line}").unwrap();
+                }
+                write_effects(&mut text, &skill.effects);
+                if !skill.answer.is_empty() {
+                    writeln!(text, "    应对：{}", skill.answer).unwrap();
+                }
+            }
+            if !phase.answer.is_empty() {
+                writeln!(text, "  应对：{}", phase.answer).unwrap();
+            }
+        }
+        if !guide.difficulty_notes.is_empty() {
+            writeln!(text, "\n难度说明").unwrap();
+        }
+        for note in &guide.difficulty_notes {
+            writeln!(text, "  {}", note.desc).unwrap();
+            if let (Some(skill), Some(desc)) = (note.skill, &note.skill_desc) {
+                writeln!(text, "    {skill}：{desc}").unwrap();
+            }
+        }
+        for note in &guide.text_notes {
+            writeln!(text, "  {note}").unwrap();
+        }
+        text
+    }
+}
+
+impl<Data: ExcelOutput + format::GameData> WikiContext for MonsterGuideConfig<'_, Data> {
+    const TEMPLATE: &'static str = "monster_guide";
+    type Context<'c>
+        = MonsterGuidePageContext<'c>
+    where
+        Self: 'c;
+
+    fn wiki_context(&self, site: Site) -> MonsterGuidePageContext<'_> {
+        let mut formatter = site.formatter(self.game);
+        MonsterGuidePageContext {
+            guide: self.context(site, &mut formatter),
+        }
+    }
+}
+
+impl<Data: ExcelOutput + format::GameData> Wiki for MonsterGuideConfig<'_, Data> {
+    fn wiki(&self) -> Cow<'static, str> {
+        render_installed(self)
+    }
+
+    fn wiki_template(&self) -> Option<Template> {
+        crate::wiki::main_template(&self.wiki())
     }
 }
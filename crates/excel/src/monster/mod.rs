@@ -3,12 +3,13 @@ pub mod guide;
 use std::borrow::Cow;
 use std::num::NonZero;
 
-use base::{FnvIndexMap, Name, Wiki};
+use base::{FnvIndexMap, Name, Template, Wiki};
 pub use model::monster::{
     DebuffResistKey, MonsterCampType, MonsterCharacterType, MonsterRank, MonsterSubType,
 };
 use model::Element;
 
+use crate::wiki::{render_installed, Site, WikiContext};
 use crate::{ExcelOutput, FromModel};
 
 #[derive(Clone)]
@@ -223,6 +224,19 @@ impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MonsterConfig<'a, Data> {
     }
 }
 
+impl<'a, Data: ExcelOutput> MonsterConfig<'a, Data> {
+    /// 同一怪物组别中的变体名，如「某某（完整）」、「某某（错误）」中的「完整」、「错误」
+    /// 取名称末尾全角括号中的部分，只有同组别中还有其他同名前缀的怪物模板时才视为变体
+    pub fn variant(&self) -> Option<&'a str> {
+        let template = self.template.as_ref()?;
+        let (base, variant) = self.name.strip_suffix('）')?.rsplit_once('（')?;
+        self.game
+            .monster_template_config_group(template.group_id)
+            .any(|other| other.id != template.id && other.name.starts_with(base))
+            .then_some(variant)
+    }
+}
+
 impl<Data: ExcelOutput> MonsterConfig<'_, Data> {
     pub fn prototype(&self) -> MonsterConfig<'_, Data> {
         // 不确定 unwrap 会不会挂，总之先试试
@@ -320,6 +334,14 @@ impl<Data: ExcelOutput> MonsterConfig<'_, Data> {
             .collect()
     }
 
+    /// 没有怪物组别的视为召唤物，扑满和虚构集合体除外
+    pub fn is_summon(&self) -> bool {
+        let group_id = (self.template.as_ref())
+            .map(|template| template.group_id)
+            .unwrap_or_default();
+        group_id == 0 && !self.name.contains("扑满") && self.name != "虚构集合体"
+    }
+
     /// 小怪，排除精英、剧情 Boss、周本 Boss
     pub fn is_minion(&self) -> bool {
         let rank = self
@@ -509,215 +531,203 @@ impl<Data: ExcelOutput> Name for MonsterConfig<'_, Data> {
     }
 }
 
-impl<Data: ExcelOutput> Wiki for MonsterConfig<'_, Data> {
-    fn wiki(&self) -> Cow<'static, str> {
-        let mut wiki = String::new();
-        // 名称
-        wiki.push_str("{{敌人\n|名称=");
-        wiki.push_str(&self.wiki_name());
-        wiki.push_str("\n|实装版本=");
-        wiki.push_str("\n|系列=");
-        // 分类（阵营）
-        wiki.push_str("\n|分类=");
+impl<Data: ExcelOutput> MonsterConfig<'_, Data> {
+    /// 在对应站点上的页面名，wiki_name 中的处理都是 BWIKI 的惯例，其它站点直接使用名称
+    pub(crate) fn page_name(&self, site: Site) -> Cow<'_, str> {
+        match site {
+            Site::Bwiki => self.wiki_name(),
+            Site::Fandom => Cow::Borrowed(self.name),
+        }
+    }
+}
+
+/// 模板 monster.jinja 的上下文
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MonsterContext<'a> {
+    /// 页面名，BWIKI 见 Name::wiki_name
+    pub name: Cow<'a, str>,
+    /// 阵营
+    pub camp: &'a str,
+    /// 怪物稀有度：BigBoss、Elite、LittleBoss、Minion、MinionLv2，没有怪物模板时为 none
+    pub rank: Option<MonsterRank>,
+    /// 没有怪物组别的视为召唤物，见 MonsterConfig::is_summon
+    pub is_summon: bool,
+    /// 介绍，换行已替换为 <br />
+    pub introduction: String,
+    /// 弱点属性，属性名见 Site::element
+    pub weaknesses: Vec<Cow<'static, str>>,
+    /// 攻击属性
+    pub damage_types: Vec<Cow<'static, str>>,
+    /// 同一怪物组别中的变体，见 MonsterConfig::variant
+    pub variant: Option<&'a str>,
+    /// 速度，向上取整
+    pub speed: u16,
+    /// 100 级时的速度
+    pub max_speed: u16,
+    pub stance: u16,
+    /// 召唤物的页面名
+    pub summons: Vec<String>,
+    /// 依次为物理、火、冰、雷、风、虚数、量子属性抗性
+    pub resistances: Vec<MonsterResistanceContext>,
+    /// 抗性高于 20% 的属性
+    pub element_resistances: Vec<Cow<'static, str>>,
+    /// 状态抵抗
+    pub debuff_resists: Vec<Cow<'static, str>>,
+    pub phases: Vec<MonsterPhaseContext<'a>>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MonsterResistanceContext {
+    pub element: Cow<'static, str>,
+    /// 百分数，向下取整
+    pub value: u32,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MonsterPhaseContext<'a> {
+    pub skills: Vec<MonsterSkillContext<'a>>,
+    /// 本阶段大招数量
+    pub threat_count: usize,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MonsterSkillContext<'a> {
+    pub name: &'a str,
+    pub tag: &'a str,
+    /// 削韧，没有时为 0
+    pub sp_hit: u16,
+    pub is_threat: bool,
+    /// 已按 wiki 语法格式化的技能描述
+    pub desc: String,
+}
+
+impl<Data: ExcelOutput + format::GameData> WikiContext for MonsterConfig<'_, Data> {
+    const TEMPLATE: &'static str = "monster";
+    type Context<'c>
+        = MonsterContext<'c>
+    where
+        Self: 'c;
+
+    fn wiki_context(&self, site: Site) -> MonsterContext<'_> {
+        let mut formatter = site.formatter(self.game);
         let camp = self
             .template
             .as_ref()
             .map(|template| template.camp())
             .unwrap_or_default();
-        wiki.push_str(camp);
-        wiki.push_str(
-            "<!-- 选填：反物质军团、裂界造物、雅利洛-Ⅵ、仙舟「罗浮」、虫群、星际和平公司、\
-             惊梦剧团、忆域迷因、模拟宇宙、星核猎手、银河 -->",
-        );
-        // 类型（周本Boss、剧情Boss等，这里没法获取全部，需要手动处理）
-        let mut typ = match self.template.as_ref().map(|template| template.rank) {
-            Some(MonsterRank::BigBoss) => "周本BOSS",
-            Some(MonsterRank::Elite) => "强敌",
-            Some(MonsterRank::LittleBoss) => "剧情BOSS",
-            Some(MonsterRank::Minion | MonsterRank::MinionLv2) => "普通",
-            None => "",
-        };
-        let group_id = self
+        let rank = self.template.as_ref().map(|template| template.rank);
+        let summons = self.summons();
+        const RESISTANCE_ELEMENT: [Element; 7] = [
+            Element::Physical,
+            Element::Fire,
+            Element::Ice,
+            Element::Thunder,
+            Element::Wind,
+            Element::Imaginary,
+            Element::Quantum,
+        ];
+        let resistances = RESISTANCE_ELEMENT
+            .into_iter()
+            .map(|element| MonsterResistanceContext {
+                element: site.element(element),
+                value: (self
+                    .damage_type_resistance
+                    .get(&element)
+                    .copied()
+                    .unwrap_or_default()
+                    * 100.) as u32,
+            })
+            .collect();
+        // 同一怪物的各个难度，技能描述中变化的参数合并为 10%/12%/14% 的形式
+        // 同模板下的其他怪物（名称不同，比如变体、精英版本）不参与合并
+        let mut variants = self
             .template
             .as_ref()
-            .map(|template| template.group_id)
-            .unwrap_or_default();
-        if group_id == 0 && !self.name.contains("扑满") && self.name != "虚构集合体" {
-            typ = "召唤物";
-        }
-        wiki.push_str("\n|类型=");
-        wiki.push_str(typ);
-        wiki.push_str(
-            "<!-- 选填：普通、强敌、剧情BOSS、周本BOSS、模拟宇宙精英、模拟宇宙首领、召唤物 -->",
-        );
-        // 介绍
-        wiki.push_str("\n|介绍=");
-        wiki.push_str(&self.introduction.replace("\\n", "<br />"));
-        // 别称
-        wiki.push_str("\n|别称=");
-        // 弱点
-        let weaknesses = self
-            .stance_weak_list
-            .iter()
-            .map(Element::wiki)
-            .intersperse(Cow::Borrowed("、"))
-            .collect::<String>();
-        wiki.push_str("\n|弱点=");
-        wiki.push_str(&weaknesses);
-        // 攻击属性
-        let damage_types = self
-            .damage_types()
-            .iter()
-            .map(Element::wiki)
-            .intersperse(Cow::Borrowed("、"))
-            .collect::<String>();
-        wiki.push_str("\n|攻击属性=");
-        wiki.push_str(&damage_types);
-        wiki.push_str("\n|出现地点=");
-        wiki.push_str("\n|掉落系列素材=");
-        wiki.push_str("\n|掉落素材=");
-        wiki.push_str("\n|掉落期望=");
-        wiki.push_str("\n|TAG=");
-        let mut tags = Vec::<&'static str>::new();
-        let summons = self.summons();
-        if !summons.is_empty() {
-            tags.push("召唤");
-        }
-        if self.name().ends_with("（完整）") {
-            tags.push("完整");
-        }
-        if self.name().ends_with("（错误）") {
-            tags.push("错误");
-        }
-        wiki.push_str(&tags.join("、"));
-        wiki.push_str("\n|速度=");
-        let speed = f32::ceil(self.speed()) as u16;
-        wiki.push_str(&speed.to_string());
-        if speed != 0 {
-            wiki.push('~');
-            let max_speed = self.speed_at(100) as u16;
-            wiki.push_str(&max_speed.to_string());
+            .map(|template| self.game.monster_config_in_template(template.id))
+            .into_iter()
+            .flatten()
+            .filter(|variant| variant.name == self.name)
+            .collect::<Vec<_>>();
+        if !variants.iter().any(|variant| variant.id == self.id) {
+            variants = vec![self.clone()];
         }
-        wiki.push_str("\n|韧性=");
-        let stance = f32::round(self.stance()) as u16;
-        wiki.push_str(&stance.to_string());
-        // 召唤物
-        wiki.push_str("\n|召唤物=");
-        let summon_names = summons
-            .iter()
-            .map(MonsterConfig::wiki_name)
-            .intersperse(Cow::Borrowed("、"))
-            .collect::<String>();
-        wiki.push_str(&summon_names);
-        // 不明，需要去看下 wiki 模板这几个参数是干什么的
-        wiki.push_str("\n|血量档位=");
-        wiki.push_str("\n|血量比例总=");
-        wiki.push_str("\n|血量1名字=");
-        wiki.push_str("\n|血量1比例=");
-        wiki.push_str("\n|血量2名字=");
-        wiki.push_str("\n|血量2比例=");
-        wiki.push_str("\n|血量3名字=");
-        wiki.push_str("\n|血量3比例=");
-        wiki.push_str("\n|血量4名字=");
-        wiki.push_str("\n|血量4比例=");
-        wiki.push_str("\n|血量5名字=");
-        wiki.push_str("\n|血量5比例=");
-        // 属性抗性
-        const RESISTANCE_ELEMENT: [(Element, &str); 7] = [
-            (Element::Physical, "物"),
-            (Element::Fire, "火"),
-            (Element::Ice, "冰"),
-            (Element::Thunder, "雷"),
-            (Element::Wind, "风"),
-            (Element::Imaginary, "虚数"),
-            (Element::Quantum, "量子"),
-        ];
-        for (element, name) in RESISTANCE_ELEMENT {
-            wiki.push_str("\n|");
-            wiki.push_str(name);
-            wiki.push_str("抗=");
-            let v = self
+        variants.sort_by_key(|variant| variant.id);
+        let phases = (1..=self.phase())
+            .map(|phase| {
+                let skills = self.phase_skill(phase);
+                MonsterPhaseContext {
+                    threat_count: skills.iter().filter(|skill| skill.is_threat).count(),
+                    skills: skills
+                        .into_iter()
+                        .map(|skill| MonsterSkillContext {
+                            name: skill.name,
+                            tag: skill.tag,
+                            sp_hit: skill.sp_hit_base,
+                            is_threat: skill.is_threat,
+                            desc: crate::misc::format_levels(
+                                variants.iter().filter_map(|variant| {
+                                    variant.skill_list.iter().find(|other| {
+                                        other.name == skill.name
+                                            && other.phase_list == skill.phase_list
+                                    })
+                                }),
+                                &mut formatter,
+                            )
+                            .unwrap_or_else(|| formatter.format(skill.desc, &skill.params)),
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        MonsterContext {
+            name: self.page_name(site),
+            camp,
+            rank,
+            is_summon: self.is_summon(),
+            introduction: self.introduction.replace("\\n", "<br />"),
+            weaknesses: self
+                .stance_weak_list
+                .iter()
+                .map(|&element| site.element(element))
+                .collect(),
+            damage_types: self
+                .damage_types()
+                .iter()
+                .map(|&element| site.element(element))
+                .collect(),
+            variant: self.variant(),
+            speed: f32::ceil(self.speed()) as u16,
+            max_speed: self.speed_at(100) as u16,
+            stance: f32::round(self.stance()) as u16,
+            summons: summons
+                .iter()
+                .map(|summon| summon.page_name(site).into_owned())
+                .collect(),
+            resistances,
+            element_resistances: self
                 .damage_type_resistance
-                .get(&element)
-                .copied()
-                .unwrap_or_default()
-                * 100.;
-            wiki.push_str(&(v as u32).to_string());
-            wiki.push('%');
-        }
-        wiki.push_str("\n|抗性备注=");
-        wiki.push_str("\n|属性抗性=");
-        let element_resistance = self
-            .damage_type_resistance
-            .iter()
-            .filter(|(_, &resistance)| resistance > 0.2)
-            .map(|(element, _)| format!("{}属性抗性", element.wiki()))
-            .map(Cow::Owned)
-            .intersperse(Cow::Borrowed("、"))
-            .collect::<String>();
-        wiki.push_str(&element_resistance);
-        // 状态抵抗
-        wiki.push_str("\n|状态抵抗=");
-        let resists = self
-            .debuff_resist
-            .iter()
-            .map(|(debuff, _)| debuff.wiki())
-            .intersperse(Cow::Borrowed("、"))
-            .collect::<String>();
-        wiki.push_str(&resists);
-        // 技能
-        let phase_count = self.phase();
-        wiki.push_str("\n|阶段数=");
-        wiki.push_str(&phase_count.to_string());
-        // 减少重复代码
-        fn phase_text(wiki: &mut String, phase: u8) {
-            wiki.push_str("\n|");
-            if phase != 1 {
-                wiki.push_str("阶段");
-                wiki.push_str(&phase.to_string());
-            }
+                .iter()
+                .filter(|(_, &resistance)| resistance > 0.2)
+                .map(|(&element, _)| site.element(element))
+                .collect(),
+            debuff_resists: self
+                .debuff_resist
+                .iter()
+                .map(|(&debuff, _)| site.debuff_resist(debuff))
+                .collect(),
+            phases,
         }
-        for phase in 1..=phase_count {
-            let skills = self.phase_skill(phase);
-            // 技能数
-            phase_text(&mut wiki, phase);
-            wiki.push_str("技能数=");
-            wiki.push_str(&skills.len().to_string());
-            // 大招
-            let threat_count = skills.iter().filter(|skill| skill.is_threat).count();
-            if threat_count != 0 {
-                phase_text(&mut wiki, phase);
-                wiki.push_str("大招=");
-                wiki.push_str(&threat_count.to_string());
-            }
+    }
+}
 
-            for (index, skill) in skills.iter().enumerate() {
-                let sp_hit = if skill.sp_hit_base == 0 {
-                    String::new()
-                } else {
-                    skill.sp_hit_base.to_string()
-                };
-                let skill_kvs: [(&'static str, &str, &'static str); 5] = [
-                    ("名称", skill.name, ""),
-                    ("TAG", skill.tag, ""),
-                    ("能量", &sp_hit, ""),
-                    ("GIF", "", "<!-- 无技能动画填0 -->"),
-                    ("描述", &skill.desc.replace("\\n", "<br />"), ""),
-                ];
-                let index_str = (index + 1).to_string();
-                for (key, val, comment) in skill_kvs {
-                    phase_text(&mut wiki, phase);
-                    wiki.push_str("技能");
-                    wiki.push_str(&index_str);
-                    wiki.push_str(key);
-                    wiki.push('=');
-                    wiki.push_str(val);
-                    wiki.push_str(comment);
-                }
-            }
-        }
-        wiki.push_str("\n}}{{WIKI底部导航|角色图鉴=展开}}");
-        std::borrow::Cow::Owned(wiki)
+impl<Data: ExcelOutput + format::GameData> Wiki for MonsterConfig<'_, Data> {
+    fn wiki(&self) -> Cow<'static, str> {
+        render_installed(self)
+    }
+
+    /// 页面开头的 {{敌人}}，参数和内置的 BWIKI 模板一致
+    fn wiki_template(&self) -> Option<Template> {
+        crate::wiki::main_template(&self.wiki())
     }
 }
 
@@ -797,6 +807,17 @@ impl<'a, Data: ExcelOutput> FromModel<'a, Data> for MonsterSkillConfig<'a> {
     }
 }
 
+/// 同名技能在不同难度的怪物上参数不同，按等级合并为一段描述
+impl crate::misc::Leveled for MonsterSkillConfig<'_> {
+    fn desc(&self) -> &str {
+        self.desc
+    }
+
+    fn params(&self) -> &[format::Argument<'_>] {
+        &self.params
+    }
+}
+
 #[derive(educe::Educe)]
 #[educe(Clone, Debug)]
 /// 对应一种怪物类型，不同的怪物类型可能是同一个种族（建模头像相同），但是一般数值上会有差异
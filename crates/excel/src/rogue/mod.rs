@@ -2,9 +2,10 @@ pub mod tourn;
 
 use std::{borrow::Cow, num::NonZero};
 
-use base::{Name, Wiki};
+use base::{Name, Template, Wiki};
 pub use model::rogue::RogueBuffCategory;
 
+use crate::wiki::{render_installed, Site, WikiContext};
 use crate::{ExcelOutput, FromModel};
 
 #[derive(educe::Educe)]
@@ -71,6 +72,7 @@ impl<Data: ExcelOutput + format::GameData> Name for RogueBuff<'_, Data> {
     }
 
     fn wiki_name(&self) -> std::borrow::Cow<'_, str> {
+        // 页面标题沿用原先的映射：不换行空格直接删除，而不是像规范形式那样替换为空格
         Cow::Owned(
             self.name()
                 .replace("\u{00A0}", "")
@@ -80,78 +82,116 @@ impl<Data: ExcelOutput + format::GameData> Name for RogueBuff<'_, Data> {
     }
 }
 
+impl<Data: ExcelOutput + format::GameData> RogueBuff<'_, Data> {
+    /// 差分宇宙中同名祝福的效果和强化后效果，和模拟宇宙中一致时返回 None
+    fn divergent_effect(
+        &self,
+        formatter: &mut format::Formatter<Data>,
+    ) -> Option<(String, Option<String>)> {
+        let buff = self.buff.as_ref()?;
+        let tourn = self.game.rogue_tourn_buff_by_name(buff.name)?;
+        // 如果 id 相同，那么描述肯定相同，直接跳过
+        if buff.id == tourn.buff.id {
+            return None;
+        }
+        // 否则判断文案完全相同才继续录入（可能存在升级前相同但是升级后不同，所以需要完整比较）
+        // 比较时用规范纯文本，避免标签、空白上的差异被当成不同的效果
+        let mut normal_form = format::Formatter::new(self.game).normal_form(true);
+        let mut normalize = |buff: Option<&crate::misc::MazeBuff>| {
+            buff.map(|buff| normal_form.format(buff.desc, &buff.params))
+                .unwrap_or_default()
+        };
+        let classic_upgrade = self.game.rogue_maze_buff(self.id);
+        let divergent_upgrade = self.game.rogue_maze_buff(tourn.id);
+        if normalize(Some(buff)) == normalize(Some(&tourn.buff))
+            && normalize(classic_upgrade.get(1)) == normalize(divergent_upgrade.get(1))
+        {
+            return None;
+        }
+        Some((
+            formatter.format(tourn.buff.desc, &tourn.buff.params),
+            divergent_upgrade
+                .get(1)
+                .map(|upgrade| formatter.format(upgrade.desc, &upgrade.params)),
+        ))
+    }
+
+    /// 基础效果、强化后效果，以及两者文案相同时合并成 10%/15% 形式的效果
+    fn effects(
+        &self,
+        formatter: &mut format::Formatter<Data>,
+    ) -> (String, Option<String>, Option<String>) {
+        let levels = self.game.rogue_maze_buff(self.id);
+        let mut effects = levels
+            .iter()
+            .map(|level| formatter.format(level.desc, &level.params));
+        let effect = effects.next().unwrap_or_default();
+        let upgraded_effect = effects.next();
+        let leveled_effect = crate::misc::format_levels(&levels, formatter);
+        (effect, upgraded_effect, leveled_effect)
+    }
+}
+
+/// 模板 rogue_buff.jinja 的上下文，只有 1 级祝福有页面
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RogueBuffContext<'a> {
+    pub name: Cow<'a, str>,
+    /// 稀有度：Common、Rare、Legendary，没有稀有度时为 none
+    pub category: Option<RogueBuffCategory>,
+    /// 命途名称
+    pub path: &'a str,
+    /// 已按 wiki 语法格式化的效果
+    pub effect: String,
+    /// 强化后效果
+    pub upgraded_effect: Option<String>,
+    /// 基础、强化后文案相同时，合并为一段的效果，变化的参数以 10%/15% 形式呈现
+    pub leveled_effect: Option<String>,
+    /// 差分宇宙中效果不同时，差分宇宙中的效果
+    pub divergent_effect: Option<String>,
+    pub divergent_upgraded_effect: Option<String>,
+    /// 差分宇宙中也有同名祝福
+    pub in_divergent: bool,
+}
+
+impl<Data: ExcelOutput + format::GameData> WikiContext for RogueBuff<'_, Data> {
+    const TEMPLATE: &'static str = "rogue_buff";
+    type Context<'c>
+        = RogueBuffContext<'c>
+    where
+        Self: 'c;
+
+    fn wiki_context(&self, site: Site) -> RogueBuffContext<'_> {
+        let mut formatter = site.formatter(self.game);
+        let (effect, upgraded_effect, leveled_effect) = self.effects(&mut formatter);
+        let divergent = self.divergent_effect(&mut formatter);
+        RogueBuffContext {
+            name: self.wiki_name(),
+            category: self.category,
+            // BWIKI 的命途取自「」中的两个字
+            path: match site {
+                Site::Bwiki => &self.r#type.title[3..9],
+                Site::Fandom => self.r#type.text,
+            },
+            effect,
+            upgraded_effect,
+            leveled_effect,
+            in_divergent: self.game.rogue_tourn_buff_by_name(self.name()).is_some(),
+            divergent_effect: divergent.as_ref().map(|(effect, _)| effect.clone()),
+            divergent_upgraded_effect: divergent.and_then(|(_, upgrade)| upgrade),
+        }
+    }
+}
+
 impl<Data: ExcelOutput + format::GameData> Wiki for RogueBuff<'_, Data> {
     fn wiki(&self) -> Cow<'static, str> {
         if self.level != 1 || self.buff.is_none() {
             return Cow::Borrowed("");
         }
-        let buff = self.buff.as_ref().unwrap();
-        let mut formatter = format::Formatter::new(self.game).media_wiki_syntax(true);
-        let mut wiki = String::new();
-        let tourn = self.game.rogue_tourn_buff_by_name(buff.name);
-        wiki.push_str("{{模拟宇宙祝福");
-        wiki.push_str("\n|名称=");
-        wiki.push_str(&self.wiki_name());
-        if let Some(category) = self.category {
-            wiki.push_str("\n|稀有度=");
-            wiki.push_str(&category.wiki());
-        }
-        let path = &self.r#type.title[3..9];
-        wiki.push_str("\n|命途=");
-        wiki.push_str(path);
-        wiki.push_str("\n|模式=模拟宇宙");
-        wiki.push_str(match path {
-            "繁育" => "、寰宇蝗灾、黄金与机械",
-            "智识" => "、黄金与机械",
-            _ => "、寰宇蝗灾、黄金与机械",
-        });
-        if tourn.is_some() {
-            wiki.push_str("、差分宇宙");
-        }
-        wiki.push_str("\n|效果=");
-        let desc = formatter.format(buff.desc, &buff.params);
-        wiki.push_str(&desc);
-        let mut upgrade_desc = String::new();
-        if let Some(upgrade) = self.game.rogue_maze_buff(self.id).get(1) {
-            wiki.push_str("\n|强化后效果=");
-            upgrade_desc = formatter.format(upgrade.desc, &upgrade.params);
-            wiki.push_str(&upgrade_desc);
-        }
-        if let Some(tourn) = self.game.rogue_tourn_buff_by_name(buff.name) {
-            if self.buff.as_ref().map(|buff| buff.id).unwrap_or_default() != tourn.buff.id {
-                // 如果 id 相同，那么描述肯定相同，直接跳过
-                let divergent_desc = formatter.format(tourn.buff.desc, &tourn.buff.params);
-                let divergent_upgrade = self.game.rogue_maze_buff(tourn.id);
-                let divergent_upgrade_desc = divergent_upgrade
-                    .get(1)
-                    .map(|upgrade| formatter.format(upgrade.desc, &upgrade.params))
-                    .unwrap_or_default();
-                // 否则判断文案完全相同才继续录入（可能存在升级前相同但是升级后不同，所以需要完整比较）
-                if desc != divergent_desc || upgrade_desc != divergent_upgrade_desc {
-                    wiki.push_str("\n|差分效果=");
-                    wiki.push_str(&divergent_desc);
-                    if let Some(upgrade) = self.game.rogue_maze_buff(tourn.id).get(1) {
-                        wiki.push_str("\n|差分强化效果=");
-                        wiki.push_str(&formatter.format(upgrade.desc, &upgrade.params));
-                    }
-                }
-            }
-        }
-        wiki.push_str("\n|TAG=");
-        wiki.push_str("\n|实装版本=");
-        wiki.push_str("\n|类型=");
-        wiki.push_str(match self.category {
-            None => "",
-            Some(RogueBuffCategory::Legendary) if self.name().starts_with("命途回响：") => "1",
-            Some(RogueBuffCategory::Legendary) if self.name().starts_with("回响构音：") => "2",
-            Some(RogueBuffCategory::Legendary) if self.name().starts_with("回响交错：") => "3",
-            Some(RogueBuffCategory::Legendary) => "4",
-            Some(RogueBuffCategory::Rare) => "5",
-            Some(RogueBuffCategory::Common) => "6",
-        });
-        wiki.push_str("\n|排序=");
-        wiki.push_str("\n}}");
-        Cow::Owned(wiki)
+        render_installed(self)
+    }
+
+    fn wiki_template(&self) -> Option<Template> {
+        crate::wiki::main_template(&self.wiki())
     }
 }
 
@@ -183,9 +223,12 @@ impl<'a, Data: ExcelOutput> FromModel<'a, Data> for RogueBuffType<'a> {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(educe::Educe)]
+#[educe(Clone, Debug)]
 /// 模拟宇宙奇物
-pub struct RogueMiracle<'a> {
+pub struct RogueMiracle<'a, Data: ?Sized> {
+    #[educe(Debug(ignore))]
+    game: &'a Data,
     pub id: u16,
     /// 1.2 及之前版本的 display 为空
     pub display: RogueMiracleDisplay<'a>,
@@ -197,10 +240,11 @@ pub struct RogueMiracle<'a> {
     pub unlock_handbook: Option<RogueHandbookMiracle<'a>>,
 }
 
-impl<'a, Data: ExcelOutput> FromModel<'a, Data> for RogueMiracle<'a> {
+impl<'a, Data: ExcelOutput> FromModel<'a, Data> for RogueMiracle<'a, Data> {
     type Model = model::rogue::RogueMiracle;
     fn from_model(game: &'a Data, model: &'a Self::Model) -> Self {
         Self {
+            game,
             id: model.miracle_id,
             // 存在一些奇物, 图鉴中展示的是模拟宇宙的效果, 游戏过程中展示的是差分宇宙的效果
             // 这一类奇物主要是差分宇宙新增的奇物和商店相关奇物 (邪恶机械卫星#900和「中等念头」群体机)
@@ -263,7 +307,7 @@ impl<'a, Data: ExcelOutput> FromModel<'a, Data> for RogueMiracle<'a> {
     }
 }
 
-impl Name for RogueMiracle<'_> {
+impl<Data> Name for RogueMiracle<'_, Data> {
     fn name(&self) -> &str {
         self.display.name
     }
@@ -272,6 +316,116 @@ impl Name for RogueMiracle<'_> {
     }
 }
 
+/// 奇物出现的玩法
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, serde::Serialize)]
+pub enum MiracleMode {
+    /// 模拟宇宙
+    Simulated,
+    /// 不可知域
+    Unknowable,
+    /// 差分宇宙
+    Divergent,
+}
+
+impl MiracleMode {
+    /// 各玩法的奇物 ID 互不相关，只能按名称在各玩法的奇物表中查找
+    pub fn of<Data: ExcelOutput>(game: &Data, name: &str) -> Vec<Self> {
+        let mut modes = Vec::new();
+        if game.rogue_miracle_by_name(name).is_some() {
+            modes.push(Self::Simulated);
+        }
+        if game.rogue_magic_miracle_by_name(name).is_some() {
+            modes.push(Self::Unknowable);
+        }
+        if game.rogue_tourn_miracle_by_name(name).is_some() {
+            modes.push(Self::Divergent);
+        }
+        modes
+    }
+}
+
+impl RogueMiracleDisplay<'_> {
+    /// 奇物效果和简述
+    /// 新版本的效果文案在 RogueMiracleEffectDisplay 中，display 中的为空，因此优先使用前者
+    pub(crate) fn format_effect<Data: format::GameData>(
+        &self,
+        effect_display: Option<&RogueMiracleEffectDisplay>,
+        formatter: &mut format::Formatter<Data>,
+    ) -> (String, Option<String>) {
+        match effect_display {
+            Some(effect) if !effect.desc.is_empty() => (
+                formatter.format(effect.desc, &effect.desc_params),
+                Some(effect.simple_desc)
+                    .filter(|desc| !desc.is_empty())
+                    .map(|desc| formatter.format(desc, &effect.desc_params)),
+            ),
+            _ => (formatter.format(self.desc, &self.desc_params), None),
+        }
+    }
+}
+
+/// 模板 rogue_miracle.jinja 的上下文
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RogueMiracleContext<'a> {
+    pub name: Cow<'a, str>,
+    /// 已按 wiki 语法格式化的效果
+    pub effect: String,
+    /// 效果简述，旧版本没有
+    pub simple_effect: Option<String>,
+    /// 背景故事
+    pub story: String,
+    /// 图鉴中所属的 DLC，如「寰宇蝗灾」「黄金与机械」
+    /// 没有图鉴的奇物（如分裂咕咕钟的各个变体）为空
+    pub handbook_types: Vec<&'a str>,
+    /// 图鉴解锁条件，模拟宇宙的奇物图鉴没有解锁条件文案，取差分宇宙同名奇物图鉴的
+    pub unlock: Option<&'a str>,
+    /// 出现在哪些玩法中
+    pub modes: Vec<MiracleMode>,
+}
+
+impl<Data: ExcelOutput + format::GameData> WikiContext for RogueMiracle<'_, Data> {
+    const TEMPLATE: &'static str = "rogue_miracle";
+    type Context<'c>
+        = RogueMiracleContext<'c>
+    where
+        Self: 'c;
+
+    fn wiki_context(&self, site: Site) -> RogueMiracleContext<'_> {
+        let mut formatter = site.formatter(self.game);
+        let (effect, simple_effect) = self
+            .display
+            .format_effect(self.effect_display.as_ref(), &mut formatter);
+        RogueMiracleContext {
+            name: self.wiki_name(),
+            effect,
+            simple_effect,
+            story: formatter.format(self.display.bg_desc, &[]),
+            handbook_types: self
+                .unlock_handbook
+                .iter()
+                .flat_map(|handbook| &handbook.type_list)
+                .map(|typ| typ.title)
+                .collect(),
+            unlock: self
+                .game
+                .rogue_tourn_miracle_by_name(self.name())
+                .and_then(|miracle| miracle.handbook)
+                .map(|handbook| handbook.unlock_desc.content),
+            modes: MiracleMode::of(self.game, self.name()),
+        }
+    }
+}
+
+impl<Data: ExcelOutput + format::GameData> Wiki for RogueMiracle<'_, Data> {
+    fn wiki(&self) -> Cow<'static, str> {
+        render_installed(self)
+    }
+
+    fn wiki_template(&self) -> Option<Template> {
+        crate::wiki::main_template(&self.wiki())
+    }
+}
+
 #[derive(Clone, Debug)]
 /// 模拟宇宙奇物展示数据（效果、背景故事等）
 pub struct RogueMiracleEffectDisplay<'a> {
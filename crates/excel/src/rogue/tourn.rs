@@ -1,6 +1,7 @@
+use crate::wiki::{render_installed, Site, WikiContext};
 use crate::{ExcelOutput, FromModel};
 
-use base::{Name, Wiki};
+use base::{Name, Template, Wiki};
 pub use model::rogue::tourn::{
     BlessBattleDisplayCategory, FormulaCategory, MiracleCategory, TitanType, TournMode,
 };
@@ -88,46 +89,58 @@ impl<Data: ExcelOutput + format::GameData> Name for RogueTournBuff<'_, Data> {
 
 impl<Data: ExcelOutput + format::GameData> Wiki for RogueTournBuff<'_, Data> {
     fn wiki(&self) -> std::borrow::Cow<'static, str> {
+        self.wiki_template().map(Cow::from).unwrap_or_default()
+    }
+
+    fn wiki_template(&self) -> Option<Template> {
         if self.level != 1 {
-            return Cow::Borrowed("");
+            return None;
         }
         let classic = self.game.rogue_buff_by_name(self.buff.name);
         if classic.is_some() {
             // 存在模拟宇宙祝福的情况，我们会在模拟宇宙祝福的 Wiki impl 中拿到完整数据
-            return Cow::Borrowed("");
+            return None;
         }
-        let mut wiki = String::from("{{模拟宇宙祝福");
+        let mut template = Template::new("模拟宇宙祝福");
         let mut formatter = format::Formatter::new(self.game).media_wiki_syntax(true);
-        wiki.push_str("\n|名称=");
-        wiki.push_str(&self.wiki_name());
+        template.insert("名称", self.wiki_name());
         if let Some(category) = self.category {
-            wiki.push_str("\n|稀有度=");
-            wiki.push_str(&category.wiki());
+            template.insert("稀有度", category.wiki());
         }
-        wiki.push_str("\n|命途=");
-        wiki.push_str(self.r#type.name);
-        wiki.push_str("\n|模式=差分宇宙");
-        wiki.push_str("\n|差分效果=");
-        wiki.push_str(&formatter.format(self.buff.desc, &self.buff.params));
+        template.insert("命途", self.r#type.name);
+        template.insert("模式", "差分宇宙");
+        template.insert(
+            "差分效果",
+            formatter.format(self.buff.desc, &self.buff.params),
+        );
         if let Some(upgrade) = self.game.rogue_maze_buff(self.id).get(1) {
-            wiki.push_str("\n|差分强化效果=");
-            wiki.push_str(&formatter.format(upgrade.desc, &upgrade.params));
+            template.insert(
+                "差分强化效果",
+                formatter.format(upgrade.desc, &upgrade.params),
+            );
         }
-        wiki.push_str("\n|TAG=");
-        wiki.push_str("\n|实装版本=");
-        wiki.push_str("\n|类型=");
-        wiki.push_str(match self.category {
-            None => "",
-            Some(RogueBuffCategory::Legendary) if self.name().starts_with("命途回响：") => "1",
-            Some(RogueBuffCategory::Legendary) if self.name().starts_with("回响构音：") => "2",
-            Some(RogueBuffCategory::Legendary) if self.name().starts_with("回响交错：") => "3",
-            Some(RogueBuffCategory::Legendary) => "4",
-            Some(RogueBuffCategory::Rare) => "5",
-            Some(RogueBuffCategory::Common) => "6",
-        });
-        wiki.push_str("\n|排序=");
-        wiki.push_str("\n}}");
-        Cow::Owned(wiki)
+        template.insert("TAG", "");
+        template.insert("实装版本", "");
+        template.insert(
+            "类型",
+            match self.category {
+                None => "",
+                Some(RogueBuffCategory::Legendary) if self.name().starts_with("命途回响：") => {
+                    "1"
+                }
+                Some(RogueBuffCategory::Legendary) if self.name().starts_with("回响构音：") => {
+                    "2"
+                }
+                Some(RogueBuffCategory::Legendary) if self.name().starts_with("回响交错：") => {
+                    "3"
+                }
+                Some(RogueBuffCategory::Legendary) => "4",
+                Some(RogueBuffCategory::Rare) => "5",
+                Some(RogueBuffCategory::Common) => "6",
+            },
+        );
+        template.insert("排序", "");
+        Some(template)
     }
 }
 
@@ -193,9 +206,9 @@ pub struct RogueTournWeeklyChallenge<'a, Data: ?Sized> {
     /// 从 .content 成员中提取出来的本周所有预设构筑方程
     pub formula: Vec<RogueTournFormula<'a, Data>>,
     /// 从 .content 成员中提取出来的本周所有预设构筑奇物
-    pub miracle: Vec<RogueTournMiracle<'a>>,
+    pub miracle: Vec<RogueTournMiracle<'a, Data>>,
     /// 从 .content 成员中提取出来的本周所有预设构筑金血祝颂
-    pub titan_blessing: Vec<RogueTournTitanBless<'a>>,
+    pub titan_blessing: Vec<RogueTournTitanBless<'a, Data>>,
     /// 第一位面首领
     pub monster_group_1: Vec<(u8, crate::rogue::RogueMonsterGroup<'a>)>,
     /// 第二位面首领
@@ -336,7 +349,7 @@ impl<Data: ExcelOutput + format::GameData> Wiki for RogueTournWeeklyChallenge<'_
             let blessings = self
                 .titan_blessing
                 .iter()
-                .map(|blessing| blessing.maze_buff[0].name)
+                .map(RogueTournTitanBless::name)
                 .intersperse(",")
                 .collect::<String>();
             wiki.push_str("\n|起始金血祝颂=");
@@ -416,13 +429,121 @@ impl<Data: ExcelOutput + format::GameData> Wiki for RogueTournWeeklyChallenge<'_
     }
 }
 
+/// 差分宇宙周期演算的全部期数，用于生成汇总页面
+#[derive(educe::Educe)]
+#[educe(Clone, Debug)]
+pub struct RogueTournWeeklySeason<'a, Data: ?Sized> {
+    /// 按期数排序
+    pub challenges: Vec<RogueTournWeeklyChallenge<'a, Data>>,
+}
+
+impl<'a, Data: ExcelOutput> RogueTournWeeklySeason<'a, Data> {
+    pub fn new(game: &'a Data) -> Self {
+        let mut challenges = game.list_rogue_tourn_weekly_challenge().collect::<Vec<_>>();
+        challenges.sort_by_key(|challenge| challenge.id);
+        Self { challenges }
+    }
+}
+
+/// 模板 rogue_tourn_weekly_season.jinja 的上下文
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RogueTournWeeklySeasonContext<'a> {
+    pub issues: Vec<RogueTournWeeklyIssueContext<'a>>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RogueTournWeeklyIssueContext<'a> {
+    pub issue: u16,
+    pub name: &'a str,
+    /// 格式为 2024/06/19
+    pub begin_date: String,
+    /// 结束当天的日期，格式同上
+    pub end_date: String,
+    /// 起始方程的页面名
+    pub formulas: Vec<Cow<'a, str>>,
+    /// 起始奇物的页面名
+    pub miracles: Vec<Cow<'a, str>>,
+    /// 起始金血祝颂
+    pub blessings: Vec<&'a str>,
+    /// 开拓祝福
+    pub bonus: Option<RogueTournBonusContext<'a>>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RogueTournBonusContext<'a> {
+    pub title: &'a str,
+    pub desc: &'a str,
+}
+
+impl<Data: ExcelOutput + format::GameData> WikiContext for RogueTournWeeklySeason<'_, Data> {
+    const TEMPLATE: &'static str = "rogue_tourn_weekly_season";
+    type Context<'c>
+        = RogueTournWeeklySeasonContext<'c>
+    where
+        Self: 'c;
+
+    fn wiki_context(&self, site: Site) -> RogueTournWeeklySeasonContext<'_> {
+        let issues = self
+            .challenges
+            .iter()
+            .map(|challenge| RogueTournWeeklyIssueContext {
+                issue: challenge.issue(),
+                name: challenge.name,
+                begin_date: challenge.begin_time().format("%Y/%m/%d").to_string(),
+                end_date: (challenge.end_time().date_naive().pred_opt().unwrap())
+                    .format("%Y/%m/%d")
+                    .to_string(),
+                formulas: challenge
+                    .formula
+                    .iter()
+                    .map(|formula| match site {
+                        Site::Bwiki => formula.wiki_name(),
+                        Site::Fandom => Cow::Borrowed(formula.name()),
+                    })
+                    .collect(),
+                miracles: challenge
+                    .miracle
+                    .iter()
+                    .map(|miracle| match site {
+                        Site::Bwiki => miracle.wiki_name(),
+                        Site::Fandom => Cow::Borrowed(miracle.name()),
+                    })
+                    .collect(),
+                blessings: challenge
+                    .titan_blessing
+                    .iter()
+                    .map(RogueTournTitanBless::name)
+                    .collect(),
+                bonus: challenge
+                    .bonus()
+                    .as_ref()
+                    .map(|bonus| RogueTournBonusContext {
+                        title: bonus.title,
+                        desc: bonus.desc,
+                    }),
+            })
+            .collect();
+        RogueTournWeeklySeasonContext { issues }
+    }
+}
+
+impl<Data: ExcelOutput + format::GameData> Wiki for RogueTournWeeklySeason<'_, Data> {
+    fn wiki(&self) -> Cow<'static, str> {
+        render_installed(self)
+    }
+
+    fn wiki_template(&self) -> Option<Template> {
+        crate::wiki::main_template(&self.wiki())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct RogueTournWeeklyDisplay<'a, Data: ?Sized> {
     pub id: u16,
     pub content: &'a str,
     pub formula: Vec<RogueTournFormula<'a, Data>>,
-    pub miracle: Vec<RogueTournMiracle<'a>>,
-    pub titan_bless: Vec<RogueTournTitanBless<'a>>,
+    pub miracle: Vec<RogueTournMiracle<'a, Data>>,
+    pub titan_bless: Vec<RogueTournTitanBless<'a, Data>>,
 }
 
 impl<'a, Data: ExcelOutput> FromModel<'a, Data> for RogueTournWeeklyDisplay<'a, Data> {
@@ -461,9 +582,12 @@ impl<'a, Data: ExcelOutput> FromModel<'a, Data> for RogueTournWeeklyDisplay<'a,
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(educe::Educe)]
+#[educe(Clone, Debug)]
 /// 差分宇宙奇物
-pub struct RogueTournMiracle<'a> {
+pub struct RogueTournMiracle<'a, Data: ?Sized> {
+    #[educe(Debug(ignore))]
+    game: &'a Data,
     pub id: u16,
     pub mode: TournMode,
     /// 奇物星级：加权、三星、二星、一星、负面
@@ -475,10 +599,11 @@ pub struct RogueTournMiracle<'a> {
     pub handbook: Option<RogueTournHandbookMiracle<'a>>,
 }
 
-impl<'a, Data: ExcelOutput> FromModel<'a, Data> for RogueTournMiracle<'a> {
+impl<'a, Data: ExcelOutput> FromModel<'a, Data> for RogueTournMiracle<'a, Data> {
     type Model = model::rogue::tourn::RogueTournMiracle;
     fn from_model(game: &'a Data, model: &Self::Model) -> Self {
         Self {
+            game,
             id: model.miracle_id,
             mode: model.tourn_mode,
             category: model.miracle_category,
@@ -500,7 +625,7 @@ impl<'a, Data: ExcelOutput> FromModel<'a, Data> for RogueTournMiracle<'a> {
     }
 }
 
-impl Name for RogueTournMiracle<'_> {
+impl<Data> Name for RogueTournMiracle<'_, Data> {
     fn name(&self) -> &str {
         self.display.name
     }
@@ -513,6 +638,65 @@ impl Name for RogueTournMiracle<'_> {
     }
 }
 
+/// 模板 rogue_tourn_miracle.jinja 的上下文
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RogueTournMiracleContext<'a> {
+    /// 页面名，BWIKI 见 Name::wiki_name
+    pub name: Cow<'a, str>,
+    /// 稀有度：Common、Rare、Legendary、Hex、Negative
+    pub category: MiracleCategory,
+    /// 已按 wiki 语法格式化的效果
+    pub effect: String,
+    /// 效果简述
+    pub simple_effect: Option<String>,
+    /// 背景故事
+    pub story: String,
+    /// 图鉴中未解锁时的提示，不在图鉴中的奇物为 none
+    pub unlock: Option<&'a str>,
+    /// 出现在哪些玩法中
+    pub modes: Vec<crate::rogue::MiracleMode>,
+}
+
+impl<Data: ExcelOutput + format::GameData> WikiContext for RogueTournMiracle<'_, Data> {
+    const TEMPLATE: &'static str = "rogue_tourn_miracle";
+    type Context<'c>
+        = RogueTournMiracleContext<'c>
+    where
+        Self: 'c;
+
+    fn wiki_context(&self, site: Site) -> RogueTournMiracleContext<'_> {
+        let mut formatter = site.formatter(self.game);
+        let (effect, simple_effect) = self
+            .display
+            .format_effect(self.effect_display.as_ref(), &mut formatter);
+        RogueTournMiracleContext {
+            name: match site {
+                Site::Bwiki => self.wiki_name(),
+                Site::Fandom => Cow::Borrowed(self.name()),
+            },
+            category: self.category,
+            effect,
+            simple_effect,
+            story: formatter.format(self.display.bg_desc, &[]),
+            unlock: self
+                .handbook
+                .as_ref()
+                .map(|handbook| handbook.unlock_desc.content),
+            modes: crate::rogue::MiracleMode::of(self.game, self.name()),
+        }
+    }
+}
+
+impl<Data: ExcelOutput + format::GameData> Wiki for RogueTournMiracle<'_, Data> {
+    fn wiki(&self) -> Cow<'static, str> {
+        render_installed(self)
+    }
+
+    fn wiki_template(&self) -> Option<Template> {
+        crate::wiki::main_template(&self.wiki())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct RogueTournHandbookMiracle<'a> {
     pub id: u16,
@@ -632,67 +816,98 @@ impl<Data: ExcelOutput> Name for RogueTournFormula<'_, Data> {
     }
 }
 
-impl<Data: ExcelOutput + format::GameData> Wiki for RogueTournFormula<'_, Data> {
-    fn wiki(&self) -> Cow<'static, str> {
-        let mut formatter = format::Formatter::new(self.game).media_wiki_syntax(true);
-        let mut wiki = String::new();
-        wiki.push_str("{{差分宇宙方程");
-        wiki.push_str("\n|名称=");
-        wiki.push_str(&self.wiki_name());
-        wiki.push_str("\n|稀有度=");
-        wiki.push_str(match self.category {
-            FormulaCategory::Epic => "2星",
-            FormulaCategory::Legendary => "3星",
-            FormulaCategory::PathEcho => "4星",
-            FormulaCategory::Rare => "1星",
-        });
-        wiki.push_str("\n|命途1=");
-        wiki.push_str(self.main_buff_type.name);
-        wiki.push_str("\n|命途1需求=");
-        wiki.push_str(&self.main_buff_num.to_string());
-        if let Some(buff) = &self.sub_buff_type {
-            wiki.push_str("\n|命途2=");
-            wiki.push_str(buff.name);
-            wiki.push_str("\n|命途2需求=");
-            wiki.push_str(&self.sub_buff_num.to_string());
-        }
-        wiki.push_str("\n|实装版本=");
-        wiki.push_str("\n|效果=");
-        wiki.push_str(&formatter.format(self.maze_buff.desc, &self.maze_buff.params));
-        wiki.push_str("\n演绎=");
-        wiki.push_str(&formatter.format(self.display.story, &[]));
-        if let Some(story) = &self.story {
+/// 模板 rogue_tourn_formula.jinja 的上下文
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RogueTournFormulaContext<'a> {
+    /// 页面名，BWIKI 见 Name::wiki_name
+    pub name: Cow<'a, str>,
+    /// 稀有度：Rare、Epic、Legendary、PathEcho
+    pub category: FormulaCategory,
+    /// 主要命途名称
+    pub main_path: &'a str,
+    pub main_path_count: u8,
+    /// 次要命途名称，临界方程为 none
+    pub sub_path: Option<&'a str>,
+    pub sub_path_count: u8,
+    /// 已按 wiki 语法格式化的效果，有多个等级时为数值递进形式
+    pub effect: String,
+    /// 背景故事
+    pub story: String,
+    /// 首次展开时的推演剧情，只有临界方程和三星方程有
+    pub deduction: Option<Vec<DeductionContext<'a>>>,
+}
+
+/// 推演剧情中的一句
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(tag = "type")]
+pub enum DeductionContext<'a> {
+    /// 对话，name 为说话人
+    Talk { name: &'a str, text: &'a str },
+    /// 选项，方程推演中选项之后没有分支
+    Options { options: Vec<&'a str> },
+}
+
+impl<Data: ExcelOutput + format::GameData> WikiContext for RogueTournFormula<'_, Data> {
+    const TEMPLATE: &'static str = "rogue_tourn_formula";
+    type Context<'c>
+        = RogueTournFormulaContext<'c>
+    where
+        Self: 'c;
+
+    fn wiki_context(&self, site: Site) -> RogueTournFormulaContext<'_> {
+        let mut formatter = site.formatter(self.game);
+        let levels = self.game.rogue_maze_buff(self.maze_buff.id);
+        let effect = crate::misc::format_levels(&levels, &mut formatter)
+            .unwrap_or_else(|| formatter.format(self.maze_buff.desc, &self.maze_buff.params));
+        let deduction = self.story.as_ref().map(|story| {
             use crate::story::Task;
             assert!(story.on_init_sequence.is_empty());
-            wiki.push_str("\n推演=\n");
+            let mut deduction = Vec::new();
             for seq in &story.on_start_sequence {
                 for task in &seq.task_list {
                     if let Task::PlayAndWaitRogueSimpleTalk { simple_talk_list } = task {
-                        for talk in simple_talk_list {
-                            wiki.push_str("  {{事件|");
-                            wiki.push_str(talk.sentence.name);
-                            wiki.push('|');
-                            wiki.push_str(talk.sentence.text);
-                            wiki.push_str("}}\n");
-                        }
+                        deduction.extend(simple_talk_list.iter().map(|talk| {
+                            DeductionContext::Talk {
+                                name: talk.sentence.name,
+                                text: talk.sentence.text,
+                            }
+                        }));
                     }
                     if let Task::PlayRogueOptionTalk { option_list } = task {
-                        // 按说选项之后应该要有分支，但是差分宇宙方程里就是没有分支，因此先不管它了
-                        wiki.push_str("  {{剧情选项");
-                        for (index, option) in option_list.iter().enumerate() {
-                            let index = index + 1;
-                            wiki.push_str("|选项");
-                            wiki.push_str(&index.to_string());
-                            wiki.push('=');
-                            wiki.push_str(option.sentence.as_ref().unwrap().text);
-                        }
-                        wiki.push_str("}}\n");
+                        let options = option_list
+                            .iter()
+                            .map(|option| option.sentence.as_ref().unwrap().text)
+                            .collect();
+                        deduction.push(DeductionContext::Options { options });
                     }
                 }
             }
+            deduction
+        });
+        RogueTournFormulaContext {
+            name: match site {
+                Site::Bwiki => self.wiki_name(),
+                Site::Fandom => Cow::Borrowed(self.name()),
+            },
+            category: self.category,
+            main_path: self.main_buff_type.name,
+            main_path_count: self.main_buff_num,
+            sub_path: self.sub_buff_type.as_ref().map(|buff| buff.name),
+            sub_path_count: self.sub_buff_num,
+            effect,
+            story: formatter.format(self.display.story, &[]),
+            deduction,
         }
-        wiki.push_str("\n}}");
-        Cow::Owned(wiki)
+    }
+}
+
+impl<Data: ExcelOutput + format::GameData> Wiki for RogueTournFormula<'_, Data> {
+    fn wiki(&self) -> Cow<'static, str> {
+        render_installed(self)
+    }
+
+    fn wiki_template(&self) -> Option<Template> {
+        crate::wiki::main_template(&self.wiki())
     }
 }
 
@@ -721,21 +936,27 @@ impl<'a, Data: ExcelOutput> FromModel<'a, Data> for RogueTournFormulaDisplay<'a>
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct RogueTournTitanBless<'a> {
+#[derive(educe::Educe)]
+#[educe(Clone, Debug)]
+/// 差分宇宙金血祝颂
+pub struct RogueTournTitanBless<'a, Data: ?Sized> {
+    #[educe(Debug(ignore))]
+    game: &'a Data,
     pub id: u16,
     pub r#type: TitanType,
     pub level: u8,
     pub maze_buff: Vec<crate::misc::MazeBuff<'a>>,
     pub extra_effect: Vec<crate::misc::ExtraEffectConfig<'a>>,
     pub ratio: i8,
+    /// 在战斗中的昼、夜哪种状态下展示
     pub battle_display_category_list: &'a [BlessBattleDisplayCategory],
 }
 
-impl<'a, Data: ExcelOutput> FromModel<'a, Data> for RogueTournTitanBless<'a> {
+impl<'a, Data: ExcelOutput> FromModel<'a, Data> for RogueTournTitanBless<'a, Data> {
     type Model = model::rogue::tourn::RogueTournTitanBless;
     fn from_model(game: &'a Data, model: &'a Self::Model) -> Self {
         Self {
+            game,
             id: model.titan_bless_id,
             r#type: model.titan_type,
             level: model.titan_bless_level,
@@ -751,3 +972,73 @@ impl<'a, Data: ExcelOutput> FromModel<'a, Data> for RogueTournTitanBless<'a> {
         }
     }
 }
+
+impl<Data> Name for RogueTournTitanBless<'_, Data> {
+    fn name(&self) -> &str {
+        self.maze_buff[0].name
+    }
+    fn wiki_name(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self.name())
+    }
+}
+
+/// 模板 rogue_tourn_titan_bless.jinja 的上下文
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RogueTournTitanBlessContext<'a> {
+    pub name: &'a str,
+    /// 所属泰坦：Ianos、Moneta、Nikadory、Phageina、Xenatos、Zagreus
+    pub titan: TitanType,
+    pub level: u8,
+    /// 已按 wiki 语法格式化的效果，有多个等级时为数值递进形式
+    pub effect: String,
+    /// 效果中提到的名词及其说明
+    pub extra_effects: Vec<RogueTournExtraEffectContext<'a>>,
+    /// Day、Night
+    pub battle_display: &'a [BlessBattleDisplayCategory],
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RogueTournExtraEffectContext<'a> {
+    pub name: &'a str,
+    pub desc: String,
+}
+
+impl<Data: ExcelOutput + format::GameData> WikiContext for RogueTournTitanBless<'_, Data> {
+    const TEMPLATE: &'static str = "rogue_tourn_titan_bless";
+    type Context<'c>
+        = RogueTournTitanBlessContext<'c>
+    where
+        Self: 'c;
+
+    fn wiki_context(&self, site: Site) -> RogueTournTitanBlessContext<'_> {
+        let mut formatter = site.formatter(self.game);
+        let first = &self.maze_buff[0];
+        let effect = crate::misc::format_levels(&self.maze_buff, &mut formatter)
+            .unwrap_or_else(|| formatter.format(first.desc, &first.params));
+        RogueTournTitanBlessContext {
+            name: self.name(),
+            titan: self.r#type,
+            level: self.level,
+            effect,
+            extra_effects: self
+                .extra_effect
+                .iter()
+                .map(|effect| RogueTournExtraEffectContext {
+                    name: effect.name,
+                    desc: formatter.format(effect.desc, &effect.desc_params),
+                })
+                .collect(),
+            battle_display: self.battle_display_category_list,
+        }
+    }
+}
+
+impl<Data: ExcelOutput + format::GameData> Wiki for RogueTournTitanBless<'_, Data> {
+    fn wiki(&self) -> Cow<'static, str> {
+        render_installed(self)
+    }
+
+    fn wiki_template(&self) -> Option<Template> {
+        crate::wiki::main_template(&self.wiki())
+    }
+}
@@ -0,0 +1,1448 @@
+//! 用模板文件描述数据到 wiki 页面的映射
+//!
+//! wiki 上的模板名、参数名经常改动，写死在代码里每次都要改代码重新编译
+//! 因此实现了 WikiContext 的类型只负责整理出上下文，页面长什么样由模板文件决定
+//!
+//! 模板使用 [minijinja](https://docs.rs/minijinja) 语法，但是因为 wikitext 里到处都是 `{{`，
+//! 所以分隔符改成了：变量 `<< name >>`，语句 `<% if ... %>`，注释 `<# ... #>`
+//! 各类型的上下文字段见对应的 `*Context` 结构体文档
+//!
+//! 内置两套模板：中文的 BWIKI 和英文的 Fandom，Fandom 模板需要搭配英文文本的数据使用，
+//! 即 `data::GameData::with_language(base, data::Language::EN)`
+//! 两个站点的描述文本语法、枚举值的显示名称不同，由 [`Site`] 区分，上下文的结构是一致的
+//!
+//! 各类型的 `Wiki::wiki` 使用 [`WikiTemplates::installed`] 渲染，默认为内置的 BWIKI 模板，
+//! 用 [`WikiTemplates::load_dir`] 覆盖模板后调用 [`WikiTemplates::install`] 即可替换。
+//! `Wiki::wiki` 不能返回错误，模板出错时会 panic，需要处理错误时直接调用 [`WikiTemplates::render`]
+//!
+//! 页面中出现了名词（ExtraEffectConfig）时，末尾会接上 `glossary` 模板渲染的名词列表，
+//! 上下文见 [`GlossaryContext`]，不需要时把 `glossary` 覆盖为空模板即可
+
+use std::borrow::Cow;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use model::monster::DebuffResistKey;
+use model::Element;
+
+/// 可以用模板渲染成 wiki 页面的类型
+pub trait WikiContext {
+    /// 模板名，对应模板目录下的 `<TEMPLATE>.jinja` 文件
+    const TEMPLATE: &'static str;
+    /// 传给模板的上下文
+    type Context<'c>: serde::Serialize
+    where
+        Self: 'c;
+    fn wiki_context(&self, site: Site) -> Self::Context<'_>;
+}
+
+/// 页面的目标站点
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Site {
+    /// 哔哩哔哩 BWIKI
+    #[default]
+    Bwiki,
+    /// Fandom 英文 wiki
+    Fandom,
+}
+
+impl Site {
+    /// 格式化描述文本用的 Formatter，BWIKI 依赖站内的颜色、注音等模板，Fandom 使用 HTML 标签
+    pub fn formatter<Data: format::GameData>(self, game: &Data) -> format::Formatter<'_, Data> {
+        format::Formatter::new(game)
+            .media_wiki_syntax(self == Self::Bwiki)
+            .fandom_syntax(self == Self::Fandom)
+    }
+
+    pub fn element(self, element: Element) -> Cow<'static, str> {
+        use base::Wiki;
+        match self {
+            Self::Bwiki => element.wiki(),
+            Self::Fandom => Cow::Borrowed(match element {
+                Element::Fire => "Fire",
+                Element::Ice => "Ice",
+                Element::Imaginary => "Imaginary",
+                Element::Physical => "Physical",
+                Element::Quantum => "Quantum",
+                Element::Thunder => "Lightning",
+                Element::Wind => "Wind",
+            }),
+        }
+    }
+
+    pub fn debuff_resist(self, key: DebuffResistKey) -> Cow<'static, str> {
+        use base::Wiki;
+        match self {
+            Self::Bwiki => key.wiki(),
+            Self::Fandom => Cow::Borrowed(match key {
+                DebuffResistKey::Confine => "Imprisonment RES",
+                DebuffResistKey::Ctrl => "Crowd Control RES",
+                DebuffResistKey::Frozen => "Frozen RES",
+                DebuffResistKey::Burn => "Burn RES",
+                DebuffResistKey::Electric => "Shock RES",
+                DebuffResistKey::Poison => "Wind Shear RES",
+                DebuffResistKey::Entangle => "Entanglement RES",
+            }),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::path::PathBuf, std::io::Error),
+    Template(minijinja::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "读取模板 {} 失败：{err}", path.display()),
+            Self::Template(err) => write!(f, "模板错误：{err:#}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Template(err) => Some(err),
+        }
+    }
+}
+
+impl From<minijinja::Error> for Error {
+    fn from(err: minijinja::Error) -> Self {
+        Self::Template(err)
+    }
+}
+
+/// 内置的 BWIKI 模板
+const BWIKI: &[(&str, &str)] = &[
+    (
+        "book_series",
+        include_str!("../templates/bwiki/book_series.jinja"),
+    ),
+    (
+        "challenge_boss",
+        include_str!("../templates/bwiki/challenge_boss.jinja"),
+    ),
+    (
+        "challenge_group",
+        include_str!("../templates/bwiki/challenge_group.jinja"),
+    ),
+    (
+        "challenge_memory",
+        include_str!("../templates/bwiki/challenge_memory.jinja"),
+    ),
+    (
+        "challenge_story",
+        include_str!("../templates/bwiki/challenge_story.jinja"),
+    ),
+    (
+        "glossary",
+        include_str!("../templates/bwiki/glossary.jinja"),
+    ),
+    (
+        "main_mission",
+        include_str!("../templates/bwiki/main_mission.jinja"),
+    ),
+    (
+        "message_section",
+        include_str!("../templates/bwiki/message_section.jinja"),
+    ),
+    ("monster", include_str!("../templates/bwiki/monster.jinja")),
+    (
+        "monster_guide",
+        include_str!("../templates/bwiki/monster_guide.jinja"),
+    ),
+    (
+        "rogue_buff",
+        include_str!("../templates/bwiki/rogue_buff.jinja"),
+    ),
+    (
+        "rogue_miracle",
+        include_str!("../templates/bwiki/rogue_miracle.jinja"),
+    ),
+    (
+        "rogue_tourn_formula",
+        include_str!("../templates/bwiki/rogue_tourn_formula.jinja"),
+    ),
+    (
+        "rogue_tourn_miracle",
+        include_str!("../templates/bwiki/rogue_tourn_miracle.jinja"),
+    ),
+    (
+        "rogue_tourn_titan_bless",
+        include_str!("../templates/bwiki/rogue_tourn_titan_bless.jinja"),
+    ),
+    (
+        "rogue_tourn_weekly_season",
+        include_str!("../templates/bwiki/rogue_tourn_weekly_season.jinja"),
+    ),
+];
+
+/// 内置的 Fandom 模板
+const FANDOM: &[(&str, &str)] = &[
+    (
+        "book_series",
+        include_str!("../templates/fandom/book_series.jinja"),
+    ),
+    (
+        "challenge_boss",
+        include_str!("../templates/fandom/challenge_boss.jinja"),
+    ),
+    (
+        "challenge_group",
+        include_str!("../templates/fandom/challenge_group.jinja"),
+    ),
+    (
+        "challenge_memory",
+        include_str!("../templates/fandom/challenge_memory.jinja"),
+    ),
+    (
+        "challenge_story",
+        include_str!("../templates/fandom/challenge_story.jinja"),
+    ),
+    (
+        "glossary",
+        include_str!("../templates/fandom/glossary.jinja"),
+    ),
+    (
+        "main_mission",
+        include_str!("../templates/fandom/main_mission.jinja"),
+    ),
+    (
+        "message_section",
+        include_str!("../templates/fandom/message_section.jinja"),
+    ),
+    ("monster", include_str!("../templates/fandom/monster.jinja")),
+    (
+        "monster_guide",
+        include_str!("../templates/fandom/monster_guide.jinja"),
+    ),
+    (
+        "rogue_buff",
+        include_str!("../templates/fandom/rogue_buff.jinja"),
+    ),
+    (
+        "rogue_miracle",
+        include_str!("../templates/fandom/rogue_miracle.jinja"),
+    ),
+    (
+        "rogue_tourn_formula",
+        include_str!("../templates/fandom/rogue_tourn_formula.jinja"),
+    ),
+    (
+        "rogue_tourn_miracle",
+        include_str!("../templates/fandom/rogue_tourn_miracle.jinja"),
+    ),
+    (
+        "rogue_tourn_titan_bless",
+        include_str!("../templates/fandom/rogue_tourn_titan_bless.jinja"),
+    ),
+    (
+        "rogue_tourn_weekly_season",
+        include_str!("../templates/fandom/rogue_tourn_weekly_season.jinja"),
+    ),
+];
+
+/// 一套页面模板，同名模板后加载的覆盖先加载的
+pub struct WikiTemplates {
+    env: minijinja::Environment<'static>,
+    site: Site,
+}
+
+impl WikiTemplates {
+    /// 不含任何模板，目标站点为 BWIKI
+    pub fn empty() -> Self {
+        Self::empty_for(Site::Bwiki)
+    }
+
+    /// 不含任何模板
+    pub fn empty_for(site: Site) -> Self {
+        let mut env = minijinja::Environment::new();
+        let syntax = minijinja::syntax::SyntaxConfig::builder()
+            .block_delimiters("<%", "%>")
+            .variable_delimiters("<<", ">>")
+            .comment_delimiters("<#", "#>")
+            .build()
+            .unwrap();
+        env.set_syntax(syntax);
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+        // 允许 if 判断不存在的字段，但是输出不存在的字段时报错，方便发现模板中的拼写错误
+        env.set_undefined_behavior(minijinja::UndefinedBehavior::SemiStrict);
+        Self { env, site }
+    }
+
+    /// 内置的 BWIKI 模板
+    pub fn bwiki() -> Self {
+        let mut templates = Self::empty();
+        for (name, source) in BWIKI {
+            templates.add_template(*name, *source).unwrap();
+        }
+        templates
+    }
+
+    /// 内置的 Fandom 模板
+    pub fn fandom() -> Self {
+        let mut templates = Self::empty_for(Site::Fandom);
+        for (name, source) in FANDOM {
+            templates.add_template(*name, *source).unwrap();
+        }
+        templates
+    }
+
+    pub fn site(&self) -> Site {
+        self.site
+    }
+
+    /// 各类型 Wiki 实现默认使用的模板，只在第一次使用时解析
+    fn builtin() -> &'static Arc<Self> {
+        static BUILTIN: OnceLock<Arc<WikiTemplates>> = OnceLock::new();
+        BUILTIN.get_or_init(|| Arc::new(Self::bwiki()))
+    }
+
+    /// 替换各类型 Wiki 实现使用的模板，之后的 Wiki::wiki 都用这套模板渲染
+    pub fn install(self) {
+        INSTALLED.set(self);
+    }
+
+    /// 各类型 Wiki 实现当前使用的模板，没有调用过 install 时为内置的 BWIKI 模板
+    pub fn installed() -> Arc<Self> {
+        INSTALLED.get()
+    }
+
+    /// 添加或覆盖单个模板
+    pub fn add_template(
+        &mut self,
+        name: impl Into<String>,
+        source: impl Into<String>,
+    ) -> Result<(), Error> {
+        Ok(self.env.add_template_owned(name.into(), source.into())?)
+    }
+
+    /// 加载目录下所有 `*.jinja` 文件，文件名（不含扩展名）为模板名
+    /// 用于按站点覆盖内置模板，没有覆盖的模板继续使用原来的
+    pub fn load_dir(&mut self, dir: impl AsRef<Path>) -> Result<(), Error> {
+        let dir = dir.as_ref();
+        let entries = std::fs::read_dir(dir).map_err(|err| Error::Io(dir.to_path_buf(), err))?;
+        for entry in entries {
+            let path = entry
+                .map_err(|err| Error::Io(dir.to_path_buf(), err))?
+                .path();
+            if path.extension().is_none_or(|ext| ext != "jinja") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let source =
+                std::fs::read_to_string(&path).map_err(|err| Error::Io(path.clone(), err))?;
+            self.add_template(name.to_string(), source)?;
+        }
+        Ok(())
+    }
+
+    pub fn render<T: WikiContext + ?Sized>(&self, item: &T) -> Result<String, Error> {
+        let template = self.env.get_template(T::TEMPLATE)?;
+        // 一个页面的文本由多个 Formatter 格式化，在整理上下文期间统一收集名词
+        let (context, terms) = format::collect_terms(|| item.wiki_context(self.site));
+        let mut wiki = template.render(context)?;
+        let Ok(glossary) = self.env.get_template("glossary") else {
+            return Ok(wiki);
+        };
+        if terms.is_empty() {
+            return Ok(wiki);
+        }
+        let terms = (terms.iter())
+            .map(|(name, desc)| GlossaryTermContext { name, desc })
+            .collect();
+        let glossary = glossary.render(GlossaryContext { terms })?;
+        if !glossary.trim().is_empty() {
+            wiki.push_str("\n\n");
+            wiki.push_str(glossary.trim());
+        }
+        Ok(wiki)
+    }
+}
+
+impl Default for WikiTemplates {
+    fn default() -> Self {
+        Self::bwiki()
+    }
+}
+
+/// install 之后使用的模板，None 表示内置的 BWIKI 模板
+/// 全局只有 [`INSTALLED`] 一份，测试中单独创建以免影响同时运行的其他测试
+struct Installed(RwLock<Option<Arc<WikiTemplates>>>);
+
+impl Installed {
+    const fn new() -> Self {
+        Self(RwLock::new(None))
+    }
+
+    fn set(&self, templates: WikiTemplates) {
+        *self.0.write().unwrap() = Some(Arc::new(templates));
+    }
+
+    fn get(&self) -> Arc<WikiTemplates> {
+        let installed = self.0.read().unwrap().clone();
+        installed.unwrap_or_else(|| WikiTemplates::builtin().clone())
+    }
+
+    fn render<T: WikiContext + ?Sized>(&self, item: &T) -> Cow<'static, str> {
+        match self.get().render(item) {
+            Ok(wiki) => Cow::Owned(wiki),
+            Err(err) => panic!("渲染模板 {} 失败：{err}", T::TEMPLATE),
+        }
+    }
+}
+
+static INSTALLED: Installed = Installed::new();
+
+/// 页面末尾的名词列表，`glossary` 模板的上下文
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct GlossaryContext<'a> {
+    /// 按在页面中首次出现的顺序排列
+    pub terms: Vec<GlossaryTermContext<'a>>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct GlossaryTermContext<'a> {
+    pub name: &'a str,
+    /// 名词说明，纯文本
+    pub desc: &'a str,
+}
+
+/// 各类型 Wiki::wiki 的实现，用 WikiTemplates::installed 渲染
+pub(crate) fn render_installed<T: WikiContext + ?Sized>(item: &T) -> Cow<'static, str> {
+    INSTALLED.render(item)
+}
+
+/// 各类型 Wiki::wiki_template 的实现，从 Wiki::wiki 的输出中解析开头的主模板
+/// 模板名和参数名都以模板文件为准，不在代码里重复一份
+pub(crate) fn main_template(wiki: &str) -> Option<base::Template> {
+    let mut templates = base::wiki::parse_templates(wiki).into_iter();
+    templates.next().map(|(_, template)| template)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::{Site, WikiContext, WikiTemplates};
+    use crate::book::*;
+    use crate::challenge::*;
+    use crate::message::*;
+    use crate::misc::RewardItemContext;
+    use crate::mission::*;
+    use crate::monster::guide::*;
+    use crate::monster::*;
+    use crate::rogue::tourn::*;
+    use crate::rogue::*;
+
+    struct Stub(&'static str);
+
+    #[derive(serde::Serialize)]
+    struct StubContext {
+        name: &'static str,
+        site: &'static str,
+    }
+
+    impl WikiContext for Stub {
+        const TEMPLATE: &'static str = "stub";
+        type Context<'c> = StubContext;
+
+        fn wiki_context(&self, site: Site) -> StubContext {
+            StubContext {
+                name: self.0,
+                site: match site {
+                    Site::Bwiki => "bwiki",
+                    Site::Fandom => "fandom",
+                },
+            }
+        }
+    }
+
+    fn render(templates: &WikiTemplates, name: &str, context: impl serde::Serialize) -> String {
+        let template = templates.env.get_template(name).unwrap();
+        template.render(context).unwrap()
+    }
+
+    #[test]
+    fn add_template_and_render() {
+        let mut templates = WikiTemplates::empty_for(Site::Fandom);
+        assert!(templates.render(&Stub("a")).is_err());
+        templates
+            .add_template("stub", "{{<< name >>|<< site >>}}")
+            .unwrap();
+        assert_eq!(templates.render(&Stub("a")).unwrap(), "{{a|fandom}}");
+        // 同名模板覆盖
+        templates.add_template("stub", "<< name >>").unwrap();
+        assert_eq!(templates.render(&Stub("b")).unwrap(), "b");
+        // 语法错误在添加时报告
+        assert!(templates.add_template("stub", "<% if %>").is_err());
+        // 输出不存在的字段时报错
+        templates.add_template("stub", "<< title >>").unwrap();
+        assert!(templates.render(&Stub("c")).is_err());
+    }
+
+    #[test]
+    fn load_dir_overrides_builtin() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("stub.jinja"), "<% include \"part\" %>!").unwrap();
+        std::fs::write(dir.path().join("part.jinja"), "<< name >>").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "<% if %>").unwrap();
+        let mut templates = WikiTemplates::bwiki();
+        templates.load_dir(dir.path()).unwrap();
+        assert_eq!(templates.render(&Stub("a")).unwrap(), "a!");
+        // 没有覆盖的内置模板仍然可用
+        assert!(templates.env.get_template("monster").is_ok());
+
+        std::fs::write(dir.path().join("broken.jinja"), "<% if %>").unwrap();
+        let err = templates.load_dir(dir.path()).unwrap_err();
+        assert!(std::matches!(err, super::Error::Template(_)));
+        let err = templates.load_dir(dir.path().join("missing")).unwrap_err();
+        assert!(std::matches!(err, super::Error::Io(..)));
+    }
+
+    #[test]
+    fn install_replaces_templates() {
+        // 不动全局的 INSTALLED，其他测试可能正在用它渲染
+        let installed = super::Installed::new();
+        assert!(installed.get().env.get_template("monster").is_ok());
+        let mut templates = WikiTemplates::empty();
+        templates.add_template("stub", "<< name >>").unwrap();
+        installed.set(templates);
+        assert_eq!(installed.render(&Stub("a")), "a");
+        installed.set(WikiTemplates::bwiki());
+        assert!(installed.get().render(&Stub("a")).is_err());
+    }
+
+    struct TermData;
+
+    impl format::GameData for TermData {
+        fn default_text_join_item(&self, _: u8, _: bool, _: bool) -> String {
+            String::new()
+        }
+        fn has_extra_effect_config(&self, name: &str) -> bool {
+            name == "裂伤"
+        }
+        fn extra_effect_desc(&self, _: &str) -> Option<String> {
+            Some(String::from("持续伤害"))
+        }
+    }
+
+    /// 上下文中的文本用 Formatter 格式化，可能包含名词
+    struct Termed(&'static str);
+
+    #[derive(serde::Serialize)]
+    struct TermedContext {
+        text: String,
+    }
+
+    impl WikiContext for Termed {
+        const TEMPLATE: &'static str = "termed";
+        type Context<'c> = TermedContext;
+
+        fn wiki_context(&self, site: Site) -> TermedContext {
+            TermedContext {
+                text: site.formatter(&TermData).format(self.0, &[]),
+            }
+        }
+    }
+
+    #[test]
+    fn render_appends_glossary() {
+        let sites = [
+            (
+                WikiTemplates::bwiki(),
+                "== 名词解释 ==\n* {{效果说明|裂伤}}：持续伤害",
+            ),
+            (
+                WikiTemplates::fandom(),
+                "==Glossary==\n* '''裂伤''': 持续伤害",
+            ),
+        ];
+        for (mut templates, glossary) in sites {
+            templates
+                .add_template("termed", "{{测试|<< text >>}}")
+                .unwrap();
+            let wiki = templates.render(&Termed("造成<u>裂伤</u>")).unwrap();
+            assert!(wiki.ends_with(&format!("}}}}\n\n{glossary}")), "{wiki}");
+        }
+        let mut templates = WikiTemplates::bwiki();
+        templates
+            .add_template("termed", "{{测试|<< text >>}}")
+            .unwrap();
+        assert_eq!(templates.render(&Termed("普通")).unwrap(), "{{测试|普通}}");
+        // 覆盖为空模板即可关闭名词列表
+        templates.add_template("glossary", "").unwrap();
+        assert_eq!(
+            templates.render(&Termed("<u>裂伤</u>")).unwrap(),
+            "{{测试|{{效果说明|裂伤}}}}"
+        );
+    }
+
+    #[test]
+    fn main_template_from_rendered_page() {
+        let templates = WikiTemplates::bwiki();
+        let wiki = render(
+            &templates,
+            "challenge_group",
+            ChallengeGroupContext::Story(story_context()),
+        );
+        let template = super::main_template(&wiki).unwrap();
+        assert_eq!(template.name, "虚构叙事单期");
+        assert_eq!(template.get("期数"), Some("012"));
+        assert_eq!(template.get("其一1队建议属性"), Some("火、冰"));
+        assert!(super::main_template("没有模板的文本").is_none());
+    }
+
+    fn story_context() -> ChallengeStoryContext<'static> {
+        let count = |name: &str, count| ChallengeMonsterCountContext {
+            name: name.to_string(),
+            count,
+        };
+        let score = |element, score| ChallengeWeaknessScoreContext {
+            element: Cow::Borrowed(element),
+            score,
+        };
+        ChallengeStoryContext {
+            issue: 12,
+            name: "测试",
+            begin_time: None,
+            end_time: None,
+            fever: false,
+            buff: Some(ChallengeBuffContext {
+                name: "逸闻",
+                desc: String::from("效果"),
+            }),
+            grit: Vec::new(),
+            options: vec![ChallengeStoryOptionContext {
+                name: "荒腔A",
+                desc: String::from("描述A"),
+                grit: None,
+            }],
+            floors: vec![ChallengeStoryFloorContext {
+                floor: 1,
+                teams: vec![
+                    ChallengeStoryTeamContext {
+                        waves: vec![
+                            ChallengeStoryWaveContext {
+                                monsters: vec!["甲".into(), "甲".into(), "乙".into()],
+                                normal: vec![count("甲", 2)],
+                                elite: vec![count("乙", 1)],
+                                max_teammate: 3,
+                                attack_ratio: None,
+                                hp_ratio: None,
+                            },
+                            ChallengeStoryWaveContext {
+                                monsters: vec!["丙".into()],
+                                normal: Vec::new(),
+                                elite: vec![count("丙", 1)],
+                                max_teammate: 4,
+                                attack_ratio: Some(10),
+                                hp_ratio: Some(0),
+                            },
+                        ],
+                        weaknesses: vec![Cow::Borrowed("火"), Cow::Borrowed("冰")],
+                        weakness_scores: vec![score("火", 500), score("冰", 0)],
+                    },
+                    ChallengeStoryTeamContext {
+                        waves: vec![ChallengeStoryWaveContext {
+                            monsters: vec!["丁".into()],
+                            normal: vec![count("丁", 1)],
+                            elite: Vec::new(),
+                            max_teammate: 3,
+                            attack_ratio: None,
+                            hp_ratio: None,
+                        }],
+                        weaknesses: vec![Cow::Borrowed("风")],
+                        weakness_scores: vec![score("风", 500)],
+                    },
+                ],
+            }],
+            special_monsters: Vec::new(),
+            elite_groups: vec![ChallengeEliteGroupContext {
+                id: 0,
+                attack_ratio: 100.,
+                defence_ratio: 100.,
+                hp_ratio: 100.,
+                speed_ratio: 100.,
+                stance_ratio: 100.,
+                waves: vec![[1, 1, 1], [1, 1, 2], [1, 2, 1]],
+            }],
+        }
+    }
+
+    #[test]
+    fn bwiki_challenge_story() {
+        let templates = WikiTemplates::bwiki();
+        let wiki = render(
+            &templates,
+            "challenge_group",
+            ChallengeGroupContext::Story(story_context()),
+        );
+        assert_eq!(
+            wiki,
+            "{{虚构叙事单期
+|期数=012
+|名称=测试
+|怪诞逸闻=效果
+|荒腔1=荒腔A
+|荒腔走板其一=描述A
+|其一1队1波=甲:2
+|其一1队1波特殊敌方=乙:1
+|其一1队2波=
+|其一1队2波特殊敌方=丙:1
+|其一1队敌方上限=3/4
+|其一1队建议属性=火、冰
+|其一1队弱点分数=火:500、冰:0
+|其一2队1波=丁:1
+|其一2队1波特殊敌方=
+|其一2队敌方上限=3
+|其一2队建议属性=风
+|其一2队弱点分数=风:500
+|其一1队2波攻击增幅=10%
+|其一1队2波生命增幅=
+}}
+<br />
+<br />
+----
+
+{{虚构叙事增援序列
+|其一上半第1波=甲、甲、乙
+|其一上半第2波=丙
+|其一下半第1波=丁
+}}
+
+<!-- 当期敌方属性增幅（降幅）： 精英组别 0，攻击 100.0%，防御 100.0%，生命 100.0%，速度 \
+             100.0%，韧性 100.0% -->"
+        );
+
+        let mut context = story_context();
+        let mut group = context.elite_groups[0].clone();
+        group.id = 1;
+        group.hp_ratio = 120.;
+        group.waves = vec![context.elite_groups[0].waves.pop().unwrap()];
+        context.elite_groups.push(group);
+        let wiki = render(
+            &templates,
+            "challenge_group",
+            ChallengeGroupContext::Story(context),
+        );
+        assert!(wiki.ends_with(
+            "\n\n<!-- 当期敌方属性增幅（降幅）：
+第1层上半第1波、第1层上半第2波：精英组别 0，攻击 100.0%，防御 100.0%，生命 100.0%，速度 \
+             100.0%，韧性 100.0%
+第1层下半第1波：精英组别 1，攻击 100.0%，防御 100.0%，生命 120.0%，速度 100.0%，韧性 100.0%
+-->"
+        ));
+    }
+
+    fn guide_context() -> MonsterGuideContext<'static> {
+        MonsterGuideContext {
+            name: String::from("首领"),
+            difficulty: 2,
+            difficulty_list: &[],
+            brief: String::new(),
+            tags: Vec::new(),
+            phases: Vec::new(),
+            difficulty_notes: Vec::new(),
+            text_notes: Vec::new(),
+        }
+    }
+
+    fn boss_context() -> ChallengeBossContext<'static> {
+        let half = |name| ChallengeBossHalfContext {
+            name,
+            boss: String::from("首领"),
+            features: vec![ChallengeBossFeatureContext {
+                name: "特性",
+                desc: String::from("造成{{效果说明|灼烧}}"),
+                effects: vec![ChallengeBuffContext {
+                    name: "灼烧",
+                    desc: String::from("持续伤害"),
+                }],
+            }],
+            buffs: vec![ChallengeBuffContext {
+                name: "公理",
+                desc: String::from("说明"),
+            }],
+            guide: guide_context(),
+        };
+        ChallengeBossContext {
+            issue: 3,
+            name: "末日",
+            begin_time: Some(String::from("2024/01/01 04:00")),
+            end_time: Some(String::from("2024/02/01 03:59")),
+            buff: ChallengeBuffContext {
+                name: "余烬",
+                desc: String::from("余烬说明"),
+            },
+            halves: vec![half("上"), half("下")],
+        }
+    }
+
+    #[test]
+    fn bwiki_challenge_boss() {
+        let templates = WikiTemplates::bwiki();
+        let wiki = render(
+            &templates,
+            "challenge_group",
+            ChallengeGroupContext::Boss(boss_context()),
+        );
+        assert!(
+            wiki.starts_with("{{末日幻影单期\n|期数=003\n|名称=末日\n|开始时间=2024/01/01 04:00\n")
+        );
+        assert!(wiki.contains(
+            "\n|上半特性1类型=固有特性\n|上半特性1名称=特性\n|上半特性1=造成<u>灼烧</u><br /><br \
+             />'''· 灼烧'''<br />持续伤害\n"
+        ));
+        assert!(wiki.contains("\n|终焉公理1名称=公理\n"));
+        assert!(wiki.contains("\n|终焉公理4名称=公理\n"));
+        assert!(wiki.contains("\n}}\n<br />\n<br />\n----\n\n{{首领图鉴\n|名称=首领\n"));
+        assert_eq!(wiki.matches("{{首领图鉴").count(), 2);
+    }
+
+    fn rogue_buff_context() -> RogueBuffContext<'static> {
+        RogueBuffContext {
+            name: Cow::Borrowed("命途回响：测试"),
+            category: Some(RogueBuffCategory::Legendary),
+            path: "智识",
+            effect: String::from("效果"),
+            upgraded_effect: None,
+            leveled_effect: None,
+            divergent_effect: Some(String::from("差分")),
+            divergent_upgraded_effect: Some(String::from("差分强化")),
+            in_divergent: true,
+        }
+    }
+
+    #[test]
+    fn bwiki_rogue_buff() {
+        let templates = WikiTemplates::bwiki();
+        assert_eq!(
+            render(&templates, "rogue_buff", rogue_buff_context()),
+            "{{模拟宇宙祝福
+|名称=命途回响：测试
+|稀有度=3星
+|命途=智识
+|模式=模拟宇宙、黄金与机械、差分宇宙
+|效果=效果
+|差分效果=差分
+|差分强化效果=差分强化
+|TAG=
+|实装版本=
+|类型=1
+|排序=
+}}"
+        );
+    }
+
+    fn message_context() -> MessageSectionContext {
+        let item = |sender, contacts: Option<&str>, kind, text: &str| MessageContext::Item {
+            sender,
+            contacts: contacts.map(str::to_string),
+            kind,
+            text: text.to_string(),
+            image: None,
+        };
+        let option = |text: &str, messages| MessageOptionContext {
+            text: text.to_string(),
+            image: None,
+            messages,
+        };
+        MessageSectionContext {
+            contacts: String::from("姬子"),
+            contacts_type: String::from("角色"),
+            signature: String::from("签名"),
+            messages: vec![
+                item(
+                    MessageSender::NPC,
+                    Some("姬子"),
+                    MessageItemType::Text,
+                    "你好",
+                ),
+                MessageContext::Selection {
+                    sticker: false,
+                    options: vec![
+                        option(
+                            "好",
+                            vec![item(
+                                MessageSender::Player,
+                                None,
+                                MessageItemType::Text,
+                                "嗯",
+                            )],
+                        ),
+                        option(
+                            "不",
+                            vec![item(
+                                MessageSender::System,
+                                None,
+                                MessageItemType::Text,
+                                "警告",
+                            )],
+                        ),
+                    ],
+                },
+                MessageContext::Selection {
+                    sticker: false,
+                    options: vec![option("再见", Vec::new())],
+                },
+                MessageContext::Item {
+                    sender: MessageSender::Player,
+                    contacts: None,
+                    kind: MessageItemType::Sticker,
+                    text: String::from("01-14"),
+                    image: Some(String::from("sticker.png")),
+                },
+                MessageContext::Item {
+                    sender: MessageSender::NPC,
+                    contacts: None,
+                    kind: MessageItemType::Image,
+                    text: String::from("图"),
+                    image: Some(String::from("ui/a.png")),
+                },
+            ],
+            mission: Some(String::from("任务")),
+            mission_type: Some(model::mission::MainMissionType::Main),
+        }
+    }
+
+    #[test]
+    fn bwiki_message_section() {
+        let templates = WikiTemplates::bwiki();
+        assert_eq!(
+            render(&templates, "message_section", message_context()),
+            "{{角色对话|模板开始|姬子}}
+  {{角色对话|左|姬子|文本|你好}}
+  {{短信选项
+  |选项1=好
+  |剧情1=
+    {{角色对话|右|开拓者|文本|嗯}}
+  |选项2=不
+  |剧情2=
+    {{短信警告|警告}}
+  }}
+  {{短信选项|选项1=再见}}
+  {{角色对话|右|开拓者|表情|01-14}}
+  {{角色对话|左|开拓者|图片|图<!-- ui/a.png -->}}
+  {{接取任务|主线任务|任务}}
+{{角色对话|模板结束}}"
+        );
+    }
+
+    fn monster_context() -> MonsterContext<'static> {
+        let skill = |name, sp_hit, is_threat| MonsterSkillContext {
+            name,
+            tag: "单攻",
+            sp_hit,
+            is_threat,
+            desc: String::from("造成伤害"),
+        };
+        MonsterContext {
+            name: Cow::Borrowed("测试（完整）"),
+            camp: "虫群",
+            rank: Some(MonsterRank::Elite),
+            is_summon: false,
+            introduction: String::from("介绍"),
+            weaknesses: vec![Cow::Borrowed("火")],
+            damage_types: vec![Cow::Borrowed("量子")],
+            variant: Some("完整"),
+            speed: 120,
+            max_speed: 132,
+            stance: 100,
+            summons: vec![String::from("召唤物")],
+            resistances: vec![MonsterResistanceContext {
+                element: Cow::Borrowed("物理"),
+                value: 20,
+            }],
+            element_resistances: vec![Cow::Borrowed("物理")],
+            debuff_resists: Vec::new(),
+            phases: vec![
+                MonsterPhaseContext {
+                    skills: vec![skill("攻击", 10, false)],
+                    threat_count: 0,
+                },
+                MonsterPhaseContext {
+                    skills: vec![skill("大招", 0, true)],
+                    threat_count: 1,
+                },
+            ],
+        }
+    }
+
+    fn memory_context() -> ChallengeMemoryContext<'static> {
+        ChallengeMemoryContext {
+            issue: 1,
+            name: "回忆",
+            begin_time: None,
+            end_time: None,
+            buff: Some(String::from("紊流")),
+            floors: vec![ChallengeMemoryFloorContext {
+                floor: 1,
+                teams: vec![ChallengeMemoryTeamContext {
+                    waves: vec![vec![String::from("甲"), String::from("乙")]],
+                    weaknesses: vec![Cow::Borrowed("Fire")],
+                }],
+            }],
+            special_monsters: vec![String::from("丙")],
+        }
+    }
+
+    fn monster_guide_context() -> MonsterGuidePageContext<'static> {
+        let mut guide = guide_context();
+        guide.brief = String::from("简介");
+        guide.tags = vec![MonsterGuideTagContext {
+            name: "特性",
+            brief: String::from("说明"),
+            detail: String::new(),
+            effects: vec![MonsterGuideEffectContext {
+                name: "灼烧",
+                desc: String::from("持续伤害"),
+            }],
+        }];
+        guide.phases = vec![MonsterGuidePhaseContext {
+            name: "第一阶段",
+            difficulty: 1,
+            desc: String::new(),
+            answer: String::from("应对"),
+            skills: vec![MonsterGuideSkillContext {
+                name: "技能",
+                difficulty: 1,
+                texts: vec![String::from("一"), String::from("二")],
+                effects: Vec::new(),
+                answer: String::new(),
+            }],
+        }];
+        guide.text_notes = vec![String::from("攻略")];
+        MonsterGuidePageContext { guide }
+    }
+
+    fn book_series_context() -> BookSeriesContext {
+        let book = |name: &str, volume: &str, content: &str| BookContext {
+            name: name.to_string(),
+            volume: volume.to_string(),
+            content: content.to_string(),
+        };
+        BookSeriesContext {
+            name: String::from("书"),
+            num: 2,
+            comments: String::from("说明"),
+            world: 1,
+            world_name: String::from("Jarilo-VI"),
+            icon: 0,
+            kind: "Book",
+            books: vec![book("上", "1", "内容一"), book("下", "2", "内容二")],
+        }
+    }
+
+    fn main_mission_context() -> MainMissionContext<'static> {
+        MainMissionContext {
+            name: "任务",
+            r#type: MainMissionType::Companion,
+            chapter: None,
+            world: Some("Xianzhou"),
+            rewards: vec![RewardItemContext {
+                name: "Credit",
+                count: 100,
+            }],
+            objectives: vec![
+                SubMissionContext {
+                    target: String::from("目标一"),
+                    desc: String::new(),
+                },
+                SubMissionContext {
+                    target: String::from("目标二"),
+                    desc: String::from("描述"),
+                },
+            ],
+            next: None,
+            messages: vec!["姬子"],
+        }
+    }
+
+    fn rogue_miracle_context() -> RogueMiracleContext<'static> {
+        RogueMiracleContext {
+            name: Cow::Borrowed("奇物"),
+            effect: String::from("效果"),
+            simple_effect: None,
+            story: String::from("故事"),
+            handbook_types: vec!["Swarm Disaster"],
+            unlock: Some("Obtain in the Swarm Disaster"),
+            modes: vec![MiracleMode::Simulated, MiracleMode::Divergent],
+        }
+    }
+
+    fn tourn_miracle_context() -> RogueTournMiracleContext<'static> {
+        RogueTournMiracleContext {
+            name: Cow::Borrowed("奇物"),
+            category: MiracleCategory::Hex,
+            effect: String::from("效果"),
+            simple_effect: Some(String::from("简述")),
+            story: String::new(),
+            unlock: None,
+            modes: vec![MiracleMode::Divergent],
+        }
+    }
+
+    fn tourn_formula_context() -> RogueTournFormulaContext<'static> {
+        RogueTournFormulaContext {
+            name: Cow::Borrowed("方程"),
+            category: FormulaCategory::PathEcho,
+            main_path: "Erudition",
+            main_path_count: 4,
+            sub_path: None,
+            sub_path_count: 0,
+            effect: String::from("效果"),
+            story: String::from("故事"),
+            deduction: Some(vec![
+                DeductionContext::Talk {
+                    name: "黑塔",
+                    text: "你好",
+                },
+                DeductionContext::Options {
+                    options: vec!["甲", "乙"],
+                },
+            ]),
+        }
+    }
+
+    fn titan_bless_context() -> RogueTournTitanBlessContext<'static> {
+        RogueTournTitanBlessContext {
+            name: "祝颂",
+            titan: TitanType::Moneta,
+            level: 2,
+            effect: String::from("效果"),
+            extra_effects: vec![RogueTournExtraEffectContext {
+                name: "名词",
+                desc: String::from("说明"),
+            }],
+            battle_display: &[
+                BlessBattleDisplayCategory::Day,
+                BlessBattleDisplayCategory::Night,
+            ],
+        }
+    }
+
+    fn weekly_season_context() -> RogueTournWeeklySeasonContext<'static> {
+        RogueTournWeeklySeasonContext {
+            issues: vec![RogueTournWeeklyIssueContext {
+                issue: 1,
+                name: "周期",
+                begin_date: String::from("2024/06/19"),
+                end_date: String::from("2024/06/25"),
+                formulas: vec![Cow::Borrowed("甲"), Cow::Borrowed("乙")],
+                miracles: vec![Cow::Borrowed("丙")],
+                blessings: vec!["丁"],
+                bonus: Some(RogueTournBonusContext {
+                    title: "祝福",
+                    desc: "说明",
+                }),
+            }],
+        }
+    }
+
+    fn nested_message_context() -> MessageSectionContext {
+        let mut context = message_context();
+        let MessageContext::Selection { options, .. } = &mut context.messages[1] else {
+            unreachable!()
+        };
+        options[0].messages.push(MessageContext::Selection {
+            sticker: true,
+            options: vec![MessageOptionContext {
+                text: String::from("表情"),
+                image: Some(String::from("emoji.png")),
+                messages: vec![MessageContext::Item {
+                    sender: MessageSender::NPC,
+                    contacts: Some(String::from("三月七")),
+                    kind: MessageItemType::Text,
+                    text: String::from("好耶"),
+                    image: None,
+                }],
+            }],
+        });
+        context
+    }
+
+    #[test]
+    fn bwiki_monster() {
+        let templates = WikiTemplates::bwiki();
+        let wiki = render(&templates, "monster", monster_context());
+        assert!(wiki.contains("\n|类型=强敌<!--"));
+        assert!(wiki.contains("\n|TAG=召唤、完整\n|速度=120~132\n|韧性=100\n|召唤物=召唤物\n"));
+        assert!(wiki.contains("\n|物抗=20%\n"));
+        assert!(wiki.contains("\n|阶段数=2\n|技能数=1\n|技能1名称=攻击\n"));
+        assert!(wiki.contains("\n|阶段2大招=1\n|阶段2技能1名称=大招\n"));
+        assert!(wiki.contains("\n|阶段2技能1能量=\n"));
+
+        // 召唤物输出为类型，非 完整/错误 的变体不作为 TAG
+        let mut context = monster_context();
+        context.is_summon = true;
+        context.variant = Some("其他");
+        let wiki = render(&templates, "monster", context);
+        assert!(wiki.contains("\n|类型=召唤物<!--"));
+        assert!(wiki.contains("\n|TAG=召唤\n"));
+    }
+
+    #[test]
+    fn fandom_monster() {
+        let templates = WikiTemplates::fandom();
+        let wiki = render(&templates, "monster", monster_context());
+        assert!(wiki.starts_with("{{Enemy Infobox\n|title      = 测试（完整）\n"));
+        assert!(wiki.contains("\n|type       = Elite Enemy\n"));
+        assert!(wiki.contains("\n|speed      = 120–132\n|summons    = 召唤物\n"));
+        assert!(wiki.contains(
+            "\n===Phase 2===\n{{Enemy Ability\n|name        = 大招\n|tag         = \
+             单攻\n|toughness   = \n|ultimate    = yes\n"
+        ));
+        assert!(wiki.ends_with("==Navigation==\n{{Enemy Navbox}}"));
+    }
+
+    #[test]
+    fn fandom_monster_guide() {
+        let templates = WikiTemplates::fandom();
+        assert_eq!(
+            render(&templates, "monster_guide", monster_guide_context()),
+            "{{Boss Guide Infobox
+|title      = 首领
+|difficulty = 2
+}}
+'''首领''' is a boss in [[Apocalyptic Shadow]].
+
+简介
+
+==Characteristics==
+{{Boss Characteristic
+|name   = 特性
+|effect = 说明
+}}
+:'''灼烧:''' 持续伤害
+
+==第一阶段==
+{{Boss Guide Skill
+|name        = 技能
+|description = 一<br />二
+|counter     = 
+}}
+'''Counter:''' 应对
+
+==Notes==
+* 攻略
+"
+        );
+    }
+
+    #[test]
+    fn fandom_challenge_memory() {
+        let templates = WikiTemplates::fandom();
+        assert_eq!(
+            render(
+                &templates,
+                "challenge_group",
+                ChallengeGroupContext::OutdatedMemory
+            ),
+            "<!-- Legacy Memory of Chaos is not supported -->"
+        );
+        assert_eq!(
+            render(
+                &templates,
+                "challenge_group",
+                ChallengeGroupContext::Memory(memory_context())
+            ),
+            "{{Memory of Chaos Infobox
+|title      = 回忆
+|issue      = 1
+|start      = 
+|end        = 
+|turbulence = 紊流
+}}
+'''回忆''' is a [[Memory of Chaos]] period.
+
+==Stages==
+===Stage 1===
+'''Node 1''' (Recommended: Fire)
+* Wave 1: [[甲]], [[乙]]
+
+==Special Enemies==
+* [[丙]]
+"
+        );
+    }
+
+    #[test]
+    fn fandom_challenge_story() {
+        let templates = WikiTemplates::fandom();
+        let wiki = render(
+            &templates,
+            "challenge_group",
+            ChallengeGroupContext::Story(story_context()),
+        );
+        assert!(wiki.starts_with("{{Pure Fiction Infobox\n|title  = 测试\n|issue  = 12\n"));
+        assert!(wiki.contains("\n|cacophony = 效果\n}}\n"));
+        assert!(wiki.contains("\n{{Pure Fiction Buff\n|name   = 荒腔A\n|effect = 描述A\n}}\n"));
+        assert!(wiki.ends_with(
+            "\n'''Node 1''' (Recommended: 火, 冰)
+* Wave 1 (max 3 on field): [[甲]], [[甲]], [[乙]]
+* Wave 2 (max 4 on field, ATK +10%): [[丙]]
+'''Node 2''' (Recommended: 风)
+* Wave 1 (max 3 on field): [[丁]]
+"
+        ));
+    }
+
+    #[test]
+    fn fandom_challenge_boss() {
+        let templates = WikiTemplates::fandom();
+        let wiki = render(
+            &templates,
+            "challenge_group",
+            ChallengeGroupContext::Boss(boss_context()),
+        );
+        assert!(wiki.starts_with("{{Apocalyptic Shadow Infobox\n|title = 末日\n|issue = 3\n"));
+        assert!(wiki.contains("\n|ruin  = 余烬说明\n}}\n"));
+        assert!(wiki.contains("\n==Node 2: 下==\n'''Boss:''' [[首领]]\n"));
+        assert!(wiki.contains(
+            "\n|unlock = \n|effect = 造成{{效果说明|灼烧}}\n}}\n:'''灼烧:''' 持续伤害\n"
+        ));
+        // 嵌入的首领图鉴不输出信息框
+        assert!(!wiki.contains("Boss Guide Infobox"));
+        assert_eq!(wiki.matches("{{Apocalyptic Shadow Buff").count(), 2);
+    }
+
+    #[test]
+    fn fandom_rogue_buff() {
+        let templates = WikiTemplates::fandom();
+        let wiki = render(&templates, "rogue_buff", rogue_buff_context());
+        assert!(wiki.contains("\n|rarity          = 3\n"));
+        assert!(wiki.contains("\n|effect_enhanced = \n"));
+        assert!(wiki.contains("\n|modes           = Simulated Universe, Divergent Universe\n"));
+        assert!(wiki.ends_with(
+            "{{Blessing Effect\n|effect          = 差分\n|effect_enhanced = 差分强化\n}}\n"
+        ));
+    }
+
+    #[test]
+    fn fandom_message_section() {
+        let templates = WikiTemplates::fandom();
+        // 选项分支中再嵌套一层选项，检查 thread 宏的递归
+        assert_eq!(
+            render(&templates, "message_section", nested_message_context()),
+            "{{Message Start|contact=姬子|signature=签名}}
+{{Message|left|姬子|你好}}
+{{Message Options
+|1 = 好
+|1_reply = 
+{{Message|right|Trailblazer|嗯}}
+{{Message Options
+|1 = 表情<!-- emoji.png -->
+|1_reply = 
+{{Message|left|三月七|好耶}}
+
+}}
+
+|2 = 不
+|2_reply = 
+{{Message Notice|警告}}
+
+}}
+{{Message Options
+|1 = 再见
+}}
+{{Message|right|Trailblazer|{{Sticker|01-14}}<!-- sticker.png -->}}
+{{Message|left|Trailblazer|图<!-- ui/a.png -->}}
+
+{{Message Mission|任务}}
+{{Message End}}"
+        );
+    }
+
+    #[test]
+    fn fandom_book_series() {
+        let templates = WikiTemplates::fandom();
+        let wiki = render(&templates, "book_series", book_series_context());
+        assert!(wiki.starts_with(
+            "{{Readable Infobox\n|title   = 书\n|type    = Book\n|world   = Jarilo-VI\n|volumes = \
+             2\n"
+        ));
+        assert!(wiki.contains("\n==Description==\n说明\n\n==上==\n{{Readable\n|volume = 1\n"));
+        assert!(wiki.ends_with("\n==下==\n{{Readable\n|volume = 2\n|text   = 内容二\n}}\n"));
+    }
+
+    #[test]
+    fn fandom_main_mission() {
+        let templates = WikiTemplates::fandom();
+        let wiki = render(&templates, "main_mission", main_mission_context());
+        assert!(wiki.contains("\n|type    = Companion Mission\n|chapter = \n"));
+        assert!(wiki.contains("\n|rewards = Credit*100\n|next    = \n"));
+        assert!(wiki.contains("\nThe quest is accepted after a message from 姬子.\n"));
+        assert!(wiki.ends_with("\n==Steps==\n# '''目标一'''\n# '''目标二'''<br />描述\n"));
+    }
+
+    #[test]
+    fn fandom_rogue_miracle() {
+        let templates = WikiTemplates::fandom();
+        let wiki = render(&templates, "rogue_miracle", rogue_miracle_context());
+        assert!(wiki.contains("\n|type   = Swarm Disaster\n"));
+        assert!(wiki.contains("\n|unlock = Obtain in the Swarm Disaster\n"));
+        assert!(wiki.contains("\n|modes  = Simulated Universe, Divergent Universe\n"));
+        // 没有效果简述时不输出对应段落
+        assert!(!wiki.contains("==Summary=="));
+        assert!(wiki.ends_with("\n==Lore==\n故事\n"));
+    }
+
+    #[test]
+    fn fandom_rogue_tourn_miracle() {
+        let templates = WikiTemplates::fandom();
+        let wiki = render(&templates, "rogue_tourn_miracle", tourn_miracle_context());
+        assert!(wiki.contains("\n|rarity = Weighted\n"));
+        assert!(wiki.contains("\n|unlock = \n|modes  = Divergent Universe\n"));
+        assert!(!wiki.contains("==Lore=="));
+        assert!(wiki.ends_with("\n==Summary==\n简述\n"));
+    }
+
+    #[test]
+    fn fandom_rogue_tourn_formula() {
+        let templates = WikiTemplates::fandom();
+        let wiki = render(&templates, "rogue_tourn_formula", tourn_formula_context());
+        assert!(wiki.contains("\n|rarity   = Critical\n|path1    = Erudition\n|path1_req = 4\n"));
+        // 临界方程没有次要命途
+        assert!(!wiki.contains("|path2"));
+        assert!(wiki.ends_with("\n==Deduction==\n:'''黑塔:''' 你好\n* 甲\n* 乙\n"));
+    }
+
+    #[test]
+    fn fandom_rogue_tourn_titan_bless() {
+        let templates = WikiTemplates::fandom();
+        let wiki = render(&templates, "rogue_tourn_titan_bless", titan_bless_context());
+        assert!(wiki.contains("\n|titan   = Mnestia\n|level   = 2\n"));
+        assert!(wiki.contains("\n|display = Day, Night\n"));
+        assert!(wiki.ends_with("\n==Terms==\n;名词\n:说明\n"));
+    }
+
+    #[test]
+    fn fandom_rogue_tourn_weekly_season() {
+        let templates = WikiTemplates::fandom();
+        assert_eq!(
+            render(
+                &templates,
+                "rogue_tourn_weekly_season",
+                weekly_season_context()
+            ),
+            "{| class=\"article-table\"
+! Week !! Name !! Duration !! Equations !! Curios !! Blessings !! Bonus
+|-
+| 1 || 周期 || 2024/06/19 – 2024/06/25
+| [[甲]]<br />[[乙]]
+| [[丙]]
+| 丁
+| '''祝福'''<br />说明
+|}"
+        );
+    }
+}
@@ -10,6 +10,10 @@ pub trait GameData {
         newline_after_block: bool,
     ) -> String;
     fn has_extra_effect_config(&self, name: &str) -> bool;
+    /// 名词的说明文案（纯文本），用于 HTML 悬浮提示、Markdown 脚注、终端附录等
+    fn extra_effect_desc(&self, _name: &str) -> Option<String> {
+        None
+    }
 }
 
 impl GameData for data::GameData {
@@ -37,4 +41,13 @@ impl GameData for data::GameData {
     fn has_extra_effect_config(&self, name: &str) -> bool {
         self._extra_effect_config_by_name().get(name).is_some()
     }
+
+    fn extra_effect_desc(&self, name: &str) -> Option<String> {
+        let effect = self._extra_effect_config_by_name().get(name)?;
+        let arguments = crate::Argument::from_array(&effect.desc_param_list);
+        let desc = crate::formatter::Formatter::new(self)
+            .normal_form(true)
+            .format(self.text(effect.extra_effect_desc), &arguments);
+        Some(desc)
+    }
 }
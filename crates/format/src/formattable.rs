@@ -95,7 +95,7 @@ impl<Data: crate::data::GameData> Formattable<f64> for Formatter<'_, Data> {
 
     fn write_float(&mut self, value: &f64, prec: u32, percent: bool) {
         let value = if percent { *value * 100. } else { *value };
-        let prec_10 = 10usize.pow(prec) as f64;
+        let prec_10 = 10f64.powi(prec as i32);
         let value = f64::round(value * prec_10) / prec_10;
         self.push_str(&format!("{:.1$}", value, prec as _));
         if percent {
@@ -19,10 +19,13 @@ enum State {
     UnityVarVal(String, String),
 }
 
+#[derive(Clone, Copy)]
 enum Syntax {
     Raw,
     MediaWiki,
     AnsiSequence,
+    Html,
+    Markdown,
 }
 
 impl Syntax {
@@ -32,12 +35,19 @@ impl Syntax {
     fn is_media_wiki(&self) -> bool {
         std::matches!(self, Self::MediaWiki)
     }
+    /// 需要转义 < > 和不换行空格的格式
+    fn is_html_like(&self) -> bool {
+        std::matches!(self, Self::MediaWiki | Self::Html)
+    }
 }
 
 pub struct Formatter<'a, Data: crate::data::GameData + ?Sized> {
     data: &'a Data,
 
+    // 实际使用的输出格式，由 selected_syntax 和 normal_form 决定，见 update_syntax
     syntax: Syntax,
+    // 各 *_syntax 方法选择的输出格式
+    selected_syntax: Syntax,
     newline_after_block: bool,
 
     state: State,
@@ -59,6 +69,52 @@ pub struct Formatter<'a, Data: crate::data::GameData + ?Sized> {
     omit_br_once: bool,
     // 在 </p> 或者 <br /> 之后需要写一个 \n，但是避免后续仍是闭合标签的情况
     need_write_newline: bool,
+    // 格式化过程中遇到的占位符，供 placeholders 和 validate 使用
+    placeholders: Vec<crate::Placeholder>,
+    // 终端输出时的折行宽度，None 时自动获取终端宽度
+    terminal_width: Option<usize>,
+    // 终端输出时 <align> 开始的位置，在 </align> 时取出整段内容对齐
+    // 第一项记录开始时是否处于 underlining 状态，不一致时说明和 <u> 交叉嵌套了，不处理
+    align: Option<(bool, usize, crate::terminal::Align)>,
+    // 终端输出时嵌套的颜色，</color> 时恢复外层颜色而非直接重置
+    colors: Vec<String>,
+    // 终端输出时嵌套的粗体、斜体、删除线，闭合时外层还有同样的样式则重新打开
+    styles: Vec<&'static str>,
+    // 检索用的规范纯文本，见 normal_form
+    normal_form: bool,
+    // MediaWiki 输出不使用 BWIKI 的站内模板，见 fandom_syntax
+    fandom: bool,
+    // 出现过的名词（ExtraEffectConfig），按首次出现的顺序排列，跨多次 format 累积
+    // Markdown 脚注和终端附录的编号即为下标加一
+    terms: Vec<String>,
+    // placeholders 和 validate 只是分析模板，不记录名词
+    analyzing: bool,
+}
+
+thread_local! {
+    /// collect_terms 进行中时收集的名词和说明，None 表示没有在收集
+    static COLLECTED_TERMS: std::cell::RefCell<Option<Vec<(String, String)>>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// 收集 f 执行期间所有 Formatter 输出过的名词及其说明（纯文本），按首次出现的顺序去重
+/// 用于给一整个页面生成名词列表，页面上的文本往往由多个 Formatter 分别格式化
+/// 规范纯文本（normal_form）不是页面输出，其中的名词不计入
+pub fn collect_terms<R>(f: impl FnOnce() -> R) -> (R, Vec<(String, String)>) {
+    let outer = COLLECTED_TERMS.replace(Some(Vec::new()));
+    let result = f();
+    let terms = COLLECTED_TERMS.replace(outer).unwrap_or_default();
+    // 嵌套收集时外层同样能拿到内层的名词
+    COLLECTED_TERMS.with_borrow_mut(|outer| {
+        if let Some(outer) = outer {
+            for term in &terms {
+                if !outer.iter().any(|(name, _)| *name == term.0) {
+                    outer.push(term.clone());
+                }
+            }
+        }
+    });
+    (result, terms)
 }
 
 impl<'a, Data: crate::data::GameData> Formatter<'a, Data> {
@@ -66,6 +122,7 @@ impl<'a, Data: crate::data::GameData> Formatter<'a, Data> {
         Self {
             data,
             syntax: Syntax::Raw,
+            selected_syntax: Syntax::Raw,
             newline_after_block: false,
             state: State::Literal,
             result: String::new(),
@@ -78,6 +135,15 @@ impl<'a, Data: crate::data::GameData> Formatter<'a, Data> {
             ruby: String::new(),
             omit_br_once: false,
             need_write_newline: false,
+            placeholders: Vec::new(),
+            terminal_width: None,
+            align: None,
+            colors: Vec::new(),
+            styles: Vec::new(),
+            normal_form: false,
+            fandom: false,
+            terms: Vec::new(),
+            analyzing: false,
         }
     }
 }
@@ -85,14 +151,43 @@ impl<'a, Data: crate::data::GameData> Formatter<'a, Data> {
 impl<Data: crate::data::GameData> Formatter<'_, Data> {
     pub fn media_wiki_syntax(mut self, set: bool) -> Self {
         if set {
-            self.syntax = Syntax::MediaWiki;
+            self.selected_syntax = Syntax::MediaWiki;
+            self.update_syntax();
+        }
+        self
+    }
+
+    /// 输出 Fandom 等英文站点用的 MediaWiki 语法
+    /// 和 media_wiki_syntax 的区别在于不依赖 BWIKI 的站内模板（颜色、注音、效果说明），改用 HTML 标签
+    pub fn fandom_syntax(mut self, set: bool) -> Self {
+        if set {
+            self.selected_syntax = Syntax::MediaWiki;
+            self.fandom = true;
+            self.update_syntax();
         }
         self
     }
 
     pub fn ansi_sequence_syntax(mut self, set: bool) -> Self {
         if set {
-            self.syntax = Syntax::AnsiSequence;
+            self.selected_syntax = Syntax::AnsiSequence;
+            self.update_syntax();
+        }
+        self
+    }
+
+    pub fn html_syntax(mut self, set: bool) -> Self {
+        if set {
+            self.selected_syntax = Syntax::Html;
+            self.update_syntax();
+        }
+        self
+    }
+
+    pub fn markdown_syntax(mut self, set: bool) -> Self {
+        if set {
+            self.selected_syntax = Syntax::Markdown;
+            self.update_syntax();
         }
         self
     }
@@ -102,25 +197,51 @@ impl<Data: crate::data::GameData> Formatter<'_, Data> {
         self
     }
 
-    pub(crate) fn push(&mut self, ch: char) {
+    /// 输出用于检索、比较、去重的规范纯文本
+    /// 去掉所有标签，展开 TEXTJOIN 默认值，性别分支固定取女性开拓者，注音只保留正文
+    /// 所有空白（包括不换行空格和换行）合并为一个空格，CJK 字符之间的空白直接去掉
+    ///
+    /// 优先于其他输出格式，和 *_syntax 方法的调用顺序无关
+    pub fn normal_form(mut self, set: bool) -> Self {
+        self.normal_form = set;
+        self.update_syntax();
+        self
+    }
+
+    fn update_syntax(&mut self) {
+        self.syntax = match self.normal_form {
+            true => Syntax::Raw,
+            false => self.selected_syntax,
+        };
+    }
+
+    /// 终端输出时的折行宽度，不设置时自动获取终端宽度，获取不到则不折行，<align> 也不对齐
+    pub fn terminal_width(mut self, width: usize) -> Self {
+        self.terminal_width = Some(width);
+        self
+    }
+
+    fn layout_width(&self) -> Option<usize> {
+        self.terminal_width.or_else(crate::terminal::detect_width)
+    }
+
+    fn buffer(&mut self) -> &mut String {
         if self.underlining {
             &mut self.underline
         } else {
             &mut self.result
         }
-        .push(ch);
+    }
+
+    pub(crate) fn push(&mut self, ch: char) {
+        self.buffer().push(ch);
     }
 
     pub(crate) fn push_str(&mut self, string: &str) {
-        if self.underlining {
-            &mut self.underline
-        } else {
-            &mut self.result
-        }
-        .push_str(string);
+        self.buffer().push_str(string);
     }
 
-    fn feed(&mut self, char: char, arguments: &[crate::Argument]) {
+    fn feed(&mut self, char: char, levels: &[&[crate::Argument]]) {
         match &mut self.state {
             State::Literal => {
                 if char != '<' && char != '\\' {
@@ -135,18 +256,25 @@ impl<Data: crate::data::GameData> Formatter<'_, Data> {
                     '<' => self.state = State::UnityTagKey(String::new()),
                     '{' => self.state = State::UnityVarKey(String::new()),
                     '\\' => self.state = State::UnityEscape,
-                    '\u{00A0}' => self.push_str(if self.syntax.is_media_wiki() {
+                    '\u{00A0}' => self.push_str(if self.syntax.is_html_like() {
                         "&nbsp;"
                     } else {
                         " "
                     }),
-                    '>' => self.push_str(if self.syntax.is_media_wiki() {
+                    '>' => self.push_str(if self.syntax.is_html_like() {
                         "&gt;"
                     } else {
                         ">"
                     }),
                     '|' if self.syntax.is_media_wiki() => self.push_str("&#x7c;"),
                     '=' if self.syntax.is_media_wiki() => self.push_str("{{=}}"),
+                    '&' if std::matches!(self.syntax, Syntax::Html) => self.push_str("&amp;"),
+                    '*' | '_' | '~' | '`' | '[' | ']'
+                        if std::matches!(self.syntax, Syntax::Markdown) =>
+                    {
+                        self.push('\\');
+                        self.push(char);
+                    }
                     _ => self.push(char),
                 };
             }
@@ -173,8 +301,8 @@ impl<Data: crate::data::GameData> Formatter<'_, Data> {
                     self.state = State::Percent;
                     return;
                 }
-                self.format_single(arguments);
-                self.feed(char, arguments);
+                self.format_single(levels);
+                self.feed(char, levels);
             }
             State::FormatArg => {
                 if char == ']' {
@@ -188,18 +316,21 @@ impl<Data: crate::data::GameData> Formatter<'_, Data> {
                     self.state = State::Percent;
                     return;
                 }
-                self.format_single(arguments);
-                self.feed(char, arguments);
+                self.format_single(levels);
+                self.feed(char, levels);
             }
             State::Percent => {
-                self.format_single(arguments);
-                self.feed(char, arguments);
+                self.format_single(levels);
+                self.feed(char, levels);
             }
             State::UnityEscape => {
                 self.state = State::Literal;
                 match char {
                     'n' => {
-                        if !self.syntax.is_media_wiki() {
+                        if std::matches!(self.syntax, Syntax::Markdown) {
+                            // Markdown 中行尾两个空格才是换行
+                            self.push_str("  \n");
+                        } else if !self.syntax.is_html_like() {
                             self.push('\n');
                         } else if !self.omit_br_once {
                             self.push_str("<br />");
@@ -221,12 +352,12 @@ impl<Data: crate::data::GameData> Formatter<'_, Data> {
                     // 处理 <<<</align> 的特殊情况
                     self.omit_br_once = false;
                     self.state = State::Literal;
-                    if self.syntax.is_media_wiki() {
+                    if self.syntax.is_html_like() {
                         self.push_str("&lt;");
                     } else {
                         self.push('<');
                     }
-                    return self.feed('<', arguments);
+                    return self.feed('<', levels);
                 }
                 if char == '=' {
                     self.omit_br_once = false;
@@ -287,21 +418,37 @@ impl<Data: crate::data::GameData> Formatter<'_, Data> {
         }
     }
 
-    fn format_single(&mut self, arguments: &[crate::Argument]) {
+    fn format_single(&mut self, levels: &[&[crate::Argument]]) {
         if !std::matches!(
             self.state,
             State::Hashbang | State::ArgNum | State::FormatArg | State::FmtArgEnd | State::Percent
         ) {
             return;
         }
-        let percent = self.state == State::Percent;
-        if self.arg_num > arguments.len() || arguments.is_empty() {
+        // 结尾单独的 #，后面没有数字，不是占位符
+        if self.state == State::Hashbang {
             self.state = State::Literal;
+            self.push('#');
+            return;
+        }
+        let percent = self.state == State::Percent;
+        let fmt_arg = std::mem::take(&mut self.fmt_arg);
+        let format = crate::PlaceholderFormat::parse(&fmt_arg);
+        self.state = State::Literal;
+        self.placeholders.push(crate::Placeholder {
+            index: self.arg_num,
+            format: format.clone(),
+            percent,
+        });
+        if levels.is_empty()
+            || levels
+                .iter()
+                .any(|arguments| self.arg_num > arguments.len() || arguments.is_empty())
+        {
             self.push('#');
             self.push_str(&self.arg_num.to_string());
-            if !self.fmt_arg.is_empty() {
+            if !fmt_arg.is_empty() {
                 self.push('[');
-                let fmt_arg = std::mem::take(&mut self.fmt_arg);
                 self.push_str(&fmt_arg);
                 self.push(']');
             }
@@ -310,20 +457,35 @@ impl<Data: crate::data::GameData> Formatter<'_, Data> {
             }
             return;
         }
-        use crate::formattable::Formattable;
-        let arg = &arguments[self.arg_num - 1];
-        if self.fmt_arg.is_empty() {
-            self.write_raw(arg, percent);
+        // 每个等级分别写入缓冲区后再取出来比较，全部相同只写一次，否则以 / 分隔
+        let mut values = Vec::with_capacity(levels.len());
+        for arguments in levels {
+            let start = self.buffer().len();
+            self.format_argument(&arguments[self.arg_num - 1], &format, percent);
+            values.push(self.buffer().split_off(start));
         }
-        if self.fmt_arg.as_bytes().first() == Some(&b'f') {
-            let prec = self.fmt_arg.as_bytes()[1] - b'0';
-            self.write_float(arg, prec as _, percent);
+        if values.iter().all(|value| value == &values[0]) {
+            self.push_str(&values[0]);
+        } else {
+            let values = values.join("/");
+            self.push_str(&values);
         }
-        if self.fmt_arg.as_bytes().first() == Some(&b'i') {
-            self.write_int(arg, percent);
+    }
+
+    fn format_argument(
+        &mut self,
+        arg: &crate::Argument,
+        format: &crate::PlaceholderFormat,
+        percent: bool,
+    ) {
+        use crate::formattable::Formattable;
+        use crate::PlaceholderFormat;
+        match format {
+            PlaceholderFormat::Raw => self.write_raw(arg, percent),
+            PlaceholderFormat::Int => self.write_int(arg, percent),
+            PlaceholderFormat::Float(prec) => self.write_float(arg, *prec, percent),
+            PlaceholderFormat::Unknown(_) => (),
         }
-        self.fmt_arg = String::new();
-        self.state = State::Literal;
     }
 
     /// 将 Unity XML 格式转为 MediaWiki 需要的格式
@@ -332,69 +494,83 @@ impl<Data: crate::data::GameData> Formatter<'_, Data> {
         if !std::matches!(self.state, State::UnityTagKey(_) | State::UnityTagVal(_, _)) {
             return;
         }
+        // <u> 在纯文本中同样需要识别名词，由 write_underline 处理
         if self.syntax.is_raw()
-            && ["u", "i", "color", "align", "size"].contains(&tag.strip_prefix('/').unwrap_or(&tag))
+            && ["i", "color", "align", "size"].contains(&tag.strip_prefix('/').unwrap_or(&tag))
         {
             self.state = State::Literal;
             return;
         }
         match tag.as_str() {
-            "u" => self.underlining = true,
-            "/u" => match self.syntax {
-                Syntax::Raw => unreachable!(),
-                Syntax::MediaWiki => {
-                    if self.data.has_extra_effect_config(&self.underline) {
-                        self.result.push_str("{{效果说明|");
-                        self.result.push_str(&self.underline);
-                        self.result.push_str("}}");
-                    } else {
-                        self.result.push_str("<u>");
-                        self.result.push_str(&self.underline);
-                        self.result.push_str("</u>");
-                    }
-                    self.underlining = false;
-                    self.underline = String::new();
+            "b" | "i" | "s" if std::matches!(self.syntax, Syntax::AnsiSequence) => {
+                let (open, _) = ansi_style(&tag);
+                self.styles.push(open);
+                self.push_str(open);
+            }
+            "/b" | "/i" | "/s" if std::matches!(self.syntax, Syntax::AnsiSequence) => {
+                let (open, close) = ansi_style(&tag[1..]);
+                if let Some(index) = self.styles.iter().rposition(|style| *style == open) {
+                    self.styles.remove(index);
                 }
-                Syntax::AnsiSequence => {
-                    self.result.push_str("\x1B[4m");
-                    self.result.push_str(&self.underline);
-                    self.result.push_str("\x1B[24m");
-                    self.underlining = false;
-                    self.underline = String::new();
+                // 重置序列会把同类样式一起关掉，比如 <b><b></b></b> 中内层的 </b>
+                self.push_str(close);
+                if self.styles.contains(&open) {
+                    self.push_str(open);
                 }
-            },
+            }
+            "u" => self.underlining = true,
+            "/u" => {
+                let underline = std::mem::take(&mut self.underline);
+                self.underlining = false;
+                self.write_underline(&underline);
+            }
             "s" => self.push_str(match self.syntax {
-                Syntax::Raw => "",
-                Syntax::MediaWiki => "<s>",
-                Syntax::AnsiSequence => "\x1B[9m",
+                Syntax::Raw | Syntax::AnsiSequence => "",
+                Syntax::MediaWiki | Syntax::Html => "<s>",
+                Syntax::Markdown => "~~",
             }),
             "/s" => self.push_str(match self.syntax {
-                Syntax::Raw => "",
-                Syntax::MediaWiki => "</s>",
-                Syntax::AnsiSequence => "\x1B[29m",
+                Syntax::Raw | Syntax::AnsiSequence => "",
+                Syntax::MediaWiki | Syntax::Html => "</s>",
+                Syntax::Markdown => "~~",
             }),
             "i" => self.push_str(match self.syntax {
-                Syntax::Raw => "",
+                Syntax::Raw | Syntax::AnsiSequence => "",
                 Syntax::MediaWiki => "''",
-                Syntax::AnsiSequence => "\x1B[3m",
+                Syntax::Html => "<i>",
+                Syntax::Markdown => "*",
             }),
             "/i" => self.push_str(match self.syntax {
-                Syntax::Raw => "",
+                Syntax::Raw | Syntax::AnsiSequence => "",
                 Syntax::MediaWiki => "''",
-                Syntax::AnsiSequence => "\x1B[23m",
+                Syntax::Html => "</i>",
+                Syntax::Markdown => "*",
             }),
             "b" => self.push_str(match self.syntax {
-                Syntax::Raw => "",
+                Syntax::Raw | Syntax::AnsiSequence => "",
                 Syntax::MediaWiki => "'''",
-                Syntax::AnsiSequence => "\x1B[1m",
+                Syntax::Html => "<b>",
+                Syntax::Markdown => "**",
             }),
             "/b" => self.push_str(match self.syntax {
-                Syntax::Raw => "",
+                Syntax::Raw | Syntax::AnsiSequence => "",
                 Syntax::MediaWiki => "'''",
-                Syntax::AnsiSequence => "\x1B[m",
+                Syntax::Html => "</b>",
+                Syntax::Markdown => "**",
             }),
             "color" => match self.syntax {
-                Syntax::Raw => (),
+                Syntax::Raw | Syntax::Markdown => (),
+                Syntax::Html => {
+                    // 游戏内颜色带 alpha 通道，CSS 的 #rrggbbaa 也能直接用
+                    self.push_str("<span style=\"color: ");
+                    self.push_str(val.as_deref().unwrap_or("inherit"));
+                    self.push_str("\">");
+                }
+                Syntax::MediaWiki if self.fandom => {
+                    self.push_str("<span style=\"color: ");
+                    self.push_str(val.as_deref().unwrap_or("inherit"));
+                    self.push_str("\">");
+                }
                 Syntax::MediaWiki => {
                     self.push_str("{{颜色|");
                     let color = val.unwrap();
@@ -408,30 +584,36 @@ impl<Data: crate::data::GameData> Formatter<'_, Data> {
                 }
                 Syntax::AnsiSequence => {
                     use std::str::FromStr;
-                    if let Some(val) = val {
-                        if let Ok(color) = base::serde::Color::from_str(&val) {
-                            self.push_str("\x1B[38;2;");
-                            self.push_str(&color.0.to_string());
-                            self.push(';');
-                            self.push_str(&color.1.to_string());
-                            self.push(';');
-                            self.push_str(&color.2.to_string());
-                            self.push('m');
-                        }
-                    }
+                    let color = val
+                        .as_deref()
+                        .map(base::serde::Color::from_str)
+                        .and_then(Result::ok)
+                        .map(|color| format!("\x1B[38;2;{};{};{}m", color.0, color.1, color.2));
+                    // 解析失败也要入栈，保证和 </color> 一一对应
+                    let color = color
+                        .or_else(|| self.colors.last().cloned())
+                        .unwrap_or_else(|| String::from("\x1B[39m"));
+                    self.push_str(&color);
+                    self.colors.push(color);
+                }
+            },
+            "/color" => match self.syntax {
+                Syntax::Raw | Syntax::Markdown => (),
+                Syntax::MediaWiki if !self.fandom => self.push_str("}}"),
+                Syntax::MediaWiki | Syntax::Html => self.push_str("</span>"),
+                Syntax::AnsiSequence => {
+                    self.colors.pop();
+                    let color = self.colors.last().map(String::as_str).unwrap_or("\x1B[39m");
+                    let color = color.to_string();
+                    self.push_str(&color);
                 }
             },
-            "/color" => self.push_str(match self.syntax {
-                Syntax::Raw => "",
-                Syntax::MediaWiki => "}}",
-                Syntax::AnsiSequence => "\x1B[39m",
-            }),
             "unbreak" | "/unbreak" => (),
             "align" => {
                 // 三种 <align="center"> <align="left"> <align="right">
                 match self.syntax {
-                    Syntax::Raw => (),
-                    Syntax::MediaWiki => {
+                    Syntax::Raw | Syntax::Markdown => (),
+                    Syntax::MediaWiki | Syntax::Html => {
                         self.push_str("<p style=\"text-align: ");
                         let align = val
                             .as_deref()
@@ -442,23 +624,30 @@ impl<Data: crate::data::GameData> Formatter<'_, Data> {
                         self.push_str("\">");
                     }
                     Syntax::AnsiSequence => {
+                        use crate::terminal::Align;
                         let align = val
                             .as_deref()
                             .map(|val| val.strip_prefix('"').unwrap_or(val))
                             .map(|val| val.strip_suffix('"').unwrap_or(val))
                             .unwrap();
-                        self.push_str(match align {
-                            "left" => "",
-                            "center" => "                   ",
-                            "right" => "                                        ",
-                            _ => unreachable!(),
-                        });
+                        let align = match align {
+                            "center" => Align::Center,
+                            "right" => Align::Right,
+                            _ => Align::Left,
+                        };
+                        let start = self.buffer().len();
+                        self.align = Some((self.underlining, start, align));
                     }
                 }
             }
             "/align" => match self.syntax {
                 Syntax::Raw => (),
-                Syntax::MediaWiki => {
+                Syntax::Markdown => {
+                    // Markdown 没有对齐，只当作段落处理
+                    self.push_str("\n\n");
+                    self.omit_br_once = true;
+                }
+                Syntax::MediaWiki | Syntax::Html => {
                     self.push_str("</p>");
                     if self.newline_after_block {
                         self.need_write_newline = true;
@@ -466,11 +655,25 @@ impl<Data: crate::data::GameData> Formatter<'_, Data> {
                     self.omit_br_once = true;
                 }
                 Syntax::AnsiSequence => {
+                    if let Some((underlining, start, align)) = self.align.take() {
+                        if underlining == self.underlining && start <= self.buffer().len() {
+                            let block = self.buffer().split_off(start);
+                            if !self.buffer().is_empty() && !self.buffer().ends_with('\n') {
+                                self.push('\n');
+                            }
+                            // 不知道终端宽度时不折行，也就没法对齐，只单独成行
+                            let block = match self.layout_width() {
+                                Some(width) => crate::terminal::align(&block, align, width),
+                                None => block,
+                            };
+                            self.push_str(&block);
+                        }
+                    }
                     self.push('\n');
                     self.omit_br_once = true;
                 }
             },
-            "size" if self.syntax.is_media_wiki() => {
+            "size" if self.syntax.is_html_like() => {
                 // 两种形式
                 // 1. <size=32> <size=18px> 直接指定字号
                 // 2. <size=+2> <size=-2>   指定相对字号
@@ -496,10 +699,12 @@ impl<Data: crate::data::GameData> Formatter<'_, Data> {
                 }
                 self.push_str("em\">");
             }
-            "/size" if self.syntax.is_media_wiki() => self.push_str("</span>"),
+            "/size" if self.syntax.is_html_like() => self.push_str("</span>"),
+            // 终端和 Markdown 都没法调整字号
+            "size" | "/size" => (),
             _ => {
                 // 不认识的标签原样填回去
-                if self.syntax.is_media_wiki() {
+                if self.syntax.is_html_like() {
                     self.push_str("&lt;");
                     self.push_str(&tag.replace('\u{00A0}', "&nbsp;"));
                     if let Some(val) = val {
@@ -530,9 +735,11 @@ impl<Data: crate::data::GameData> Formatter<'_, Data> {
             "F" => {
                 if !self.m_content.is_empty() {
                     self.push_str(&val.unwrap());
-                    self.push('/');
                     let m_content = std::mem::take(&mut self.m_content);
-                    self.push_str(&m_content);
+                    if !self.normal_form {
+                        self.push('/');
+                        self.push_str(&m_content);
+                    }
                 } else {
                     self.f_content = val.unwrap();
                 }
@@ -541,12 +748,45 @@ impl<Data: crate::data::GameData> Formatter<'_, Data> {
                 if !self.f_content.is_empty() {
                     let f_content = std::mem::take(&mut self.f_content);
                     self.push_str(&f_content);
-                    self.push('/');
-                    self.push_str(&val.unwrap());
+                    if !self.normal_form {
+                        self.push('/');
+                        self.push_str(&val.unwrap());
+                    }
                 } else {
                     self.m_content = val.unwrap();
                 }
             }
+            "RUBY_B" if self.normal_form => (),
+            "RUBY_E" if self.normal_form => (),
+            "RUBY_B" if std::matches!(self.syntax, Syntax::Html) => {
+                self.push_str("<ruby>");
+                self.ruby = val.unwrap();
+            }
+            "RUBY_E" if std::matches!(self.syntax, Syntax::Html) => {
+                self.push_str("<rt>");
+                let ruby = std::mem::take(&mut self.ruby);
+                self.push_str(&html_escape(&ruby));
+                self.push_str("</rt></ruby>");
+            }
+            "RUBY_B" if std::matches!(self.syntax, Syntax::Markdown) => {
+                self.ruby = val.unwrap();
+            }
+            "RUBY_E" if std::matches!(self.syntax, Syntax::Markdown) => {
+                self.push('（');
+                let ruby = std::mem::take(&mut self.ruby);
+                self.push_str(&ruby);
+                self.push('）');
+            }
+            "RUBY_B" if self.fandom => {
+                self.push_str("<ruby>");
+                self.ruby = val.unwrap();
+            }
+            "RUBY_E" if self.fandom => {
+                self.push_str("<rp>(</rp><rt>");
+                let ruby = std::mem::take(&mut self.ruby);
+                self.push_str(&ruby);
+                self.push_str("</rt><rp>)</rp></ruby>");
+            }
             "RUBY_B" => {
                 self.push_str("{{注音|");
                 self.ruby = val.unwrap();
@@ -580,10 +820,168 @@ impl<Data: crate::data::GameData> Formatter<'_, Data> {
         self.state = State::Literal;
     }
 
+    /// 写入 <u></u> 中的内容，名词（ExtraEffectConfig）按格式链接或编号
+    fn write_underline(&mut self, underline: &str) {
+        if !self.data.has_extra_effect_config(underline) {
+            self.result.push_str(match self.syntax {
+                Syntax::Raw => "",
+                Syntax::AnsiSequence => "\x1B[4m",
+                Syntax::MediaWiki | Syntax::Html | Syntax::Markdown => "<u>",
+            });
+            self.result.push_str(underline);
+            self.result.push_str(match self.syntax {
+                Syntax::Raw => "",
+                Syntax::AnsiSequence => "\x1B[24m",
+                Syntax::MediaWiki | Syntax::Html | Syntax::Markdown => "</u>",
+            });
+            return;
+        }
+        let index = match self.terms.iter().position(|term| term == underline) {
+            Some(index) => index + 1,
+            None if self.analyzing => self.terms.len() + 1,
+            None => {
+                self.terms.push(underline.to_string());
+                self.terms.len()
+            }
+        };
+        if !self.analyzing && !self.normal_form {
+            self.collect_term(underline);
+        }
+        match self.syntax {
+            Syntax::Raw => self.result.push_str(underline),
+            Syntax::MediaWiki if self.fandom => {
+                // 说明放在悬浮提示中，| 会截断模板参数，需要转义
+                let desc = self.data.extra_effect_desc(underline).unwrap_or_default();
+                self.result.push_str("<abbr title=\"");
+                self.result
+                    .push_str(&html_escape(&desc).replace('|', "&#x7c;"));
+                self.result.push_str("\"><u>");
+                self.result.push_str(underline);
+                self.result.push_str("</u></abbr>");
+            }
+            Syntax::MediaWiki => {
+                self.result.push_str("{{效果说明|");
+                self.result.push_str(underline);
+                self.result.push_str("}}");
+            }
+            Syntax::AnsiSequence => {
+                self.result.push_str("\x1B[4m");
+                self.result.push_str(underline);
+                self.result.push_str("\x1B[24m[");
+                self.result.push_str(&index.to_string());
+                self.result.push(']');
+            }
+            Syntax::Html => {
+                let desc = self.data.extra_effect_desc(underline).unwrap_or_default();
+                self.result.push_str("<abbr title=\"");
+                self.result.push_str(&html_escape(&desc));
+                self.result.push_str("\"><u>");
+                self.result.push_str(underline);
+                self.result.push_str("</u></abbr>");
+            }
+            Syntax::Markdown => {
+                self.result.push_str(underline);
+                self.result.push_str("[^");
+                self.result.push_str(&index.to_string());
+                self.result.push(']');
+            }
+        }
+    }
+
+    fn collect_term(&self, term: &str) {
+        let is_new = COLLECTED_TERMS.with_borrow(|terms| {
+            (terms.as_ref()).is_some_and(|terms| terms.iter().all(|(name, _)| name != term))
+        });
+        if !is_new {
+            return;
+        }
+        // 说明本身也要格式化，放在借用之外
+        let desc = self.data.extra_effect_desc(term).unwrap_or_default();
+        COLLECTED_TERMS.with_borrow_mut(|terms| {
+            if let Some(terms) = terms {
+                terms.push((term.to_string(), desc));
+            }
+        });
+    }
+
+    /// 到目前为止所有 format 中出现过的名词，按首次出现的顺序排列
+    pub fn terms(&self) -> &[String] {
+        &self.terms
+    }
+
+    /// 名词解释附录，列出 terms 中所有名词及其说明
+    /// Markdown 输出为脚注定义，终端输出为带编号的列表，和正文中的编号对应
+    pub fn glossary(&self) -> String {
+        let mut glossary = String::new();
+        if self.terms.is_empty() {
+            return glossary;
+        }
+        if std::matches!(self.syntax, Syntax::Html) {
+            glossary.push_str("<dl>\n");
+        }
+        for (index, term) in self.terms.iter().enumerate() {
+            let index = (index + 1).to_string();
+            let desc = self.data.extra_effect_desc(term).unwrap_or_default();
+            match self.syntax {
+                Syntax::Raw => {
+                    glossary.push_str(term);
+                    glossary.push('：');
+                    glossary.push_str(&desc);
+                }
+                Syntax::MediaWiki if self.fandom => {
+                    glossary.push_str("* '''");
+                    glossary.push_str(term);
+                    glossary.push_str("''': ");
+                    glossary.push_str(&desc);
+                }
+                Syntax::MediaWiki => {
+                    glossary.push_str("* {{效果说明|");
+                    glossary.push_str(term);
+                    glossary.push_str("}}：");
+                    glossary.push_str(&desc);
+                }
+                Syntax::AnsiSequence => {
+                    glossary.push('[');
+                    glossary.push_str(&index);
+                    glossary.push_str("] \x1B[4m");
+                    glossary.push_str(term);
+                    glossary.push_str("\x1B[24m：");
+                    glossary.push_str(&desc);
+                }
+                Syntax::Html => {
+                    glossary.push_str("<dt>");
+                    glossary.push_str(&html_escape(term));
+                    glossary.push_str("</dt><dd>");
+                    glossary.push_str(&html_escape(&desc));
+                    glossary.push_str("</dd>");
+                }
+                Syntax::Markdown => {
+                    glossary.push_str("[^");
+                    glossary.push_str(&index);
+                    glossary.push_str("]: **");
+                    glossary.push_str(term);
+                    glossary.push_str("**：");
+                    glossary.push_str(&desc);
+                }
+            }
+            glossary.push('\n');
+        }
+        if std::matches!(self.syntax, Syntax::Html) {
+            glossary.push_str("</dl>");
+        } else {
+            glossary.pop();
+        }
+        glossary
+    }
+
     pub fn flush(&mut self) {
         if self.underlining {
-            self.push_str("<u>");
+            // 没有闭合的 <u> 原样输出，先退出 underlining 状态才能写入 result
+            self.underlining = false;
             let underline = std::mem::take(&mut self.underline);
+            if !self.syntax.is_raw() {
+                self.push_str("<u>");
+            }
             self.push_str(&underline);
         }
         if let State::UnityTagKey(tag) = &mut self.state {
@@ -621,15 +1019,85 @@ impl<Data: crate::data::GameData> Formatter<'_, Data> {
             self.need_write_newline = false;
         }
         self.omit_br_once = false;
+        self.align = None;
+        self.colors.clear();
+        self.styles.clear();
         self.state = State::Literal;
     }
 
     pub fn format(&mut self, format: &str, arguments: &[crate::Argument]) -> String {
+        self.format_levels(format, &[arguments])
+    }
+
+    /// 同一段描述在不同等级下参数不同（如 MazeBuff 的各个等级）
+    /// 各等级相同的参数只输出一次，不同的参数输出为 10%/12%/14% 的形式
+    pub fn format_levels(&mut self, format: &str, levels: &[&[crate::Argument]]) -> String {
+        self.placeholders.clear();
         for char in format.chars() {
-            self.feed(char, arguments);
+            self.feed(char, levels);
         }
-        self.format_single(arguments);
+        self.format_single(levels);
         self.flush();
-        std::mem::take(&mut self.result)
+        let result = std::mem::take(&mut self.result);
+        if self.normal_form {
+            return normalize_whitespace(&result);
+        }
+        if let Syntax::AnsiSequence = self.syntax {
+            if let Some(width) = self.layout_width() {
+                return crate::terminal::wrap(&result, width);
+            }
+        }
+        result
+    }
+
+    /// 静态分析模板中用到的参数占位符，按出现顺序返回
+    /// 标签（如 <color=#e47d00>）和变量（如 {F#她}）中的 # 不算占位符
+    pub fn placeholders(&mut self, format: &str) -> Vec<crate::Placeholder> {
+        self.analyzing = true;
+        self.format_levels(format, &[]);
+        self.analyzing = false;
+        std::mem::take(&mut self.placeholders)
+    }
+
+    /// 检查模板中的占位符和参数列表是否匹配
+    pub fn validate(&mut self, format: &str, arguments: &[crate::Argument]) -> Vec<crate::Issue> {
+        let placeholders = self.placeholders(format);
+        crate::placeholder::validate(&placeholders, arguments)
     }
 }
+
+/// 终端输出中 b、i、s 标签的开始和重置序列
+fn ansi_style(tag: &str) -> (&'static str, &'static str) {
+    match tag {
+        "b" => ("\x1B[1m", "\x1B[22m"),
+        "i" => ("\x1B[3m", "\x1B[23m"),
+        _ => ("\x1B[9m", "\x1B[29m"),
+    }
+}
+
+/// 合并连续空白，去掉首尾空白，CJK 字符（包括全角标点）之间的空白直接去掉
+fn normalize_whitespace(text: &str) -> String {
+    use unicode_width::UnicodeWidthChar;
+    let is_wide = |char: char| char.width() == Some(2);
+    let mut result = String::with_capacity(text.len());
+    let mut pending_space = false;
+    for char in text.chars() {
+        if char.is_whitespace() {
+            pending_space = !result.is_empty();
+            continue;
+        }
+        if pending_space && !(result.ends_with(is_wide) && is_wide(char)) {
+            result.push(' ');
+        }
+        pending_space = false;
+        result.push(char);
+    }
+    result
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
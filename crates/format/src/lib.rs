@@ -2,10 +2,13 @@ mod argument;
 mod data;
 mod formattable;
 mod formatter;
+mod placeholder;
+mod terminal;
 
 pub use argument::Argument;
 pub use data::GameData;
-pub use formatter::Formatter;
+pub use formatter::{collect_terms, Formatter};
+pub use placeholder::{Issue, Placeholder, PlaceholderFormat};
 
 #[cfg(test)]
 mod test {
@@ -108,4 +111,294 @@ mod test {
             "<br />\n<p style=\"text-align: right\">换行后居右</p>\n"
         );
     }
+
+    #[test]
+    fn level_progression() {
+        let mut f = Formatter::new(&Data);
+        let levels: [&[_]; 3] = [
+            &[Floating(0.1), Unsigned(2)],
+            &[Floating(0.12), Unsigned(2)],
+            &[Floating(0.14), Unsigned(2)],
+        ];
+        assert_eq!(
+            f.format_levels("伤害提高#1[i]%，持续#2[i]回合", &levels),
+            "伤害提高10%/12%/14%，持续2回合"
+        );
+        assert_eq!(f.format_levels("#3[i]", &levels), "#3[i]");
+        assert_eq!(f.format_levels("#1[i]%", &[]), "#1[i]%");
+    }
+
+    #[test]
+    fn placeholders() {
+        use crate::{Placeholder, PlaceholderFormat};
+        let mut f = Formatter::new(&Data);
+        assert_eq!(
+            f.placeholders("<color=#f29e38ff>#1[f1]%</color>，#2，{F#她}#3[i]"),
+            [
+                Placeholder {
+                    index: 1,
+                    format: PlaceholderFormat::Float(1),
+                    percent: true
+                },
+                Placeholder {
+                    index: 2,
+                    format: PlaceholderFormat::Raw,
+                    percent: false
+                },
+                Placeholder {
+                    index: 3,
+                    format: PlaceholderFormat::Int,
+                    percent: false
+                },
+            ]
+        );
+        // 结尾单独的 # 不是占位符
+        assert_eq!(
+            f.placeholders("#1[i]%，结尾#"),
+            [Placeholder {
+                index: 1,
+                format: PlaceholderFormat::Int,
+                percent: true
+            }]
+        );
+        assert_eq!(f.format("#1[i]%，结尾#", &[Floating(0.5)]), "50%，结尾#");
+        // 多位数的精度
+        assert_eq!(
+            f.placeholders("#1[f12]，#2[f]"),
+            [
+                Placeholder {
+                    index: 1,
+                    format: PlaceholderFormat::Float(12),
+                    percent: false
+                },
+                Placeholder {
+                    index: 2,
+                    format: PlaceholderFormat::Unknown(std::string::String::from("f")),
+                    percent: false
+                },
+            ]
+        );
+        assert_eq!(
+            f.format("#1[f10]，#1[f]", &[Floating(0.5)]),
+            "0.5000000000，"
+        );
+    }
+
+    #[test]
+    fn validate() {
+        use crate::{Issue, Placeholder, PlaceholderFormat};
+        let mut f = Formatter::new(&Data);
+        assert_eq!(
+            f.validate("#1[i]%，#2[f1]", &[Floating(0.15), Floating(0.5)]),
+            []
+        );
+        let int = |index, percent| Placeholder {
+            index,
+            format: PlaceholderFormat::Int,
+            percent,
+        };
+        assert_eq!(
+            f.validate(
+                "#1[i]%，#2[i]，#4[i]",
+                &[Floating(0.475), Floating(2.), Signed(3)]
+            ),
+            [
+                Issue::NotInteger(int(1, true), 0.475),
+                Issue::OutOfRange(int(4, false)),
+                Issue::Unused(3),
+            ]
+        );
+        assert_eq!(f.validate("#1[i]%，结尾#", &[Floating(0.5)]), []);
+        assert_eq!(
+            f.validate("#1[i]", &[String("开拓者")]),
+            [Issue::NotNumeric(int(1, false))]
+        );
+    }
+
+    #[test]
+    fn terminal_layout() {
+        let mut f = Formatter::new(&Data)
+            .ansi_sequence_syntax(true)
+            .terminal_width(10);
+        assert_eq!(f.format("一二三四五六七", &[]), "一二三四五\n六七");
+        // 标点不出现在行首
+        assert_eq!(f.format("一二三四五，六", &[]), "一二三四\n五，六");
+        assert_eq!(f.format("hello world", &[]), "hello\nworld");
+        assert_eq!(
+            f.format(r#"<align="center">居中</align>"#, &[]),
+            "   居中\n"
+        );
+        assert_eq!(
+            f.format(r#"<align="right">居右</align>"#, &[]),
+            "      居右\n"
+        );
+        assert_eq!(
+            f.format("<color=#ff0000>红<color=#00ff00>绿</color>红</color>", &[]),
+            "\x1B[38;2;255;0;0m红\x1B[38;2;0;255;0m绿\x1B[38;2;255;0;0m红\x1B[39m"
+        );
+        // 内层闭合后恢复外层的同类样式，其他样式不受影响
+        assert_eq!(
+            f.format("<b>粗<i>斜<b>粗</b>斜</i>粗</b>", &[]),
+            "\x1B[1m粗\x1B[3m斜\x1B[1m粗\x1B[22m\x1B[1m斜\x1B[23m粗\x1B[22m"
+        );
+    }
+
+    #[test]
+    fn normal_form() {
+        let mut f = Formatter::new(&Data).normal_form(true);
+        assert_eq!(
+            f.format(
+                "<color=#e47d00ff><unbreak>#1[i]%</unbreak></color>的\u{00A0}<u>伤害</u>\\n下一行",
+                &[Floating(0.5)]
+            ),
+            "50%的伤害下一行"
+        );
+        assert_eq!(f.format("{F#她}{M#他}和{M#他}{F#她}", &[]), "她和她");
+        assert_eq!(f.format("{RUBY_B#丰饶星神}药师{RUBY_E}", &[]), "药师");
+        assert_eq!(
+            f.format("  Grand\u{00A0}\u{00A0}Melodie  ", &[]),
+            "Grand Melodie"
+        );
+        // 和其他输出格式的调用顺序无关
+        let text = "<i>斜体</i>{F#她}{M#他}";
+        let mut before = Formatter::new(&Data)
+            .normal_form(true)
+            .media_wiki_syntax(true);
+        let mut after = Formatter::new(&Data)
+            .media_wiki_syntax(true)
+            .normal_form(true);
+        assert_eq!(before.format(text, &[]), "斜体她");
+        assert_eq!(after.format(text, &[]), "斜体她");
+        let mut f = Formatter::new(&Data)
+            .html_syntax(true)
+            .normal_form(true)
+            .normal_form(false);
+        assert_eq!(f.format(text, &[]), "<i>斜体</i>她/他");
+    }
+
+    #[test]
+    fn glossary() {
+        struct Data;
+        impl crate::data::GameData for Data {
+            fn default_text_join_item(&self, _: u8, _: bool, _: bool) -> std::string::String {
+                std::string::String::new()
+            }
+            fn has_extra_effect_config(&self, name: &str) -> bool {
+                ["反震", "裂伤"].contains(&name)
+            }
+            fn extra_effect_desc(&self, name: &str) -> Option<std::string::String> {
+                self.has_extra_effect_config(name)
+                    .then(|| std::string::String::from(name) + "的说明")
+            }
+        }
+        let text = "造成<u>裂伤</u>和<u>反震</u>，再次<u>裂伤</u>，<u>普通</u>";
+        let mut f = Formatter::new(&Data).markdown_syntax(true);
+        assert_eq!(
+            f.format(text, &[]),
+            "造成裂伤[^1]和反震[^2]，再次裂伤[^1]，<u>普通</u>"
+        );
+        assert_eq!(f.terms(), ["裂伤", "反震"]);
+        assert_eq!(
+            f.glossary(),
+            "[^1]: **裂伤**：裂伤的说明\n[^2]: **反震**：反震的说明"
+        );
+        let mut f = Formatter::new(&Data).html_syntax(true);
+        assert_eq!(
+            f.format("<u>反震</u> & <b>粗体</b>", &[]),
+            "<abbr title=\"反震的说明\"><u>反震</u></abbr> &amp; <b>粗体</b>"
+        );
+        assert_eq!(
+            f.glossary(),
+            "<dl>\n<dt>反震</dt><dd>反震的说明</dd>\n</dl>"
+        );
+        let mut f = Formatter::new(&Data)
+            .ansi_sequence_syntax(true)
+            .terminal_width(80);
+        assert_eq!(f.format("<u>反震</u>", &[]), "\x1B[4m反震\x1B[24m[1]");
+        assert_eq!(f.glossary(), "[1] \x1B[4m反震\x1B[24m：反震的说明");
+        // 分析占位符不计入名词
+        assert!(f.validate("<u>裂伤</u>#1[i]", &[Floating(1.)]).is_empty());
+        assert_eq!(f.placeholders("<u>裂伤</u>").len(), 0);
+        assert_eq!(f.terms(), ["反震"]);
+
+        // 纯文本输出同样记录名词
+        let mut f = Formatter::new(&Data);
+        assert_eq!(f.format(text, &[]), "造成裂伤和反震，再次裂伤，普通");
+        assert_eq!(f.terms(), ["裂伤", "反震"]);
+        assert_eq!(f.glossary(), "裂伤：裂伤的说明\n反震：反震的说明");
+        assert_eq!(f.format("没闭合的<u>下划线", &[]), "没闭合的下划线");
+    }
+
+    #[test]
+    fn collect_terms() {
+        struct Data;
+        impl crate::data::GameData for Data {
+            fn default_text_join_item(&self, _: u8, _: bool, _: bool) -> std::string::String {
+                std::string::String::new()
+            }
+            fn has_extra_effect_config(&self, name: &str) -> bool {
+                ["反震", "裂伤"].contains(&name)
+            }
+            fn extra_effect_desc(&self, name: &str) -> Option<std::string::String> {
+                Some(std::string::String::from(name) + "的说明")
+            }
+        }
+        let (text, terms) = crate::collect_terms(|| {
+            let mut f = Formatter::new(&Data).media_wiki_syntax(true);
+            let mut text = f.format("<u>裂伤</u>", &[]);
+            // 另一个 Formatter 的名词也会被收集，分析和规范纯文本不计入
+            let mut f = Formatter::new(&Data);
+            text += &f.format("<u>反震</u><u>裂伤</u>", &[]);
+            f.validate("<u>普通</u>", &[]);
+            Formatter::new(&Data)
+                .normal_form(true)
+                .format("<u>普通</u>", &[]);
+            text
+        });
+        assert_eq!(text, "{{效果说明|裂伤}}反震裂伤");
+        let terms = terms
+            .iter()
+            .map(|(name, desc)| (name.as_str(), desc.as_str()));
+        assert_eq!(
+            terms.collect::<Vec<_>>(),
+            [("裂伤", "裂伤的说明"), ("反震", "反震的说明")]
+        );
+        // 不在收集时不记录
+        Formatter::new(&Data).format("<u>裂伤</u>", &[]);
+        assert!(crate::collect_terms(|| ()).1.is_empty());
+    }
+
+    #[test]
+    fn fandom_syntax() {
+        struct Data;
+        impl crate::data::GameData for Data {
+            fn default_text_join_item(&self, _: u8, _: bool, _: bool) -> std::string::String {
+                std::string::String::new()
+            }
+            fn has_extra_effect_config(&self, name: &str) -> bool {
+                name == "Bleed"
+            }
+            fn extra_effect_desc(&self, _: &str) -> Option<std::string::String> {
+                Some(std::string::String::from("Loses HP | each turn"))
+            }
+        }
+        let mut f = Formatter::new(&Data).fandom_syntax(true);
+        assert_eq!(
+            f.format(
+                "Deals <color=#f29e38ff>#1[i]%</color> DMG",
+                &[Floating(0.5)]
+            ),
+            "Deals <span style=\"color: #f29e38ff\">50%</span> DMG"
+        );
+        assert_eq!(
+            f.format("{RUBY_B#Aeon}<i>Lan</i>{RUBY_E}", &[]),
+            "<ruby>''Lan''<rp>(</rp><rt>Aeon</rt><rp>)</rp></ruby>"
+        );
+        assert_eq!(
+            f.format("Inflicts <u>Bleed</u>, a|b=c", &[]),
+            "Inflicts <abbr title=\"Loses HP &#x7c; each turn\"><u>Bleed</u></abbr>, \
+             a&#x7c;b{{=}}c"
+        );
+        assert_eq!(f.glossary(), "* '''Bleed''': Loses HP | each turn");
+    }
 }
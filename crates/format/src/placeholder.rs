@@ -0,0 +1,127 @@
+/// 占位符的格式化方式，即 #1 #1[i] #1[f1] 中方括号内的部分
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PlaceholderFormat {
+    /// #1 形式，数字会被四舍五入到整数
+    Raw,
+    /// #1[i] 形式
+    Int,
+    /// #1[f1] 形式，保留若干位小数
+    Float(u32),
+    /// 不认识的格式，格式化时什么都不会输出
+    Unknown(String),
+}
+
+impl PlaceholderFormat {
+    pub(crate) fn parse(fmt_arg: &str) -> Self {
+        let prec = (fmt_arg.strip_prefix('f'))
+            .filter(|prec| !prec.is_empty() && prec.bytes().all(|char| char.is_ascii_digit()))
+            .and_then(|prec| prec.parse().ok());
+        match (fmt_arg, prec) {
+            ("", _) => Self::Raw,
+            ("i", _) => Self::Int,
+            (_, Some(prec)) => Self::Float(prec),
+            _ => Self::Unknown(fmt_arg.to_string()),
+        }
+    }
+}
+
+/// 描述模板中的一个参数占位符，如 #2[f1]%
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Placeholder {
+    /// 参数下标，从 1 开始
+    pub index: usize,
+    pub format: PlaceholderFormat,
+    /// 是否以百分数形式输出
+    pub percent: bool,
+}
+
+impl std::fmt::Display for Placeholder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.index)?;
+        match &self.format {
+            PlaceholderFormat::Raw => (),
+            PlaceholderFormat::Int => f.write_str("[i]")?,
+            PlaceholderFormat::Float(prec) => write!(f, "[f{prec}]")?,
+            PlaceholderFormat::Unknown(fmt_arg) => write!(f, "[{fmt_arg}]")?,
+        }
+        if self.percent {
+            f.write_str("%")?;
+        }
+        Ok(())
+    }
+}
+
+/// 模板与参数列表对不上的地方
+#[derive(Clone, Debug, PartialEq)]
+pub enum Issue {
+    /// 占位符下标超出参数列表，格式化时会原样输出
+    OutOfRange(Placeholder),
+    /// 参数列表中没有被任何占位符用到的参数，下标从 1 开始
+    Unused(usize),
+    /// 以整数形式输出的参数实际上有小数部分，会被四舍五入
+    NotInteger(Placeholder, f64),
+    /// 以数字形式输出的参数实际上是字符串
+    NotNumeric(Placeholder),
+    /// 不认识的格式
+    UnknownFormat(Placeholder),
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfRange(placeholder) => write!(f, "{placeholder} 超出参数列表范围"),
+            Self::Unused(index) => write!(f, "参数 {index} 未被使用"),
+            Self::NotInteger(placeholder, value) => {
+                write!(f, "{placeholder} 以整数输出，但是参数值为 {value}")
+            }
+            Self::NotNumeric(placeholder) => {
+                write!(f, "{placeholder} 以数字输出，但是参数为字符串")
+            }
+            Self::UnknownFormat(placeholder) => write!(f, "{placeholder} 格式未知"),
+        }
+    }
+}
+
+/// 检查模板中的占位符和参数列表是否匹配
+pub(crate) fn validate(placeholders: &[Placeholder], arguments: &[crate::Argument]) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut used = vec![false; arguments.len()];
+    for placeholder in placeholders {
+        if placeholder.index == 0 || placeholder.index > arguments.len() {
+            issues.push(Issue::OutOfRange(placeholder.clone()));
+            continue;
+        }
+        used[placeholder.index - 1] = true;
+        if let PlaceholderFormat::Unknown(_) = placeholder.format {
+            issues.push(Issue::UnknownFormat(placeholder.clone()));
+            continue;
+        }
+        let value = match arguments[placeholder.index - 1] {
+            crate::Argument::String(_) => {
+                issues.push(Issue::NotNumeric(placeholder.clone()));
+                continue;
+            }
+            crate::Argument::Signed(_) | crate::Argument::Unsigned(_) => continue,
+            crate::Argument::Floating(value) => value,
+        };
+        if let PlaceholderFormat::Float(_) = placeholder.format {
+            continue;
+        }
+        let scaled = if placeholder.percent {
+            value * 100.
+        } else {
+            value
+        };
+        // 数据中大多是 f32 转过来的，直接判断是否为整数会有误差
+        if (scaled - scaled.round()).abs() > 1e-4 * scaled.abs().max(1.) {
+            issues.push(Issue::NotInteger(placeholder.clone(), value));
+        }
+    }
+    issues.extend(
+        used.into_iter()
+            .enumerate()
+            .filter(|(_, used)| !used)
+            .map(|(index, _)| Issue::Unused(index + 1)),
+    );
+    issues
+}
@@ -0,0 +1,185 @@
+//! 终端排版：按显示宽度计算 CJK 字符、按终端宽度折行、居中和居右对齐
+//! 所有函数都会跳过 ANSI 转义序列，转义序列不占宽度
+
+use unicode_width::UnicodeWidthChar;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// 获取终端宽度，不是终端（比如重定向到文件）时返回 None
+pub(crate) fn detect_width() -> Option<usize> {
+    terminal_size::terminal_size().map(|(width, _)| width.0 as usize)
+}
+
+/// 行首禁则：这些标点不应该出现在行首
+fn is_closing_punctuation(char: char) -> bool {
+    "，。、；：？！）」』》〉】〕”’…—,.;:?!)]}".contains(char)
+}
+
+#[derive(Debug)]
+enum Unit<'a> {
+    /// ANSI 转义序列，不占宽度
+    Escape(&'a str),
+    /// 连续的空白
+    Space(&'a str),
+    /// 连续的 ASCII 单词，不在中间折断
+    Word(&'a str),
+    /// 单个非 ASCII 字符，CJK 字符可以在任意位置折断
+    Char(&'a str),
+}
+
+impl<'a> Unit<'a> {
+    fn as_str(&self) -> &'a str {
+        match self {
+            Self::Escape(s) | Self::Space(s) | Self::Word(s) | Self::Char(s) => s,
+        }
+    }
+
+    fn width(&self) -> usize {
+        match self {
+            Self::Escape(_) => 0,
+            Self::Space(s) | Self::Word(s) | Self::Char(s) => {
+                s.chars().map(|char| char.width().unwrap_or(0)).sum()
+            }
+        }
+    }
+}
+
+/// 将单行文本拆为不可分割的排版单元
+fn units(line: &str) -> Vec<Unit<'_>> {
+    let mut units = Vec::new();
+    let mut rest = line;
+    while let Some(char) = rest.chars().next() {
+        let len = if char == '\x1B' {
+            // CSI 序列以 0x40..=0x7E 的字节结尾
+            rest.char_indices()
+                .skip(2)
+                .find(|(_, char)| ('\x40'..='\x7E').contains(char))
+                .map(|(index, char)| index + char.len_utf8())
+                .unwrap_or(rest.len())
+        } else if char.is_whitespace() {
+            rest.find(|char: char| !char.is_whitespace())
+                .unwrap_or(rest.len())
+        } else if char.is_ascii() {
+            rest.find(|char: char| !char.is_ascii() || char.is_whitespace() || char == '\x1B')
+                .unwrap_or(rest.len())
+        } else {
+            char.len_utf8()
+        };
+        let (unit, remain) = rest.split_at(len);
+        units.push(match char {
+            '\x1B' => Unit::Escape(unit),
+            _ if char.is_whitespace() => Unit::Space(unit),
+            _ if char.is_ascii() => Unit::Word(unit),
+            _ => Unit::Char(unit),
+        });
+        rest = remain;
+    }
+    units
+}
+
+/// 终端上的显示宽度，CJK 字符和全角标点占两格，ANSI 转义序列不占宽度
+pub(crate) fn width(text: &str) -> usize {
+    units(text).iter().map(Unit::width).sum()
+}
+
+/// 结束当前行，去掉行尾空白
+fn finish_line(current: &mut Vec<Unit>) -> String {
+    while let Some(Unit::Space(_)) = current.last() {
+        current.pop();
+    }
+    current.drain(..).map(|unit| unit.as_str()).collect()
+}
+
+/// 单行文本折行，CJK 字符逐字折断，ASCII 单词整体折断（单词比整行还长时才逐字折断）
+fn wrap_line(line: &str, max_width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current: Vec<Unit> = Vec::new();
+    let mut current_width = 0;
+    for unit in units(line) {
+        let unit_width = unit.width();
+        if current_width + unit_width <= max_width {
+            current_width += unit_width;
+            current.push(unit);
+            continue;
+        }
+        if let Unit::Space(_) = unit {
+            // 行尾空白直接丢掉
+            if !current.is_empty() {
+                lines.push(finish_line(&mut current));
+            }
+            current_width = 0;
+            continue;
+        }
+        let mut carried = Vec::new();
+        if unit.as_str().starts_with(is_closing_punctuation) {
+            // 将上一个字符一起带到下一行，避免标点出现在行首
+            if let Some(index) = current
+                .iter()
+                .rposition(|unit| !std::matches!(unit, Unit::Escape(_)))
+            {
+                if index != 0 {
+                    carried = current.split_off(index);
+                }
+            }
+        }
+        if !current.is_empty() {
+            lines.push(finish_line(&mut current));
+        }
+        current_width = carried.iter().map(Unit::width).sum();
+        current = carried;
+        if let Unit::Word(word) = unit {
+            if current_width + unit_width > max_width {
+                // 超长单词只能逐字折断
+                let mut rest = word;
+                while current_width + width(rest) > max_width && !rest.is_empty() {
+                    let split = max_width
+                        .saturating_sub(current_width)
+                        .max(1)
+                        .min(rest.len());
+                    let (head, tail) = rest.split_at(split);
+                    current.push(Unit::Word(head));
+                    lines.push(finish_line(&mut current));
+                    current_width = 0;
+                    rest = tail;
+                }
+                current_width = width(rest);
+                current.push(Unit::Word(rest));
+                continue;
+            }
+        }
+        current_width += unit_width;
+        current.push(unit);
+    }
+    lines.push(finish_line(&mut current));
+    lines
+}
+
+/// 多行文本逐行折行
+pub(crate) fn wrap(text: &str, max_width: usize) -> String {
+    text.split('\n')
+        .flat_map(|line| wrap_line(line, max_width))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 折行后逐行按对齐方式补齐左侧空格
+pub(crate) fn align(text: &str, align: Align, max_width: usize) -> String {
+    wrap(text, max_width)
+        .split('\n')
+        .map(|line| {
+            let line = line.trim();
+            let padding = match align {
+                Align::Left => 0,
+                Align::Center => (max_width - width(line).min(max_width)) / 2,
+                Align::Right => max_width - width(line).min(max_width),
+            };
+            " ".repeat(padding) + line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,427 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use base::{Name, Wiki};
+use reqwest::header::{RETRY_AFTER, USER_AGENT};
+use reqwest::StatusCode;
+
+use crate::model::{
+    ApiError, EditResponse, ErrorResponse, LoginResponse, PagesQuery, QueryResponse, TokensQuery,
+};
+use crate::{Error, Result};
+
+/// 编辑参数
+#[derive(Clone, Debug, Default)]
+pub struct EditOptions {
+    /// 编辑摘要
+    pub summary: String,
+    /// 标记为小编辑
+    pub minor: bool,
+    /// 标记为机器人编辑，需要账号有 bot 权限
+    pub bot: bool,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EditResult {
+    /// 内容和页面上的一致，没有产生新版本
+    NoChange,
+    /// 新建页面时 old_revid 为 0
+    Edited { old_revid: u64, new_revid: u64 },
+    /// 试运行，没有真正提交，diff 为 unified diff 格式
+    DryRun { diff: String },
+}
+
+/// 编辑所基于的页面版本，提交时用于检测编辑冲突
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BaseRevision {
+    /// 所基于版本的时间戳，页面不存在时为 None，提交时要求页面仍不存在
+    pub basetimestamp: Option<String>,
+    /// 获取页面内容时服务器的时间，用于检测期间页面被删除
+    pub starttimestamp: Option<String>,
+}
+
+/// 页面当前的内容
+#[derive(Clone, Debug, Default)]
+pub struct Current {
+    /// 页面不存在时为 None
+    pub content: Option<String>,
+    pub base: BaseRevision,
+}
+
+/// 发布结果
+#[derive(Clone, Debug)]
+pub struct Publish {
+    pub title: String,
+    pub result: EditResult,
+    /// 与页面上已有的内容合并时，两边都有值且不一致的参数
+    pub conflicts: Vec<base::wiki::Conflict>,
+}
+
+/// MediaWiki Action API 客户端
+///
+/// 所有请求都会带上 maxlag 参数（如果设置了的话），服务器延迟过高或者触发频率限制时，
+/// 按 Retry-After 等待后重试。两次编辑之间至少间隔 edit_interval
+pub struct Client {
+    client: reqwest::Client,
+    api: String,
+    user_agent: String,
+    maxlag: Option<u32>,
+    max_retries: u32,
+    retry_delay: Duration,
+    edit_interval: Duration,
+    dry_run: bool,
+
+    /// 上次提交编辑的时间，编辑期间一直持有，保证并发的编辑也按 edit_interval 间隔提交
+    last_edit: tokio::sync::Mutex<Option<Instant>>,
+    csrf_token: Mutex<Option<String>>,
+}
+
+impl Client {
+    /// api 为 api.php 的完整地址，如 https://wiki.biligame.com/sr/api.php
+    pub fn new(api: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            // 登录状态保存在 cookie 中
+            client: reqwest::Client::builder().cookie_store(true).build()?,
+            api: api.into(),
+            user_agent: concat!("sr-wiki-bot/", env!("CARGO_PKG_VERSION")).to_string(),
+            maxlag: Some(5),
+            max_retries: 5,
+            retry_delay: Duration::from_secs(5),
+            edit_interval: Duration::from_secs(2),
+            dry_run: false,
+            last_edit: tokio::sync::Mutex::new(None),
+            csrf_token: Mutex::new(None),
+        })
+    }
+
+    /// 部分站点要求 User-Agent 中带上联系方式
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// 服务器复制延迟超过 maxlag 秒时拒绝请求，None 表示不限制
+    pub fn maxlag(mut self, maxlag: Option<u32>) -> Self {
+        self.maxlag = maxlag;
+        self
+    }
+
+    /// 因 maxlag 或频率限制失败时的最大重试次数
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// 响应中没有 Retry-After 时，重试前等待的时间
+    pub fn retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// 两次编辑之间的最小间隔
+    pub fn edit_interval(mut self, edit_interval: Duration) -> Self {
+        self.edit_interval = edit_interval;
+        self
+    }
+
+    /// 试运行，编辑时只和页面上的内容做 diff，不提交
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+impl Client {
+    /// 使用机器人密码登录（特殊:机器人密码），用户名形如 User@BotName
+    pub async fn login(&self, username: &str, password: &str) -> Result<()> {
+        let tokens: QueryResponse<TokensQuery> = self
+            .request(
+                false,
+                &[("action", "query"), ("meta", "tokens"), ("type", "login")],
+            )
+            .await?;
+        let token = tokens.query.tokens.logintoken.unwrap_or_default();
+        let res: LoginResponse = self
+            .request(
+                true,
+                &[
+                    ("action", "login"),
+                    ("lgname", username),
+                    ("lgpassword", password),
+                    ("lgtoken", token.as_str()),
+                ],
+            )
+            .await?;
+        if res.login.result != "Success" {
+            return Err(Error::Login {
+                result: res.login.result,
+                reason: res.login.reason.unwrap_or_default(),
+            });
+        }
+        // 登录前获取的 token 属于匿名用户
+        *self.csrf_token.lock().unwrap() = None;
+        Ok(())
+    }
+
+    /// 获取页面当前的 wikitext，页面不存在时返回 None
+    pub async fn wikitext(&self, title: &str) -> Result<Option<String>> {
+        Ok(self.current(title).await?.content)
+    }
+
+    /// 获取页面当前的 wikitext 和版本时间戳，传给 edit_with_base 以检测编辑冲突
+    pub async fn current(&self, title: &str) -> Result<Current> {
+        let res: QueryResponse<PagesQuery> = self
+            .request(
+                false,
+                &[
+                    ("action", "query"),
+                    ("prop", "revisions"),
+                    ("rvprop", "content|timestamp"),
+                    ("rvslots", "main"),
+                    ("curtimestamp", "1"),
+                    ("titles", title),
+                ],
+            )
+            .await?;
+        let mut current = Current {
+            content: None,
+            base: BaseRevision {
+                basetimestamp: None,
+                starttimestamp: res.curtimestamp,
+            },
+        };
+        let Some(page) = res.query.pages.into_iter().next() else {
+            return Ok(current);
+        };
+        if page.invalid {
+            return Err(Error::Api {
+                code: "invalidtitle".to_string(),
+                info: page.title,
+            });
+        }
+        if let Some(revision) = page.revisions.into_iter().next() {
+            current.content = Some(revision.slots.main.content);
+            current.base.basetimestamp = revision.timestamp;
+        }
+        Ok(current)
+    }
+
+    /// 以 text 覆盖页面内容，页面不存在时新建
+    pub async fn edit(&self, title: &str, text: &str, options: &EditOptions) -> Result<EditResult> {
+        self.edit_with_base(title, text, None, options).await
+    }
+
+    /// 同 edit，base 为编辑所基于的页面版本
+    /// 在那之后页面被修改、删除或者创建时不会覆盖，返回 Error::EditConflict
+    pub async fn edit_with_base(
+        &self,
+        title: &str,
+        text: &str,
+        base: Option<&BaseRevision>,
+        options: &EditOptions,
+    ) -> Result<EditResult> {
+        if self.dry_run {
+            let current = self.wikitext(title).await?.unwrap_or_default();
+            return Ok(dry_run_result(title, &current, text));
+        }
+        let minor = if options.minor { "minor" } else { "notminor" };
+        let mut retried = false;
+        let mut last_edit = self.last_edit.lock().await;
+        loop {
+            self.wait_edit_interval(*last_edit).await;
+            let token = self.csrf_token().await?;
+            let mut params = vec![
+                ("action", "edit"),
+                ("title", title),
+                ("text", text),
+                ("summary", options.summary.as_str()),
+                (minor, "1"),
+            ];
+            if options.bot {
+                params.push(("bot", "1"));
+            }
+            if let Some(base) = base {
+                match &base.basetimestamp {
+                    Some(basetimestamp) => params.push(("basetimestamp", basetimestamp)),
+                    None => params.push(("createonly", "1")),
+                }
+                if let Some(starttimestamp) = &base.starttimestamp {
+                    params.push(("starttimestamp", starttimestamp));
+                }
+            }
+            // token 参数要放在最后，避免请求被截断时提交不完整的内容
+            params.push(("token", token.as_str()));
+            let res = self.request::<EditResponse>(true, &params).await;
+            *last_edit = Some(Instant::now());
+            let edit = match res {
+                // token 过期（比如会话失效）时重新获取一次
+                Err(Error::Api { code, .. }) if code == "badtoken" && !retried => {
+                    retried = true;
+                    *self.csrf_token.lock().unwrap() = None;
+                    continue;
+                }
+                Err(Error::Api { code, .. })
+                    if std::matches!(
+                        code.as_str(),
+                        "editconflict" | "pagedeleted" | "articleexists"
+                    ) =>
+                {
+                    return Err(Error::EditConflict {
+                        title: title.to_string(),
+                    });
+                }
+                res => res?.edit,
+            };
+            if edit.result != "Success" {
+                return Err(Error::Edit(edit.result));
+            }
+            if edit.nochange {
+                return Ok(EditResult::NoChange);
+            }
+            return Ok(EditResult::Edited {
+                old_revid: edit.oldrevid.unwrap_or_default(),
+                new_revid: edit.newrevid.unwrap_or_default(),
+            });
+        }
+    }
+
+    /// 发布到 wiki_name 对应的页面
+    /// 页面已存在时与页面上的模板合并，保留编辑手填的参数，见 base::wiki::merge
+    /// 合并期间页面被其他人修改时返回 Error::EditConflict，不会覆盖对方的修改
+    pub async fn publish<T: Wiki + Name + ?Sized>(
+        &self,
+        item: &T,
+        options: &EditOptions,
+    ) -> Result<Publish> {
+        let title = item.wiki_name().into_owned();
+        let generated = item.wiki();
+        self.publish_text(title, &generated, options).await
+    }
+
+    /// 同 publish，页面内容由调用方生成，比如用自定义的模板渲染
+    pub async fn publish_text(
+        &self,
+        title: impl Into<String>,
+        generated: &str,
+        options: &EditOptions,
+    ) -> Result<Publish> {
+        let title = title.into();
+        let current = self.current(&title).await?;
+        let existing = current.content;
+        let (text, conflicts) = match &existing {
+            Some(existing) => {
+                let merged = base::wiki::merge(existing, generated);
+                (merged.text, merged.conflicts)
+            }
+            None => (generated.to_string(), Vec::new()),
+        };
+        let result = if self.dry_run {
+            dry_run_result(&title, existing.as_deref().unwrap_or_default(), &text)
+        } else {
+            (self.edit_with_base(&title, &text, Some(&current.base), options)).await?
+        };
+        Ok(Publish {
+            title,
+            result,
+            conflicts,
+        })
+    }
+}
+
+impl Client {
+    async fn csrf_token(&self) -> Result<String> {
+        let cached = self.csrf_token.lock().unwrap().clone();
+        if let Some(token) = cached {
+            return Ok(token);
+        }
+        let res: QueryResponse<TokensQuery> = self
+            .request(false, &[("action", "query"), ("meta", "tokens")])
+            .await?;
+        let token = res.query.tokens.csrftoken.unwrap_or_default();
+        *self.csrf_token.lock().unwrap() = Some(token.clone());
+        Ok(token)
+    }
+
+    async fn wait_edit_interval(&self, last_edit: Option<Instant>) {
+        if let Some(elapsed) = last_edit.map(|last_edit| last_edit.elapsed()) {
+            if elapsed < self.edit_interval {
+                tokio::time::sleep(self.edit_interval - elapsed).await;
+            }
+        }
+    }
+
+    async fn request<T: serde::de::DeserializeOwned>(
+        &self,
+        post: bool,
+        params: &[(&str, &str)],
+    ) -> Result<T> {
+        let maxlag = self.maxlag.map(|maxlag| maxlag.to_string());
+        let mut form = vec![("format", "json"), ("formatversion", "2")];
+        if let Some(maxlag) = maxlag.as_deref() {
+            form.push(("maxlag", maxlag));
+        }
+        form.extend_from_slice(params);
+        let mut retries = 0;
+        loop {
+            let req = if post {
+                self.client.post(&self.api).form(&form)
+            } else {
+                self.client.get(&self.api).query(&form)
+            };
+            let res = req.header(USER_AGENT, &self.user_agent).send().await?;
+            let retry_after = res
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_secs);
+            let status = res.status();
+            let error = if status == StatusCode::TOO_MANY_REQUESTS
+                || status == StatusCode::SERVICE_UNAVAILABLE
+            {
+                ApiError {
+                    code: format!("http-{}", status.as_u16()),
+                    info: status.to_string(),
+                }
+            } else {
+                let bytes = res.error_for_status()?.bytes().await?;
+                match serde_json::from_slice::<ErrorResponse>(&bytes)?.error {
+                    Some(error) => error,
+                    None => return Ok(serde_json::from_slice(&bytes)?),
+                }
+            };
+            if !is_retryable(&error.code) || retries >= self.max_retries {
+                return Err(Error::Api {
+                    code: error.code,
+                    info: error.info,
+                });
+            }
+            retries += 1;
+            tokio::time::sleep(retry_after.unwrap_or(self.retry_delay)).await;
+        }
+    }
+}
+
+/// 服务器延迟过高或者触发频率限制，等待后重试即可
+fn is_retryable(code: &str) -> bool {
+    std::matches!(code, "maxlag" | "ratelimited" | "http-429" | "http-503")
+}
+
+fn dry_run_result(title: &str, current: &str, text: &str) -> EditResult {
+    match diff(title, current, text) {
+        Some(diff) => EditResult::DryRun { diff },
+        None => EditResult::NoChange,
+    }
+}
+
+/// 页面内容的 unified diff，内容一致时返回 None
+pub fn diff(title: &str, old: &str, new: &str) -> Option<String> {
+    if old == new {
+        return None;
+    }
+    let diff = similar::TextDiff::from_lines(old, new)
+        .unified_diff()
+        .header(title, title)
+        .to_string();
+    Some(diff)
+}
@@ -0,0 +1,23 @@
+pub mod client;
+pub mod model;
+
+pub use client::{BaseRevision, Client, Current, EditOptions, EditResult, Publish};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("network error")]
+    Network(#[from] reqwest::Error),
+    #[error("deserialization error")]
+    Deserialization(#[from] serde_json::Error),
+    #[error("api error: {code} {info}")]
+    Api { code: String, info: String },
+    #[error("login failed: {result} {reason}")]
+    Login { result: String, reason: String },
+    #[error("edit failed: {0}")]
+    Edit(String),
+    /// 获取页面内容之后页面被其他人修改、删除或者创建，需要重新获取后再编辑
+    #[error("edit conflict: {title}")]
+    EditConflict { title: String },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,94 @@
+//! Action API 的响应，均按 formatversion=2 解析，只保留用到的字段
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct ApiError {
+    pub code: String,
+    #[serde(default)]
+    pub info: String,
+}
+
+/// 所有响应出错时都有 error 字段，先单独解析出来
+#[derive(Clone, Debug, serde::Deserialize)]
+pub(crate) struct ErrorResponse {
+    pub error: Option<ApiError>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub(crate) struct QueryResponse<T> {
+    pub query: T,
+    /// 请求带上 curtimestamp 时服务器的当前时间
+    #[serde(default)]
+    pub curtimestamp: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub(crate) struct TokensQuery {
+    pub tokens: Tokens,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub(crate) struct Tokens {
+    pub logintoken: Option<String>,
+    pub csrftoken: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub(crate) struct LoginResponse {
+    pub login: Login,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub(crate) struct Login {
+    pub result: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub(crate) struct PagesQuery {
+    pub pages: Vec<Page>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct Page {
+    pub title: String,
+    #[serde(default)]
+    pub missing: bool,
+    #[serde(default)]
+    pub invalid: bool,
+    #[serde(default)]
+    pub revisions: Vec<Revision>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct Revision {
+    pub revid: u64,
+    /// rvprop 带上 timestamp 时才有
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    pub slots: Slots,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct Slots {
+    pub main: Slot,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct Slot {
+    pub content: String,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub(crate) struct EditResponse {
+    pub edit: Edit,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub(crate) struct Edit {
+    pub result: String,
+    #[serde(default)]
+    pub nochange: bool,
+    pub oldrevid: Option<u64>,
+    pub newrevid: Option<u64>,
+}
@@ -0,0 +1,375 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use mediawiki::{Client, EditOptions, EditResult};
+use wiremock::matchers::{body_string_contains, method, query_param, query_param_is_missing};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+struct Monster;
+
+impl base::Name for Monster {
+    fn name(&self) -> &str {
+        "虚卒·掠夺者"
+    }
+    fn wiki_name(&self) -> Cow<'_, str> {
+        Cow::Borrowed("虚卒·掠夺者")
+    }
+}
+
+impl base::Wiki for Monster {
+    fn wiki(&self) -> Cow<'static, str> {
+        Cow::Borrowed("{{敌人\n|名称=虚卒·掠夺者\n|别称=\n|韧性=20\n}}")
+    }
+}
+
+fn client(server: &MockServer) -> Client {
+    Client::new(format!("{}/api.php", server.uri()))
+        .unwrap()
+        .retry_delay(Duration::ZERO)
+        .edit_interval(Duration::ZERO)
+}
+
+fn json(body: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(body.to_owned(), "application/json")
+}
+
+async fn mock_csrf_token(server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(query_param("meta", "tokens"))
+        .and(query_param_is_missing("type"))
+        .respond_with(json(r#"{"query":{"tokens":{"csrftoken":"csrf+\\"}}}"#))
+        .mount(server)
+        .await;
+}
+
+async fn mock_page(server: &MockServer, content: Option<&str>) {
+    let page = match content {
+        Some(content) => serde_json::json!({
+            "pageid": 1,
+            "ns": 0,
+            "title": "虚卒·掠夺者",
+            "revisions": [{
+                "revid": 10,
+                "timestamp": "2024-01-01T00:00:00Z",
+                "slots": { "main": { "content": content } },
+            }],
+        }),
+        None => serde_json::json!({ "ns": 0, "title": "虚卒·掠夺者", "missing": true }),
+    };
+    let body = serde_json::json!({
+        "curtimestamp": "2024-01-02T00:00:00Z",
+        "query": { "pages": [page] },
+    });
+    Mock::given(method("GET"))
+        .and(query_param("prop", "revisions"))
+        .and(query_param("rvprop", "content|timestamp"))
+        .and(query_param("titles", "虚卒·掠夺者"))
+        .respond_with(json(&body.to_string()))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn login() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(query_param("type", "login"))
+        .and(query_param("maxlag", "5"))
+        .respond_with(json(r#"{"query":{"tokens":{"logintoken":"login+\\"}}}"#))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("action=login"))
+        .and(body_string_contains("lgname=User%40Bot"))
+        .and(body_string_contains("lgtoken=login%2B%5C"))
+        .respond_with(json(r#"{"login":{"result":"Success","lguserid":1}}"#))
+        .expect(1)
+        .mount(&server)
+        .await;
+    client(&server).login("User@Bot", "password").await.unwrap();
+}
+
+#[tokio::test]
+async fn login_failed() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(query_param("type", "login"))
+        .respond_with(json(r#"{"query":{"tokens":{"logintoken":"login+\\"}}}"#))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(json(
+            r#"{"login":{"result":"Failed","reason":"Incorrect username or password entered."}}"#,
+        ))
+        .mount(&server)
+        .await;
+    let err = client(&server)
+        .login("User@Bot", "wrong")
+        .await
+        .unwrap_err();
+    assert!(std::matches!(err, mediawiki::Error::Login { result, .. } if result == "Failed"));
+}
+
+#[tokio::test]
+async fn wikitext() {
+    let server = MockServer::start().await;
+    mock_page(&server, Some("{{敌人}}")).await;
+    let client = client(&server);
+    assert_eq!(
+        client.wikitext("虚卒·掠夺者").await.unwrap().as_deref(),
+        Some("{{敌人}}")
+    );
+
+    let server = MockServer::start().await;
+    mock_page(&server, None).await;
+    let client = self::client(&server);
+    assert_eq!(client.wikitext("虚卒·掠夺者").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn edit_retries_on_maxlag() {
+    let server = MockServer::start().await;
+    mock_csrf_token(&server).await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("action=edit"))
+        .respond_with(
+            json(r#"{"error":{"code":"maxlag","info":"Waiting for a database server: 6 seconds lagged."}}"#)
+                .insert_header("Retry-After", "0"),
+        )
+        .up_to_n_times(2)
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("action=edit"))
+        .and(body_string_contains("summary=%E6%9B%B4%E6%96%B0"))
+        .and(body_string_contains("minor=1"))
+        .and(body_string_contains("bot=1"))
+        .and(body_string_contains("token=csrf%2B%5C"))
+        .respond_with(json(
+            r#"{"edit":{"result":"Success","pageid":1,"oldrevid":10,"newrevid":11}}"#,
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let options = EditOptions {
+        summary: "更新".to_string(),
+        minor: true,
+        bot: true,
+    };
+    let result = client(&server)
+        .edit("虚卒·掠夺者", "{{敌人}}", &options)
+        .await
+        .unwrap();
+    assert_eq!(
+        result,
+        EditResult::Edited {
+            old_revid: 10,
+            new_revid: 11
+        }
+    );
+}
+
+#[tokio::test]
+async fn concurrent_edits_keep_interval() {
+    let server = MockServer::start().await;
+    mock_csrf_token(&server).await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("action=edit"))
+        .respond_with(json(
+            r#"{"edit":{"result":"Success","pageid":1,"oldrevid":10,"newrevid":11}}"#,
+        ))
+        .expect(2)
+        .mount(&server)
+        .await;
+    let client = client(&server).edit_interval(Duration::from_millis(300));
+    let options = EditOptions::default();
+    let start = std::time::Instant::now();
+    let (first, second) = tokio::join!(
+        client.edit("虚卒·掠夺者", "{{敌人}}", &options),
+        client.edit("虚卒·掠夺者", "{{敌人}}", &options),
+    );
+    first.unwrap();
+    second.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(300));
+}
+
+#[tokio::test]
+async fn badtoken_retry_keeps_interval() {
+    let server = MockServer::start().await;
+    mock_csrf_token(&server).await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("action=edit"))
+        .respond_with(json(
+            r#"{"error":{"code":"badtoken","info":"Invalid CSRF token."}}"#,
+        ))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("action=edit"))
+        .respond_with(json(
+            r#"{"edit":{"result":"Success","pageid":1,"oldrevid":10,"newrevid":11}}"#,
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = client(&server).edit_interval(Duration::from_millis(300));
+    let start = std::time::Instant::now();
+    client
+        .edit("虚卒·掠夺者", "{{敌人}}", &EditOptions::default())
+        .await
+        .unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(300));
+}
+
+#[tokio::test]
+async fn edit_gives_up_after_max_retries() {
+    let server = MockServer::start().await;
+    mock_csrf_token(&server).await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+        .expect(3)
+        .mount(&server)
+        .await;
+    let err = client(&server)
+        .max_retries(2)
+        .edit("虚卒·掠夺者", "{{敌人}}", &EditOptions::default())
+        .await
+        .unwrap_err();
+    assert!(std::matches!(err, mediawiki::Error::Api { code, .. } if code == "http-429"));
+}
+
+#[tokio::test]
+async fn publish_dry_run_merges_existing_page() {
+    let server = MockServer::start().await;
+    mock_page(
+        &server,
+        Some("{{敌人\n|名称=虚卒·掠夺者\n|别称=小兵\n|韧性=10\n}}\n[[分类:敌人]]"),
+    )
+    .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let publish = client(&server)
+        .dry_run(true)
+        .publish(&Monster, &EditOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(publish.title, "虚卒·掠夺者");
+    assert_eq!(publish.conflicts.len(), 1);
+    assert_eq!(publish.conflicts[0].key, "韧性");
+    let EditResult::DryRun { diff } = publish.result else {
+        panic!("expected dry run diff, got {:?}", publish.result);
+    };
+    assert!(diff.contains("\n-|韧性=10\n+|韧性=20\n"));
+    assert!(diff.contains("\n |别称=小兵\n"));
+}
+
+#[tokio::test]
+async fn publish_creates_missing_page() {
+    let server = MockServer::start().await;
+    mock_page(&server, None).await;
+    mock_csrf_token(&server).await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("title=%E8%99%9A%E5%8D%92"))
+        .and(body_string_contains("notminor=1"))
+        .and(body_string_contains("createonly=1"))
+        .and(body_string_contains(
+            "starttimestamp=2024-01-02T00%3A00%3A00Z",
+        ))
+        .respond_with(json(
+            r#"{"edit":{"result":"Success","pageid":1,"new":true,"oldrevid":0,"newrevid":1}}"#,
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let publish = client(&server)
+        .publish(&Monster, &EditOptions::default())
+        .await
+        .unwrap();
+    assert!(publish.conflicts.is_empty());
+    assert_eq!(
+        publish.result,
+        EditResult::Edited {
+            old_revid: 0,
+            new_revid: 1
+        }
+    );
+}
+
+#[tokio::test]
+async fn publish_sends_timestamps() {
+    let server = MockServer::start().await;
+    mock_page(&server, Some("{{敌人\n|名称=虚卒·掠夺者\n|韧性=10\n}}")).await;
+    mock_csrf_token(&server).await;
+    Mock::given(method("POST"))
+        .and(body_string_contains(
+            "basetimestamp=2024-01-01T00%3A00%3A00Z",
+        ))
+        .and(body_string_contains(
+            "starttimestamp=2024-01-02T00%3A00%3A00Z",
+        ))
+        .and(body_string_contains("text=%7B%7B%E6%95%8C%E4%BA%BA"))
+        .respond_with(json(
+            r#"{"edit":{"result":"Success","pageid":1,"oldrevid":10,"newrevid":11}}"#,
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let publish = client(&server)
+        .publish(&Monster, &EditOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        publish.result,
+        EditResult::Edited {
+            old_revid: 10,
+            new_revid: 11
+        }
+    );
+}
+
+#[tokio::test]
+async fn publish_edit_conflict() {
+    let server = MockServer::start().await;
+    mock_page(&server, Some("{{敌人\n|名称=虚卒·掠夺者\n|韧性=10\n}}")).await;
+    mock_csrf_token(&server).await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("basetimestamp="))
+        .respond_with(json(
+            r#"{"error":{"code":"editconflict","info":"Edit conflict."}}"#,
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let err = client(&server)
+        .publish(&Monster, &EditOptions::default())
+        .await
+        .unwrap_err();
+    assert!(std::matches!(err, mediawiki::Error::EditConflict { title } if title == "虚卒·掠夺者"));
+}
+
+#[tokio::test]
+async fn publish_text_uses_given_content() {
+    let server = MockServer::start().await;
+    mock_page(&server, Some("{{敌人\n|名称=虚卒·掠夺者\n|韧性=10\n}}")).await;
+    let publish = client(&server)
+        .dry_run(true)
+        .publish_text(
+            "虚卒·掠夺者",
+            "{{敌人\n|名称=虚卒·掠夺者\n|韧性=30\n}}",
+            &EditOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(publish.title, "虚卒·掠夺者");
+    let EditResult::DryRun { diff } = publish.result else {
+        panic!("expected dry run diff, got {:?}", publish.result);
+    };
+    assert!(diff.contains("\n-|韧性=10\n+|韧性=30\n"));
+}
@@ -94,6 +94,26 @@ pub struct Text {
     pub hash: NonZero<i128>,
 }
 
+impl Text {
+    /// 少数配置中的文本不是哈希值，而是字符串 key（如 MissionChapterConfig.ChapterName）
+    /// 游戏用固定的字符串哈希算法将 key 转为 TextMap 中的哈希，空 key 返回 None
+    pub fn from_key(key: &str) -> Option<Self> {
+        let mut hash1: i32 = 5381;
+        let mut hash2: i32 = hash1;
+        let chars = key.encode_utf16().collect::<Vec<_>>();
+        for pair in chars.chunks(2) {
+            hash1 = (hash1 << 5).wrapping_add(hash1) ^ pair[0] as i32;
+            if let Some(&char) = pair.get(1) {
+                hash2 = (hash2 << 5).wrapping_add(hash2) ^ char as i32;
+            }
+        }
+        let hash = hash1.wrapping_add(hash2.wrapping_mul(1566083941));
+        NonZero::new(hash as i128)
+            .filter(|_| !key.is_empty())
+            .map(|hash| Self { hash })
+    }
+}
+
 #[derive(
     Clone, Copy, Debug, Default, Eq, PartialEq, Hash, serde::Deserialize, serde::Serialize,
 )]